@@ -12,14 +12,18 @@ fn build_error_modal(ctx: &Context<Main>, e: &err::Error) -> Option<Html> {
         link.send_message(Message::Error(e));
     });
     match e {
-        err::Error::Other(msg) => {
+        err::Error::Other(msg) | err::Error::Gpu(msg) => {
+            let title = match e {
+                err::Error::Gpu(_) => "Graphics",
+                _ => "Error",
+            };
             let link = ctx.link().clone();
             let on_clear_error = Callback::from(move |_| {
                 link.send_message(Message::ClearError);
             });
             Some(html! {
                 <Modal on_close={on_clear_error}>
-                    <div style={"padding: 1em;background-color: red;width: 100%;"}>{"Error"}</div>
+                    <div style={"padding: 1em;background-color: red;width: 100%;"}>{title}</div>
                     <pre style={"padding: 1em;flex: 1;width: 100%;"}>{msg.clone()}</pre>
                 </Modal>
             })
@@ -1,34 +1,298 @@
-use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use cgmath::Point2;
 
-use painting::point::Point;
-use yew::Callback;
+use wasm_bindgen::{closure::Closure, JsCast};
+use yew::{Callback, KeyboardEvent};
 
 use crate::{
     component::{Column, Row},
-    element, err, service,
+    element::{
+        self, Edge, EdgeList, ExportArea, ExportFormat, ExportOption, Ink, InkKind, Layer,
+        PageFrame, Paper, PdfPages, Rect, Snap, Tool, Viewport, ViewportFeed, DEFAULT_LAYER_ID,
+    },
+    err, export, metrics, router, service, util,
 };
 
+/// How long the view has to stay put before it is written to the URL.
+const SAVE_VIEW_DELAY: Duration = Duration::from_millis(300);
+/// Background of exported images that keep one.
+const EXPORT_BACKGROUND: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+/// Radians the rotate buttons turn the board by.
+const ROTATION_STEP: f32 = std::f32::consts::PI / 12.0;
+/// Key showing or hiding the performance HUD, which `?hud` in the URL shows at first.
+const HUD_KEY: &str = "F2";
+const HUD_FLAG: &str = "hud";
+/// Query key holding the name shown to other users.
+const NAME_KEY: &str = "name";
+/// Colors users are told apart by.
+const PEER_COLOR_V: [&str; 6] = [
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#008080",
+];
+/// Least time between two pointer updates sent to the others, in milliseconds.
+const PUBLISH_MS: f64 = 50.0;
+/// How often idle users are looked for and held back updates sent.
+const PRESENCE_TICK: Duration = Duration::from_secs(1);
+
+fn bounds<'a>(edge_v: impl Iterator<Item = &'a Edge>) -> Option<Rect> {
+    edge_v
+        .filter_map(|edge| edge.bounds())
+        .reduce(|a, b| a.union(&b))
+}
+
+/// A PDF page of a whole board, None if it is empty.
+fn board_page(layer_v: &[Layer], edge_v: &[Edge]) -> Option<export::PdfPage> {
+    Some(export::PdfPage {
+        rect: bounds(edge_v.iter())?,
+        group_v: export::layer_group_v(edge_v, layer_v),
+    })
+}
+
+/// Download a PDF of `page_v`, as `option` lays them out.
+fn download_pdf(page_v: &[export::PdfPage], option: &ExportOption) -> err::Result<()> {
+    if page_v.is_empty() {
+        return Err(err::Error::Other(format!("nothing to export")));
+    }
+    let pdf = export::to_pdf(page_v, option.page_size, option.landscape);
+    util::download_data(&pdf, "application/pdf", "huiwen.pdf")
+}
+
 #[derive(yew::Properties, PartialEq)]
 pub struct Props {
     #[prop_or_default]
     pub on_error: Callback<err::Error>,
+    /// Where to look at first, usually taken from the URL.
+    #[prop_or_default]
+    pub view: Option<Viewport>,
+    /// Id of the board to open, None for the default one.
+    #[prop_or_default]
+    pub board: Option<String>,
 }
 
 pub enum Message {
-    Commit(Vec<Point>),
-    Refresh(Vec<Vec<Point>>),
+    Commit(Edge),
+    Erase(String),
+    Select(Vec<String>),
+    Refresh(Vec<Edge>),
     Post(bool),
     PostRefresh,
     Clear,
+    SetTool(Tool),
+    SetSnap(Snap),
+    /// Draw in a worker or not, on a new canvas.
+    SetOffscreen(bool),
+    SwitchHud,
+    /// The pointer moved over the board, to this world position.
+    Pointer(Point2<f32>),
+    Peer(service::PeerEvent),
+    /// Laser or ephemeral ink drawn here, to show the others.
+    Ink(Ink),
+    ClearInk,
+    PresenceTick,
     Error(err::Error),
     Bigger,
     Smaller,
+    ResetZoom,
+    FitAll,
+    FitSelection,
+    Rotate(f32),
+    ResetRotation,
+    SetViewport(Viewport),
+    SaveView(u32),
+    AddLayer,
+    SelectLayer(String),
+    ChangeLayer(Layer),
+    /// Layer id and how many places to move it up.
+    MoveLayer((String, isize)),
+    RefreshLayers(Vec<Layer>),
+    LayersSaved,
+    SetPaper(Paper),
+    RefreshPaper(Paper),
+    PaperSaved,
+    Export((ExportFormat, ExportOption)),
+    AddPageFrame,
+    RemovePageFrame(String),
+    RefreshPageFrames(Vec<PageFrame>),
+    PageFramesSaved,
+    Import(web_sys::File),
+    Imported(export::Document),
 }
 
 #[derive(Default)]
 pub struct HomePage {
-    edge_v: Vec<Vec<Point>>,
-    scale: u32,
+    edge_v: EdgeList,
+    selected_id_v: Vec<String>,
+    tool: Tool,
+    viewport: Viewport,
+    /// `viewport` for what follows it over the board, which moves without
+    /// the page rendering again.
+    viewport_feed: ViewportFeed,
+    /// Bumped on every view change, so only the last one is saved.
+    view_version: u32,
+    /// Edges drawn here that no pull has brought back yet.
+    committing_id_v: Vec<String>,
+    /// Edges erased here that pulls may still bring back.
+    erasing_id_v: Vec<String>,
+    /// Bottom layer first.
+    layer_v: Vec<Layer>,
+    /// The layer new edges go on.
+    layer_id: String,
+    /// Layer saves still on their way; pulls are ignored until they land.
+    saving_layer_n: u32,
+    snap: Snap,
+    /// The canvas draws in a worker, where the browser allows it.
+    offscreen: bool,
+    hud: bool,
+    /// Listens on the whole page for `HUD_KEY`.
+    on_hud_key: Option<Closure<dyn Fn(KeyboardEvent)>>,
+    paper: Paper,
+    /// Paper saves still on their way, as with layers.
+    saving_paper_n: u32,
+    page_frame_v: Vec<PageFrame>,
+    /// Page frame saves still on their way, as with layers.
+    saving_page_frame_n: u32,
+    /// This user, as the others see them.
+    me: service::Peer,
+    /// Other users on the board by id, idle ones included.
+    peer_m: HashMap<String, service::Peer>,
+    /// Ids of the users shown, by name.
+    shown_id_v: Vec<String>,
+    /// Ink of other users still showing, by user id, oldest first. The last
+    /// one may still be drawn.
+    peer_ink_m: HashMap<String, Vec<Ink>>,
+    presence: Option<service::PresenceChannel>,
+    /// When `me` was last sent, and whether it changed since.
+    published_at: f64,
+    me_dirty: bool,
+}
+
+impl HomePage {
+    fn set_viewport(&mut self, ctx: &yew::Context<Self>, viewport: Viewport) -> bool {
+        if viewport == self.viewport {
+            return false;
+        }
+        // Of the page itself, only the zoom and rotation buttons show it.
+        let shown = viewport.percent() != self.viewport.percent()
+            || viewport.degrees() != self.viewport.degrees();
+        self.viewport = viewport;
+        self.viewport_feed.set(viewport);
+        self.view_version = self.view_version.wrapping_add(1);
+        let version = self.view_version;
+        ctx.link().send_future(async move {
+            yew::platform::time::sleep(SAVE_VIEW_DELAY).await;
+            Message::SaveView(version)
+        });
+        shown
+    }
+
+    fn save_layers(&mut self, ctx: &yew::Context<Self>) {
+        self.saving_layer_n += 1;
+        let board = ctx.props().board.clone();
+        let layer_v = self.layer_v.clone();
+        let link = ctx.link().clone();
+        ctx.link().send_future(async move {
+            if let Err(e) = service::save_layer_v(board.as_deref(), layer_v).await {
+                link.send_message(Message::Error(e));
+            }
+            Message::LayersSaved
+        });
+    }
+
+    fn save_page_frames(&mut self, ctx: &yew::Context<Self>) {
+        self.saving_page_frame_n += 1;
+        let board = ctx.props().board.clone();
+        let frame_v = self.page_frame_v.clone();
+        let link = ctx.link().clone();
+        ctx.link().send_future(async move {
+            if let Err(e) = service::save_page_frame_v(board.as_deref(), frame_v).await {
+                link.send_message(Message::Error(e));
+            }
+            Message::PageFramesSaved
+        });
+    }
+
+    /// Export a PDF with a page for every page frame, or for every board.
+    fn export_pdf_pages(&self, ctx: &yew::Context<Self>, option: ExportOption) {
+        match option.pages {
+            PdfPages::PerFrame => {
+                let page_v: Vec<export::PdfPage> = self
+                    .page_frame_v
+                    .iter()
+                    .map(|frame| export::PdfPage {
+                        group_v: export::layer_group_v(
+                            self.edge_v.iter().filter(|edge| {
+                                edge.bounds().is_some_and(|b| b.intersects(&frame.rect))
+                            }),
+                            &self.layer_v,
+                        ),
+                        rect: frame.rect,
+                    })
+                    .collect();
+                if let Err(e) = download_pdf(&page_v, &option) {
+                    ctx.link().send_message(Message::Error(e));
+                }
+            }
+            PdfPages::PerBoard => {
+                let mut page_v: Vec<export::PdfPage> = board_page(&self.layer_v, &self.edge_v)
+                    .into_iter()
+                    .collect();
+                ctx.link().send_future(async move {
+                    for board in &option.board_v {
+                        let (mut layer_v, edge_v) = match service::pull_board(board).await {
+                            Ok(board) => board,
+                            Err(e) => return Message::Error(e),
+                        };
+                        if layer_v.is_empty() {
+                            layer_v.push(Layer::default());
+                        }
+                        page_v.extend(board_page(&layer_v, &edge_v));
+                    }
+                    match download_pdf(&page_v, &option) {
+                        Ok(_) => Message::Post(false),
+                        Err(e) => Message::Error(e),
+                    }
+                });
+            }
+            PdfPages::One | PdfPages::PerLayer => (),
+        }
+    }
+
+    /// Tell `metrics` how many commits and erasures wait for a pull.
+    fn note_sync(&self) {
+        let sync_n = self.committing_id_v.len() + self.erasing_id_v.len();
+        metrics::update(|m| m.sync_n = sync_n);
+    }
+
+    /// Tell the others about `me`, unless it was done within `PUBLISH_MS`;
+    /// then the next `PresenceTick` does.
+    fn publish_me(&mut self) {
+        let now = js_sys::Date::now();
+        self.me.active = now;
+        let presence = match &self.presence {
+            Some(presence) => presence,
+            None => return,
+        };
+        if now - self.published_at < PUBLISH_MS {
+            self.me_dirty = true;
+            return;
+        }
+        presence.publish(&self.me);
+        self.published_at = now;
+        self.me_dirty = false;
+    }
+
+    /// Bring `shown_id_v` in line with who is not idle, true if it changed.
+    fn update_shown(&mut self) -> bool {
+        let shown_id_v = service::shown_id_v(&self.peer_m, js_sys::Date::now());
+        if shown_id_v == self.shown_id_v {
+            return false;
+        }
+        self.shown_id_v = shown_id_v;
+        true
+    }
 }
 
 impl yew::Component for HomePage {
@@ -38,9 +302,67 @@ impl yew::Component for HomePage {
 
     fn create(ctx: &yew::Context<Self>) -> Self {
         ctx.link().send_message(Self::Message::PostRefresh);
+        let link = ctx.link().clone();
+        let on_hud_key = Closure::<dyn Fn(KeyboardEvent)>::new(move |e: KeyboardEvent| {
+            if e.key() == HUD_KEY {
+                link.send_message(Self::Message::SwitchHud);
+            }
+        });
+        let on_hud_key = web_sys::window().and_then(|window| {
+            window
+                .add_event_listener_with_callback("keydown", on_hud_key.as_ref().unchecked_ref())
+                .ok()
+                .map(|_| on_hud_key)
+        });
+        let id = element::new_id();
+        let me = service::Peer {
+            name: router::query_value(NAME_KEY).unwrap_or_else(|| format!("Guest {}", &id[..4])),
+            color: PEER_COLOR_V[id.bytes().map(usize::from).sum::<usize>() % PEER_COLOR_V.len()]
+                .to_string(),
+            tool: Tool::Pen.name().to_string(),
+            pos: None,
+            active: js_sys::Date::now(),
+            id,
+        };
+        let link = ctx.link().clone();
+        let presence =
+            match service::join_presence(ctx.props().board.as_deref(), me.clone(), move |e| {
+                link.send_message(Self::Message::Peer(e));
+            }) {
+                Ok(presence) => Some(presence),
+                Err(e) => {
+                    log::warn!("failed to join presence: {e}");
+                    None
+                }
+            };
+        ctx.link().send_message(Self::Message::PresenceTick);
         Self {
-            edge_v: Vec::new(),
-            scale: 62,
+            edge_v: EdgeList::default(),
+            selected_id_v: Vec::new(),
+            tool: Tool::Pen,
+            viewport: ctx.props().view.unwrap_or_default(),
+            viewport_feed: ViewportFeed::new(ctx.props().view.unwrap_or_default()),
+            view_version: 0,
+            committing_id_v: Vec::new(),
+            erasing_id_v: Vec::new(),
+            layer_v: vec![Layer::default()],
+            layer_id: DEFAULT_LAYER_ID.to_string(),
+            saving_layer_n: 0,
+            snap: Snap::default(),
+            offscreen: false,
+            hud: router::has_flag(HUD_FLAG),
+            on_hud_key,
+            paper: Paper::default(),
+            saving_paper_n: 0,
+            page_frame_v: Vec::new(),
+            saving_page_frame_n: 0,
+            me,
+            peer_m: HashMap::new(),
+            shown_id_v: Vec::new(),
+            peer_ink_m: HashMap::new(),
+            presence,
+            published_at: 0.0,
+            me_dirty: false,
         }
     }
 
@@ -50,6 +372,96 @@ impl yew::Component for HomePage {
             link.send_message(Self::Message::Commit(pt_v));
         });
 
+        let link = ctx.link().clone();
+        let erase = Callback::from(move |id| {
+            link.send_message(Self::Message::Erase(id));
+        });
+
+        let link = ctx.link().clone();
+        let select = Callback::from(move |id_v| {
+            link.send_message(Self::Message::Select(id_v));
+        });
+
+        let link = ctx.link().clone();
+        let on_viewport = Callback::from(move |viewport| {
+            link.send_message(Self::Message::SetViewport(viewport));
+        });
+
+        let link = ctx.link().clone();
+        let on_pointer = Callback::from(move |pos| {
+            link.send_message(Self::Message::Pointer(pos));
+        });
+
+        let link = ctx.link().clone();
+        let on_ink = Callback::from(move |ink| {
+            link.send_message(Self::Message::Ink(ink));
+        });
+
+        let link = ctx.link().clone();
+        let on_clear_ink = Callback::from(move |_| {
+            link.send_message(Self::Message::ClearInk);
+        });
+
+        let link = ctx.link().clone();
+        let on_canvas_error = Callback::from(move |e| {
+            link.send_message(Self::Message::Error(e));
+        });
+
+        let link = ctx.link().clone();
+        let pen = Callback::from(move |_| {
+            link.send_message(Self::Message::SetTool(Tool::Pen));
+        });
+
+        let link = ctx.link().clone();
+        let eraser = Callback::from(move |_| {
+            link.send_message(Self::Message::SetTool(Tool::Eraser));
+        });
+
+        let link = ctx.link().clone();
+        let selector = Callback::from(move |_| {
+            link.send_message(Self::Message::SetTool(Tool::Select));
+        });
+
+        let link = ctx.link().clone();
+        let line = Callback::from(move |_| {
+            link.send_message(Self::Message::SetTool(Tool::Line));
+        });
+
+        let link = ctx.link().clone();
+        let laser = Callback::from(move |_| {
+            link.send_message(Self::Message::SetTool(Tool::Laser));
+        });
+
+        let link = ctx.link().clone();
+        let ephemeral = Callback::from(move |_| {
+            link.send_message(Self::Message::SetTool(Tool::Ephemeral));
+        });
+
+        let snap = self.snap;
+        let link = ctx.link().clone();
+        let snap_grid = Callback::from(move |_| {
+            link.send_message(Self::Message::SetSnap(Snap {
+                grid: !snap.grid,
+                ..snap
+            }));
+        });
+
+        let link = ctx.link().clone();
+        let snap_endpoint = Callback::from(move |_| {
+            link.send_message(Self::Message::SetSnap(Snap {
+                endpoint: !snap.endpoint,
+                ..snap
+            }));
+        });
+
+        // A canvas handed to a worker stays there, so the canvas is keyed on
+        // this and switching brings a new one.
+        let offscreen = self.offscreen;
+        let link = ctx.link().clone();
+        let switch_offscreen = Callback::from(move |_| {
+            link.send_message(Self::Message::SetOffscreen(!offscreen));
+        });
+
         let link = ctx.link().clone();
         let clear = Callback::from(move |_| {
             link.send_message(Self::Message::Clear);
@@ -65,7 +477,96 @@ impl yew::Component for HomePage {
             link.send_message(Self::Message::Smaller);
         });
 
+        let link = ctx.link().clone();
+        let reset_zoom = Callback::from(move |_| {
+            link.send_message(Self::Message::ResetZoom);
+        });
+
+        let link = ctx.link().clone();
+        let fit_all = Callback::from(move |_| {
+            link.send_message(Self::Message::FitAll);
+        });
+
+        let link = ctx.link().clone();
+        let fit_selection = Callback::from(move |_| {
+            link.send_message(Self::Message::FitSelection);
+        });
+
+        let link = ctx.link().clone();
+        let rotate_left = Callback::from(move |_| {
+            link.send_message(Self::Message::Rotate(-ROTATION_STEP));
+        });
+
+        let link = ctx.link().clone();
+        let rotate_right = Callback::from(move |_| {
+            link.send_message(Self::Message::Rotate(ROTATION_STEP));
+        });
+
+        let link = ctx.link().clone();
+        let reset_rotation = Callback::from(move |_| {
+            link.send_message(Self::Message::ResetRotation);
+        });
+
+        let link = ctx.link().clone();
+        let on_add = Callback::from(move |_| {
+            link.send_message(Self::Message::AddLayer);
+        });
+
+        let link = ctx.link().clone();
+        let on_select = Callback::from(move |id| {
+            link.send_message(Self::Message::SelectLayer(id));
+        });
+
+        let link = ctx.link().clone();
+        let on_change = Callback::from(move |layer| {
+            link.send_message(Self::Message::ChangeLayer(layer));
+        });
+
+        let link = ctx.link().clone();
+        let on_move = Callback::from(move |id_delta| {
+            link.send_message(Self::Message::MoveLayer(id_delta));
+        });
+
+        let link = ctx.link().clone();
+        let on_export = Callback::from(move |format_option| {
+            link.send_message(Self::Message::Export(format_option));
+        });
+
+        let link = ctx.link().clone();
+        let on_import = Callback::from(move |file| {
+            link.send_message(Self::Message::Import(file));
+        });
+
+        let link = ctx.link().clone();
+        let on_paper = Callback::from(move |paper| {
+            link.send_message(Self::Message::SetPaper(paper));
+        });
+
+        let link = ctx.link().clone();
+        let on_add_frame = Callback::from(move |_| {
+            link.send_message(Self::Message::AddPageFrame);
+        });
+
+        let link = ctx.link().clone();
+        let on_remove_frame = Callback::from(move |id| {
+            link.send_message(Self::Message::RemovePageFrame(id));
+        });
+
+        let has_selection = !self.selected_id_v.is_empty();
         let edge_v = self.edge_v.clone();
+        let selected_id_v = self.selected_id_v.clone();
+        let layer_v = self.layer_v.clone();
+        let layer_id = self.layer_id.clone();
+        let paper = self.paper;
+        let frame_v = self.page_frame_v.clone();
+        let tool = self.tool;
+        let viewport = self.viewport;
+        let viewport_feed = self.viewport_feed.clone();
+        let peer_v: Vec<service::Peer> = self
+            .shown_id_v
+            .iter()
+            .filter_map(|id| self.peer_m.get(id).cloned())
+            .collect();
 
         yew::html! {
             <Column
@@ -74,14 +575,87 @@ impl yew::Component for HomePage {
                 border={format!("1em solid transparent")}
                 justify_content={format!("space-between")}>
                 <Row height={format!("1.5em")}>
+                    <button onclick={pen} disabled={tool == Tool::Pen}>{"Pen"}</button>
+                    <button onclick={eraser} disabled={tool == Tool::Eraser}>{"Eraser"}</button>
+                    <button onclick={selector} disabled={tool == Tool::Select}>{"Select"}</button>
+                    <button onclick={line} disabled={tool == Tool::Line}>{"Line"}</button>
+                    <button onclick={laser} disabled={tool == Tool::Laser}>{"Laser"}</button>
+                    <button onclick={ephemeral} disabled={tool == Tool::Ephemeral}>{"Ephemeral pen"}</button>
+                    <button onclick={snap_grid}>{if snap.grid { "Grid snap: on" } else { "Grid snap: off" }}</button>
+                    <button onclick={snap_endpoint}>{if snap.endpoint { "End snap: on" } else { "End snap: off" }}</button>
+                    <button onclick={switch_offscreen}>{if offscreen { "Worker: on" } else { "Worker: off" }}</button>
                     <button onclick={clear}>{"Clear"}</button>
-                    <button onclick={bigger}>{"+"}</button>
                     <button onclick={smaller}>{"-"}</button>
+                    <button onclick={reset_zoom}>{format!("{}%", viewport.percent())}</button>
+                    <button onclick={bigger}>{"+"}</button>
+                    <button onclick={fit_all}>{"Fit"}</button>
+                    <button onclick={fit_selection} disabled={selected_id_v.is_empty()}>{"Fit selection"}</button>
+                    <button onclick={rotate_left}>{"⟲"}</button>
+                    <button onclick={reset_rotation}>{format!("{}°", viewport.degrees())}</button>
+                    <button onclick={rotate_right}>{"⟳"}</button>
+                    <element::Avatars peer_v={peer_v.clone()} />
                 </Row>
-                <Column
+                <Row
                     height={format!("calc(100% - 2em)")}>
-                    <element::Canvas {commit} {edge_v} />
-                </Column>
+                    <Column
+                        width={format!("calc(100% - 12em)")}>
+                        <div style={"position: relative;width: 100%;height: 100%;"}>
+                            <element::Canvas
+                                key={if offscreen { "offscreen" } else { "main" }}
+                                {commit}
+                                {erase}
+                                {select}
+                                edge_v={edge_v.clone()}
+                                {selected_id_v}
+                                layer_v={layer_v.clone()}
+                                layer_id={layer_id.clone()}
+                                {paper}
+                                {snap}
+                                {tool}
+                                viewport={viewport_feed.clone()}
+                                on_viewport={on_viewport.clone()}
+                                on_error={on_canvas_error}
+                                {on_pointer}
+                                {on_ink}
+                                {on_clear_ink}
+                                peer_ink_v={self.peer_ink_m.values().flatten().cloned().collect::<Vec<_>>()}
+                                {offscreen} />
+                            <element::PageFrames frame_v={frame_v.clone()} viewport={viewport_feed.clone()} />
+                            <element::Cursors {peer_v} viewport={viewport_feed.clone()} />
+                            if self.hud {
+                                <div style={"position: absolute;left: 0.5em;top: 0.5em;"}>
+                                    <element::PerfHud />
+                                </div>
+                            }
+                            <div style={"position: absolute;right: 0.5em;bottom: 0.5em;"}>
+                                <element::Minimap
+                                    {edge_v}
+                                    layer_v={layer_v.clone()}
+                                    viewport={viewport_feed}
+                                    {on_viewport} />
+                            </div>
+                        </div>
+                    </Column>
+                    <Column
+                        width={format!("12em")}
+                        justify_content={format!("space-between")}>
+                        <element::Layers
+                            {layer_v}
+                            {layer_id}
+                            {on_select}
+                            {on_change}
+                            {on_move}
+                            {on_add} />
+                        <element::PaperPanel {paper} on_change={on_paper} />
+                        <element::ExportPanel
+                            {on_export}
+                            {on_import}
+                            {has_selection}
+                            {frame_v}
+                            {on_add_frame}
+                            {on_remove_frame} />
+                    </Column>
+                </Row>
             </Column>
         }
     }
@@ -89,18 +663,62 @@ impl yew::Component for HomePage {
     fn update(&mut self, ctx: &yew::prelude::Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Message::Commit(edge) => {
-                self.edge_v.push(edge.clone());
+                self.committing_id_v.push(edge.id.clone());
+                self.edge_v.update(|edge_v| edge_v.push(edge.clone()));
+                self.note_sync();
+                let board = ctx.props().board.clone();
                 ctx.link().send_future(async move {
-                    let _ = service::commit_edge(edge).await;
+                    let _ = service::commit_edge(board.as_deref(), edge).await;
                     Message::Post(false)
                 });
-                false
+                true
+            }
+            Message::Erase(id) => {
+                self.edge_v
+                    .update(|edge_v| edge_v.retain(|edge| edge.id != id));
+                self.selected_id_v.retain(|selected_id| *selected_id != id);
+                self.erasing_id_v.push(id.clone());
+                self.note_sync();
+                let board = ctx.props().board.clone();
+                ctx.link().send_future(async move {
+                    match service::erase_edge(board.as_deref(), &id).await {
+                        Ok(_) => Self::Message::Post(false),
+                        Err(e) => Self::Message::Error(e),
+                    }
+                });
+                true
+            }
+            Message::Select(id_v) => {
+                self.selected_id_v = id_v;
+                true
             }
             Message::Post(b) => b,
             Message::PostRefresh => {
+                let board = ctx.props().board.clone();
+                ctx.link().send_future(async move {
+                    match service::pull_layer_v(board.as_deref()).await {
+                        Ok(layer_v) => Self::Message::RefreshLayers(layer_v),
+                        Err(e) => Self::Message::Error(e),
+                    }
+                });
+                let board = ctx.props().board.clone();
+                ctx.link().send_future(async move {
+                    match service::pull_paper(board.as_deref()).await {
+                        Ok(paper) => Self::Message::RefreshPaper(paper.unwrap_or_default()),
+                        Err(e) => Self::Message::Error(e),
+                    }
+                });
+                let board = ctx.props().board.clone();
+                ctx.link().send_future(async move {
+                    match service::pull_page_frame_v(board.as_deref()).await {
+                        Ok(frame_v) => Self::Message::RefreshPageFrames(frame_v),
+                        Err(e) => Self::Message::Error(e),
+                    }
+                });
+                let board = ctx.props().board.clone();
                 let link = ctx.link().clone();
                 ctx.link().send_future(async move {
-                    let msg = match service::pull_edge_v().await {
+                    let msg = match service::pull_edge_v(board.as_deref()).await {
                         Ok(r) => Self::Message::Refresh(r),
                         Err(e) => Self::Message::Error(e),
                     };
@@ -112,36 +730,461 @@ impl yew::Component for HomePage {
                 });
                 false
             }
-            Message::Refresh(edge_v) => {
-                if edge_v.len() > self.edge_v.len() {
-                    self.edge_v = edge_v;
-                    true
-                } else {
-                    false
+            Message::Refresh(mut edge_v) => {
+                // A pull may have started before the latest commits and erasures landed.
+                self.committing_id_v
+                    .retain(|id| !edge_v.iter().any(|edge| edge.id == *id));
+                self.erasing_id_v
+                    .retain(|id| edge_v.iter().any(|edge| edge.id == *id));
+                self.note_sync();
+                edge_v.retain(|edge| !self.erasing_id_v.contains(&edge.id));
+                edge_v.extend(
+                    self.edge_v
+                        .iter()
+                        .filter(|edge| self.committing_id_v.contains(&edge.id))
+                        .cloned(),
+                );
+
+                let same = edge_v.len() == self.edge_v.len()
+                    && edge_v
+                        .iter()
+                        .zip(self.edge_v.iter())
+                        .all(|(a, b)| a.id == b.id);
+                if same {
+                    return false;
                 }
+                let id_v: HashSet<&str> = edge_v.iter().map(|edge| edge.id.as_str()).collect();
+                self.selected_id_v.retain(|id| id_v.contains(id.as_str()));
+                self.edge_v.set(edge_v);
+                true
             }
             Message::Clear => {
-                self.edge_v.clear();
+                self.edge_v.set(Vec::new());
+                self.selected_id_v.clear();
+                self.committing_id_v.clear();
+                self.erasing_id_v.clear();
+                self.note_sync();
+                let board = ctx.props().board.clone();
                 ctx.link().send_future(async move {
-                    match service::clear().await {
+                    match service::clear(board.as_deref()).await {
                         Ok(_) => Self::Message::Post(false),
                         Err(e) => Self::Message::Error(e),
                     }
                 });
                 true
             }
+            Message::SetTool(tool) => {
+                self.tool = tool;
+                self.me.tool = tool.name().to_string();
+                self.publish_me();
+                true
+            }
+            Message::SetSnap(snap) => {
+                self.snap = snap;
+                true
+            }
+            Message::SetOffscreen(offscreen) => {
+                self.offscreen = offscreen;
+                true
+            }
+            Message::Pointer(pos) => {
+                self.me.pos = Some((pos.x, pos.y));
+                self.publish_me();
+                false
+            }
+            Message::Peer(service::PeerEvent::Seen(peer)) => {
+                if peer.id == self.me.id {
+                    return false;
+                }
+                let changed = self.peer_m.get(&peer.id) != Some(&peer);
+                self.peer_m.insert(peer.id.clone(), peer);
+                self.update_shown() || changed
+            }
+            Message::Peer(service::PeerEvent::Gone(id)) => {
+                self.peer_m.remove(&id);
+                let had_ink = self.peer_ink_m.remove(&id).is_some();
+                self.update_shown() || had_ink
+            }
+            Message::Peer(service::PeerEvent::Ink(id, mut ink)) => {
+                if id == self.me.id {
+                    return false;
+                }
+                let ink_v = self.peer_ink_m.entry(id).or_default();
+                if ink_v.last().is_some_and(|last| last.end.is_none()) {
+                    ink_v.pop();
+                }
+                // Their clock is not ours; it fades from when it got here.
+                if ink.end.is_some() {
+                    ink.end = Some(metrics::now());
+                }
+                ink_v.push(ink);
+                true
+            }
+            Message::Peer(service::PeerEvent::ClearInk(id)) => match self.peer_ink_m.get_mut(&id) {
+                Some(ink_v) => {
+                    ink_v.retain(|ink| ink.kind != InkKind::Ephemeral);
+                    true
+                }
+                None => false,
+            },
+            Message::Ink(ink) => {
+                if let Some(presence) = &self.presence {
+                    presence.send_ink(&ink);
+                }
+                false
+            }
+            Message::ClearInk => {
+                if let Some(presence) = &self.presence {
+                    presence.clear_ink();
+                }
+                false
+            }
+            Message::PresenceTick => {
+                ctx.link().send_future(async {
+                    yew::platform::time::sleep(PRESENCE_TICK).await;
+                    Message::PresenceTick
+                });
+                if self.me_dirty {
+                    self.publish_me();
+                }
+                let now = metrics::now();
+                let ink_n: usize = self.peer_ink_m.values().map(Vec::len).sum();
+                for ink_v in self.peer_ink_m.values_mut() {
+                    ink_v.retain(|ink| !ink.is_gone(now));
+                }
+                self.peer_ink_m.retain(|_, ink_v| !ink_v.is_empty());
+                let ink_gone = ink_n != self.peer_ink_m.values().map(Vec::len).sum::<usize>();
+                self.update_shown() || ink_gone
+            }
+            Message::SwitchHud => {
+                self.hud = !self.hud;
+                true
+            }
             Message::Error(e) => {
                 ctx.props().on_error.emit(e);
                 false
             }
-            Message::Bigger => {
-                self.scale += 1;
+            Message::Bigger => self.set_viewport(ctx, self.viewport.zoom_in()),
+            Message::Smaller => self.set_viewport(ctx, self.viewport.zoom_out()),
+            Message::ResetZoom => self.set_viewport(ctx, self.viewport.reset_zoom()),
+            Message::FitAll => match bounds(self.edge_v.iter()) {
+                Some(rect) => self.set_viewport(ctx, self.viewport.fit(&rect)),
+                None => false,
+            },
+            Message::FitSelection => {
+                let selected_v = self
+                    .edge_v
+                    .iter()
+                    .filter(|edge| self.selected_id_v.contains(&edge.id));
+                match bounds(selected_v) {
+                    Some(rect) => self.set_viewport(ctx, self.viewport.fit(&rect)),
+                    None => false,
+                }
+            }
+            Message::Rotate(delta) => self.set_viewport(ctx, self.viewport.rotate_by(delta)),
+            Message::ResetRotation => {
+                self.set_viewport(ctx, self.viewport.rotate_by(-self.viewport.rotation))
+            }
+            Message::SetViewport(viewport) => self.set_viewport(ctx, viewport),
+            Message::SaveView(version) => {
+                if version == self.view_version {
+                    if let Err(e) =
+                        router::replace_view(ctx.props().board.as_deref(), &self.viewport)
+                    {
+                        log::warn!("when save view:\n\t{e}");
+                    }
+                }
                 false
             }
-            Message::Smaller => {
-                self.scale -= 1;
+            Message::AddLayer => {
+                let layer = Layer::new(format!("Layer {}", self.layer_v.len() + 1));
+                self.layer_id = layer.id.clone();
+                self.layer_v.push(layer);
+                self.save_layers(ctx);
+                true
+            }
+            Message::SelectLayer(id) => {
+                self.layer_id = id;
+                true
+            }
+            Message::ChangeLayer(layer) => {
+                let slot = match self.layer_v.iter_mut().find(|it| it.id == layer.id) {
+                    Some(slot) => slot,
+                    None => return false,
+                };
+                if !layer.is_editable() {
+                    let edge_v = &self.edge_v;
+                    self.selected_id_v.retain(|id| {
+                        !edge_v
+                            .iter()
+                            .any(|edge| edge.id == *id && edge.layer_id == layer.id)
+                    });
+                }
+                *slot = layer;
+                self.save_layers(ctx);
+                true
+            }
+            Message::MoveLayer((id, delta)) => {
+                let i = match self.layer_v.iter().position(|layer| layer.id == id) {
+                    Some(i) => i,
+                    None => return false,
+                };
+                let to = (i as isize + delta).clamp(0, self.layer_v.len() as isize - 1) as usize;
+                if to == i {
+                    return false;
+                }
+                let layer = self.layer_v.remove(i);
+                self.layer_v.insert(to, layer);
+                self.save_layers(ctx);
+                true
+            }
+            Message::RefreshLayers(layer_v) => {
+                if self.saving_layer_n > 0 || layer_v.is_empty() || layer_v == self.layer_v {
+                    return false;
+                }
+                if !layer_v.iter().any(|layer| layer.id == self.layer_id) {
+                    self.layer_id = layer_v.last().unwrap().id.clone();
+                }
+                self.layer_v = layer_v;
+                true
+            }
+            Message::LayersSaved => {
+                self.saving_layer_n -= 1;
                 false
             }
+            Message::SetPaper(paper) => {
+                if paper == self.paper {
+                    return false;
+                }
+                self.paper = paper;
+                self.saving_paper_n += 1;
+                let board = ctx.props().board.clone();
+                let link = ctx.link().clone();
+                ctx.link().send_future(async move {
+                    if let Err(e) = service::save_paper(board.as_deref(), paper).await {
+                        link.send_message(Message::Error(e));
+                    }
+                    Message::PaperSaved
+                });
+                true
+            }
+            Message::RefreshPaper(paper) => {
+                if self.saving_paper_n > 0 || paper == self.paper {
+                    return false;
+                }
+                self.paper = paper;
+                true
+            }
+            Message::PaperSaved => {
+                self.saving_paper_n -= 1;
+                false
+            }
+            Message::Export((format, option)) => {
+                if format == ExportFormat::Pdf
+                    && matches!(option.pages, PdfPages::PerFrame | PdfPages::PerBoard)
+                {
+                    self.export_pdf_pages(ctx, option);
+                    return false;
+                }
+                let edge_v: Vec<&Edge> = match option.area {
+                    ExportArea::Board => self.edge_v.iter().collect(),
+                    ExportArea::View => {
+                        let rect = self.viewport.world_rect();
+                        self.edge_v
+                            .iter()
+                            .filter(|edge| edge.bounds().is_some_and(|b| b.intersects(&rect)))
+                            .collect()
+                    }
+                    ExportArea::Selection => self
+                        .edge_v
+                        .iter()
+                        .filter(|edge| self.selected_id_v.contains(&edge.id))
+                        .collect(),
+                };
+                if matches!(format, ExportFormat::Json | ExportFormat::InkMl) {
+                    let doc = export::Document {
+                        meta: export::Meta {
+                            generator: format!("huiwen {}", env!("CARGO_PKG_VERSION")),
+                            created: String::from(js_sys::Date::new_0().to_iso_string()),
+                        },
+                        layer_v: self.layer_v.clone(),
+                        edge_v: edge_v.into_iter().cloned().collect(),
+                    };
+                    let rs = if format == ExportFormat::Json {
+                        util::download_data(
+                            doc.to_json().as_bytes(),
+                            "application/json",
+                            "huiwen.json",
+                        )
+                    } else {
+                        let ink = export::to_inkml(&doc);
+                        util::download_data(ink.as_bytes(), "application/inkml+xml", "huiwen.inkml")
+                    };
+                    if let Err(e) = rs {
+                        ctx.link().send_message(Message::Error(e));
+                    }
+                    return false;
+                }
+                let content = match option.area {
+                    ExportArea::View => Some(self.viewport.world_rect()),
+                    _ => bounds(edge_v.iter().copied()),
+                };
+                let Some(content) = content else {
+                    ctx.link()
+                        .send_message(Message::Error(err::Error::Other(format!(
+                            "nothing to export"
+                        ))));
+                    return false;
+                };
+                let frame = match option.area {
+                    ExportArea::View => export::frame_view(&self.viewport, option.scale),
+                    _ => export::frame_rect(&content, self.viewport.zoom * option.scale),
+                };
+                let group_v = export::layer_group_v(edge_v, &self.layer_v);
+                let background = if option.transparent {
+                    None
+                } else {
+                    Some(EXPORT_BACKGROUND)
+                };
+                match format {
+                    ExportFormat::Png => {
+                        ctx.link().send_future(async move {
+                            let rs = export::to_png_url(group_v, frame, background)
+                                .await
+                                .and_then(|url| util::download(&url, "huiwen.png"));
+                            match rs {
+                                Ok(_) => Self::Message::Post(false),
+                                Err(e) => Self::Message::Error(e),
+                            }
+                        });
+                    }
+                    ExportFormat::Svg => {
+                        let svg = export::to_svg(&group_v, &frame, background);
+                        let rs = util::download_data(svg.as_bytes(), "image/svg+xml", "huiwen.svg");
+                        if let Err(e) = rs {
+                            ctx.link().send_message(Message::Error(e));
+                        }
+                    }
+                    ExportFormat::Pdf => {
+                        let page_v: Vec<export::PdfPage> = if option.pages == PdfPages::PerLayer {
+                            group_v
+                                .into_iter()
+                                .map(|group| export::PdfPage {
+                                    group_v: vec![group],
+                                    rect: content,
+                                })
+                                .collect()
+                        } else {
+                            vec![export::PdfPage {
+                                group_v,
+                                rect: content,
+                            }]
+                        };
+                        if let Err(e) = download_pdf(&page_v, &option) {
+                            ctx.link().send_message(Message::Error(e));
+                        }
+                    }
+                    ExportFormat::Json | ExportFormat::InkMl => (),
+                }
+                false
+            }
+            Message::AddPageFrame => {
+                let name = format!("Frame {}", self.page_frame_v.len() + 1);
+                self.page_frame_v
+                    .push(PageFrame::new(name, self.viewport.world_rect()));
+                self.save_page_frames(ctx);
+                true
+            }
+            Message::RemovePageFrame(id) => {
+                self.page_frame_v.retain(|frame| frame.id != id);
+                self.save_page_frames(ctx);
+                true
+            }
+            Message::RefreshPageFrames(frame_v) => {
+                if self.saving_page_frame_n > 0 || frame_v == self.page_frame_v {
+                    return false;
+                }
+                self.page_frame_v = frame_v;
+                true
+            }
+            Message::PageFramesSaved => {
+                self.saving_page_frame_n -= 1;
+                false
+            }
+            Message::Import(file) => {
+                let is_json = file.name().to_lowercase().ends_with(".json");
+                ctx.link().send_future(async move {
+                    let rs = util::read_text(&file).await.and_then(|s| {
+                        if is_json {
+                            export::Document::from_json(&s)
+                        } else {
+                            export::from_inkml(&s)
+                        }
+                    });
+                    match rs {
+                        Ok(doc) => Self::Message::Imported(doc),
+                        Err(e) => Self::Message::Error(e),
+                    }
+                });
+                false
+            }
+            Message::Imported(mut doc) => {
+                // Ids come from the file; any not made by `new_id` gets one that is.
+                let mut layer_id_m: HashMap<String, String> = HashMap::new();
+                for layer in &mut doc.layer_v {
+                    if !element::is_id(&layer.id) {
+                        let id = element::new_id();
+                        layer_id_m.insert(std::mem::replace(&mut layer.id, id.clone()), id);
+                    }
+                }
+                for edge in &mut doc.edge_v {
+                    if let Some(id) = layer_id_m.get(&edge.layer_id) {
+                        edge.layer_id = id.clone();
+                    } else if !element::is_id(&edge.layer_id) {
+                        edge.layer_id = DEFAULT_LAYER_ID.to_string();
+                    }
+                }
+                // Layers already here keep their settings.
+                let new_layer_v: Vec<Layer> = doc
+                    .layer_v
+                    .into_iter()
+                    .filter(|layer| !self.layer_v.iter().any(|l| l.id == layer.id))
+                    .collect();
+                if !new_layer_v.is_empty() {
+                    self.layer_v.extend(new_layer_v);
+                    self.save_layers(ctx);
+                }
+                let mut id_v: HashSet<String> =
+                    self.edge_v.iter().map(|edge| edge.id.clone()).collect();
+                for mut edge in doc.edge_v {
+                    if edge.point_v.is_empty() {
+                        continue;
+                    }
+                    // Importing a board twice, or into itself, keeps both copies.
+                    if !element::is_id(&edge.id) || !id_v.insert(edge.id.clone()) {
+                        edge.id = element::new_id();
+                        id_v.insert(edge.id.clone());
+                    }
+                    self.committing_id_v.push(edge.id.clone());
+                    self.edge_v.update(|edge_v| edge_v.push(edge.clone()));
+                    let board = ctx.props().board.clone();
+                    ctx.link().send_future(async move {
+                        let _ = service::commit_edge(board.as_deref(), edge).await;
+                        Message::Post(false)
+                    });
+                }
+                self.note_sync();
+                true
+            }
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &yew::Context<Self>) {
+        if let (Some(window), Some(on_hud_key)) = (web_sys::window(), self.on_hud_key.take()) {
+            let _ = window.remove_event_listener_with_callback(
+                "keydown",
+                on_hud_key.as_ref().unchecked_ref(),
+            );
         }
     }
 }
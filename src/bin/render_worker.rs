@@ -0,0 +1,12 @@
+#[cfg(target_arch = "wasm32")]
+use huiwen::render_worker;
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    let _ = console_log::init_with_level(log::Level::Info);
+    render_worker::run();
+}
+
+/// The worker runs in the browser only.
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {}
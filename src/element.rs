@@ -1,9 +1,49 @@
 mod canvas;
+mod export_option;
+#[cfg(target_arch = "wasm32")]
+mod export_panel;
+#[cfg(target_arch = "wasm32")]
 mod input;
-mod tree;
+#[cfg(target_arch = "wasm32")]
+mod layers;
+#[cfg(target_arch = "wasm32")]
+mod minimap;
+#[cfg(target_arch = "wasm32")]
 mod modal;
+#[cfg(target_arch = "wasm32")]
+mod page_frames;
+#[cfg(target_arch = "wasm32")]
+mod paper_panel;
+#[cfg(target_arch = "wasm32")]
+mod perf_hud;
+#[cfg(target_arch = "wasm32")]
+mod presence;
+#[cfg(target_arch = "wasm32")]
+mod tree;
+#[cfg(target_arch = "wasm32")]
+mod viewport_feed;
 
-pub use modal::*;
 pub use canvas::*;
+pub use export_option::*;
+#[cfg(target_arch = "wasm32")]
+pub use export_panel::*;
+#[cfg(target_arch = "wasm32")]
 pub use input::*;
+#[cfg(target_arch = "wasm32")]
+pub use layers::*;
+#[cfg(target_arch = "wasm32")]
+pub use minimap::*;
+#[cfg(target_arch = "wasm32")]
+pub use modal::*;
+#[cfg(target_arch = "wasm32")]
+pub use page_frames::*;
+#[cfg(target_arch = "wasm32")]
+pub use paper_panel::*;
+#[cfg(target_arch = "wasm32")]
+pub use perf_hud::*;
+#[cfg(target_arch = "wasm32")]
+pub use presence::*;
+#[cfg(target_arch = "wasm32")]
 pub use tree::*;
+#[cfg(target_arch = "wasm32")]
+pub use viewport_feed::*;
@@ -0,0 +1,89 @@
+use painting::{point::Point, AsCanvas};
+use wgpu::SurfaceError;
+use winit::dpi::PhysicalSize;
+
+// Public
+/// Any canvas `painting` draws on, counting the points it is handed.
+pub struct CountedCanvas {
+    canvas: Box<dyn AsCanvas>,
+    /// Points of the ended lines, and of the open one.
+    point_n: usize,
+    open_n: usize,
+    /// Points handed over since the last `take_upload_n`.
+    upload_n: usize,
+}
+
+impl CountedCanvas {
+    pub fn new(canvas: Box<dyn AsCanvas>) -> Self {
+        Self {
+            canvas,
+            point_n: 0,
+            open_n: 0,
+            upload_n: 0,
+        }
+    }
+
+    /// Points drawn, counting the line still open.
+    pub fn point_n(&self) -> usize {
+        self.point_n + self.open_n
+    }
+
+    /// Points handed to `painting` since the last call.
+    pub fn take_upload_n(&mut self) -> usize {
+        std::mem::take(&mut self.upload_n)
+    }
+}
+
+impl painting::AsCanvas for CountedCanvas {
+    fn render(&mut self) -> Result<(), SurfaceError> {
+        self.canvas.render()
+    }
+
+    fn push_point(&mut self, pt: Point) {
+        self.open_n += 1;
+        self.upload_n += 1;
+        self.canvas.push_point(pt)
+    }
+
+    fn start_line(&mut self, pt: Point) {
+        self.open_n = 1;
+        self.upload_n += 1;
+        self.canvas.start_line(pt)
+    }
+
+    fn end_line(&mut self) {
+        self.point_n += std::mem::take(&mut self.open_n);
+        self.canvas.end_line()
+    }
+
+    fn cancle_line(&mut self) {
+        self.open_n = 0;
+        self.canvas.cancle_line()
+    }
+
+    fn set_aspect(&mut self, aspect: f32) {
+        self.canvas.set_aspect(aspect)
+    }
+
+    fn clear(&mut self) {
+        self.point_n = 0;
+        self.open_n = 0;
+        self.canvas.clear()
+    }
+
+    fn get_size(&self) -> &PhysicalSize<u32> {
+        self.canvas.get_size()
+    }
+
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        self.canvas.resize(new_size)
+    }
+
+    fn move_content(&mut self, x: f32, y: f32, z: f32) {
+        self.canvas.move_content(x, y, z)
+    }
+
+    fn scacle(&mut self, x: f32, y: f32, z: f32) {
+        self.canvas.scacle(x, y, z)
+    }
+}
@@ -0,0 +1,281 @@
+use std::{cell::RefCell, ops::Deref, rc::Rc};
+
+use cgmath::{InnerSpace, MetricSpace, Point2, Point3, Vector2};
+use painting::point::Point;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Rect, DEFAULT_LAYER_ID};
+
+/// `PointerEvent.buttons` bit of the barrel button.
+const BARREL_BUTTON: u16 = 2;
+/// `PointerEvent.buttons` bit of the eraser end of a pen.
+const ERASER_BUTTON: u16 = 32;
+/// Narrowest a nib gets when the stroke runs along its edge.
+const MIN_NIB_RATIO: f32 = 0.25;
+
+thread_local! {
+    /// Where `new_id` takes ids from.
+    static ID_SOURCE: RefCell<Box<dyn FnMut() -> String>> = RefCell::new(Box::new(random_id));
+}
+
+/// 16 random hex digits.
+#[cfg(target_arch = "wasm32")]
+fn random_id() -> String {
+    let hi = (js_sys::Math::random() * u32::MAX as f64) as u32;
+    let lo = (js_sys::Math::random() * u32::MAX as f64) as u32;
+    format!("{hi:08x}{lo:08x}")
+}
+
+/// 16 hex digits counting up, off the browser where there is no `Math.random`.
+#[cfg(not(target_arch = "wasm32"))]
+fn random_id() -> String {
+    use std::cell::Cell;
+
+    thread_local! {
+        static NEXT: Cell<u64> = const { Cell::new(0) };
+    }
+    NEXT.with(|next| {
+        next.set(next.get() + 1);
+        format!("{:016x}", next.get())
+    })
+}
+
+/// `Edge::point_v` as rows of position, color and width, as `painting`
+/// doesn't serialize its points.
+mod point_v {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(point_v: &[Point], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(point_v.iter().map(|pt| {
+            let [r, g, b, a] = pt.color;
+            [pt.pos.x, pt.pos.y, pt.pos.z, r, g, b, a, pt.width]
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Point>, D::Error> {
+        let row_v = Vec::<[f32; 8]>::deserialize(d)?;
+        Ok(row_v
+            .into_iter()
+            .map(|[x, y, z, r, g, b, a, width]| Point {
+                pos: Point3::new(x, y, z),
+                color: [r, g, b, a],
+                width,
+            })
+            .collect())
+    }
+}
+
+// Public
+/// What a stylus reported for one point, besides position and pressure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stylus {
+    /// Degrees in [-90, 90], positive towards the right.
+    pub tilt_x: f32,
+    /// Degrees in [-90, 90], positive towards the user.
+    pub tilt_y: f32,
+    /// Degrees in [0, 360), clockwise rotation of the pen around its axis.
+    pub twist: f32,
+    pub barrel: bool,
+    pub eraser: bool,
+    /// Milliseconds since the first point of the edge.
+    pub time: f32,
+}
+
+impl Stylus {
+    pub fn from_event(e: &web_sys::PointerEvent) -> Self {
+        let buttons = e.buttons();
+        Self {
+            tilt_x: e.tilt_x() as f32,
+            tilt_y: e.tilt_y() as f32,
+            twist: e.twist() as f32,
+            barrel: buttons & BARREL_BUTTON != 0,
+            eraser: buttons & ERASER_BUTTON != 0,
+            time: 0.0,
+        }
+    }
+
+    /// True if the pen reported an orientation at all.
+    pub fn is_oriented(&self) -> bool {
+        self.tilt_x != 0.0 || self.tilt_y != 0.0 || self.twist != 0.0
+    }
+
+    /// Angle of the nib in radians, taken from twist or else from the tilt azimuth.
+    pub fn nib_angle(&self) -> f32 {
+        if self.twist != 0.0 {
+            self.twist.to_radians()
+        } else {
+            self.tilt_y.to_radians().atan2(self.tilt_x.to_radians())
+        }
+    }
+
+    /// Factor to apply to the pen width for a stroke heading along `direction`.
+    ///
+    /// A flat nib is broad across its angle and thin along it, and a tilted pen
+    /// lays down a wider mark than an upright one.
+    ///
+    /// Without a `direction`, as at the start of a stroke, only the tilt counts.
+    pub fn width_factor(&self, direction: Vector2<f32>) -> f32 {
+        if !self.is_oriented() {
+            return 1.0;
+        }
+        let nib = if direction.magnitude2() == 0.0 {
+            1.0
+        } else {
+            let heading = direction.y.atan2(direction.x);
+            let across = (heading - self.nib_angle()).sin().abs();
+            MIN_NIB_RATIO + (1.0 - MIN_NIB_RATIO) * across
+        };
+
+        let tilt = Vector2::new(self.tilt_x, self.tilt_y).magnitude().min(90.0) / 90.0;
+        nib * (1.0 + tilt)
+    }
+}
+
+/// One stroke, as drawn and as stored.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub id: String,
+    pub layer_id: String,
+    #[serde(with = "point_v")]
+    pub point_v: Vec<Point>,
+    /// Same length as `point_v`.
+    pub stylus_v: Vec<Stylus>,
+}
+
+impl Edge {
+    pub fn new() -> Self {
+        Self {
+            id: new_id(),
+            layer_id: DEFAULT_LAYER_ID.to_string(),
+            point_v: Vec::new(),
+            stylus_v: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, pt: Point, stylus: Stylus) {
+        self.point_v.push(pt);
+        self.stylus_v.push(stylus);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.point_v.is_empty()
+    }
+
+    /// True if any point of this edge lies within `radius` of `pos`.
+    pub fn hit(&self, pos: Point2<f32>, radius: f32) -> bool {
+        self.point_v
+            .iter()
+            .any(|pt| Point2::new(pt.pos.x, pt.pos.y).distance(pos) <= radius)
+    }
+
+    pub fn bounds(&self) -> Option<Rect> {
+        Rect::bound(
+            self.point_v
+                .iter()
+                .map(|pt| Point2::new(pt.pos.x, pt.pos.y)),
+        )
+    }
+}
+
+impl Default for Edge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The edges of a board as handed from component to component: shared
+/// rather than copied, and compared by version rather than edge by edge.
+#[derive(Clone, Debug, Default)]
+pub struct EdgeList {
+    edge_v: Rc<Vec<Edge>>,
+    /// Bumped on every change.
+    version: u64,
+}
+
+impl EdgeList {
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Change the edges with `f`, copying them first if they are shared.
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut Vec<Edge>) -> R) -> R {
+        self.version += 1;
+        f(Rc::make_mut(&mut self.edge_v))
+    }
+
+    pub fn set(&mut self, edge_v: Vec<Edge>) {
+        self.version += 1;
+        self.edge_v = Rc::new(edge_v);
+    }
+}
+
+impl Deref for EdgeList {
+    type Target = Vec<Edge>;
+
+    fn deref(&self) -> &Vec<Edge> {
+        &self.edge_v
+    }
+}
+
+impl PartialEq for EdgeList {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version && Rc::ptr_eq(&self.edge_v, &other.edge_v)
+    }
+}
+
+/// True if `id` could have come from `new_id`, or is the default layer's.
+///
+/// Ids from files are checked with this before they go anywhere.
+pub fn is_id(id: &str) -> bool {
+    id == DEFAULT_LAYER_ID
+        || (id.len() == 16 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')))
+}
+
+/// A fresh id for an edge, layer or page frame.
+pub fn new_id() -> String {
+    ID_SOURCE.with(|source| (source.borrow_mut())())
+}
+
+/// Take the ids of `new_id` in this thread from `source` from now on.
+pub fn set_id_source(source: impl FnMut() -> String + 'static) {
+    ID_SOURCE.with(|old| *old.borrow_mut() = Box::new(source));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ids_come_from_the_source() {
+        let a = new_id();
+        let b = new_id();
+        assert!(is_id(&a) && is_id(&b));
+        assert_ne!(a, b);
+
+        let mut n = 0;
+        set_id_source(move || {
+            n += 1;
+            format!("{n:016x}")
+        });
+        assert_eq!(Edge::new().id, "0000000000000001");
+        assert_eq!(new_id(), "0000000000000002");
+    }
+
+    #[test]
+    fn edge_lists_tell_changes_apart() {
+        let mut edge_v = EdgeList::default();
+        let shared = edge_v.clone();
+        assert_eq!(shared, edge_v);
+
+        edge_v.update(|edge_v| edge_v.push(Edge::new()));
+        assert_ne!(shared, edge_v);
+        assert!(shared.is_empty());
+        assert_eq!(edge_v.len(), 1);
+
+        // Another list is never the same, even with the same edges.
+        let mut other = EdgeList::default();
+        other.set(edge_v.to_vec());
+        assert_eq!(other.version(), edge_v.version());
+        assert_ne!(other, edge_v);
+    }
+}
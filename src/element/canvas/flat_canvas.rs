@@ -0,0 +1,188 @@
+use cgmath::{ElementWise, Point2, Vector3};
+use painting::point::{Pen, Point};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use wgpu::SurfaceError;
+use winit::dpi::PhysicalSize;
+
+use crate::{err, export::outline};
+
+use super::raw_canvas::overlay;
+
+const BACKGROUND: &str = "rgba(255, 255, 255, 1)";
+
+fn css_color(color: [f32; 4]) -> String {
+    let [r, g, b] = [color[0], color[1], color[2]].map(|c| (c.clamp(0.0, 1.0) * 255.0).round());
+    format!("rgba({r}, {g}, {b}, {})", color[3].clamp(0.0, 1.0))
+}
+
+// Public
+/// Draws the lines `painting` would, with the Canvas 2D API instead of the GPU.
+///
+/// Lines are kept as they come and all drawn again on every `render`, each
+/// filled around its outline like the exports do.
+///
+/// It draws on a canvas of its own laid over the given one, which keeps
+/// taking the pointer: a canvas that was offered to WebGL may not give a
+/// 2D context any more.
+pub struct FlatCanvas {
+    context: CanvasRenderingContext2d,
+    html_canvas: HtmlCanvasElement,
+    size: PhysicalSize<u32>,
+    line_v: Vec<Vec<Point>>,
+    /// The line started and not yet ended.
+    open: Option<Vec<Point>>,
+    /// What `move_content` and `scacle` did to the content, in device space.
+    offset: Vector3<f32>,
+    scale: Vector3<f32>,
+}
+
+impl FlatCanvas {
+    /// Draw over `under`, which must sit in an element positioned for it.
+    pub fn create(under: &HtmlCanvasElement) -> err::Result<Self> {
+        let html_canvas = overlay(under)?;
+        let context = match html_canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+        {
+            Some(context) => context,
+            None => {
+                html_canvas.remove();
+                return Err(err::Error::Gpu("failed to get 2d context".to_string()));
+            }
+        };
+        let size = PhysicalSize::new(html_canvas.width(), html_canvas.height());
+        Ok(Self {
+            context,
+            html_canvas,
+            size,
+            line_v: Vec::new(),
+            open: None,
+            offset: Vector3::new(0.0, 0.0, 0.0),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        })
+    }
+
+    fn draw_line(&self, line: &[Point], pen: &Pen, client: PhysicalSize<u32>) {
+        if line.is_empty() {
+            return;
+        }
+        // Where `px2point` puts the corners tells where device points land.
+        let origin = pen.px2point(0.0, 0.0, 1.0, client).pos;
+        let far = pen
+            .px2point(client.width as f32, client.height as f32, 1.0, client)
+            .pos;
+        let k_x = client.width as f32 / (far.x - origin.x);
+        let k_y = client.height as f32 / (far.y - origin.y);
+        let px_v: Vec<Point> = line
+            .iter()
+            .map(|pt| {
+                let x = pt.pos.x * self.scale.x + self.offset.x;
+                let y = pt.pos.y * self.scale.y + self.offset.y;
+                let mut px = pt.clone();
+                px.pos.x = (x - origin.x) * k_x;
+                px.pos.y = (y - origin.y) * k_y;
+                // Widths are in clip space too, so they scale like heights.
+                px.width = pt.width * k_y.abs();
+                px
+            })
+            .collect();
+        let polygon = outline(&px_v);
+        let context = &self.context;
+        context.set_fill_style(&JsValue::from_str(&css_color(line[0].color)));
+        context.begin_path();
+        for (i, pt) in polygon.iter().enumerate() {
+            let Point2 { x, y } = *pt;
+            if i == 0 {
+                context.move_to(x as f64, y as f64);
+            } else {
+                context.line_to(x as f64, y as f64);
+            }
+        }
+        context.close_path();
+        context.fill();
+    }
+}
+
+impl painting::AsCanvas for FlatCanvas {
+    fn render(&mut self) -> Result<(), SurfaceError> {
+        let client = PhysicalSize::new(
+            self.html_canvas.client_width().max(1) as u32,
+            self.html_canvas.client_height().max(1) as u32,
+        );
+        let (width, height) = (self.html_canvas.width(), self.html_canvas.height());
+        let context = &self.context;
+        let _ = context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+        context.set_fill_style(&JsValue::from_str(BACKGROUND));
+        context.fill_rect(0.0, 0.0, width as f64, height as f64);
+        // Draw in client pixels; the page stretches the canvas to fit them.
+        let _ = context.set_transform(
+            width as f64 / client.width as f64,
+            0.0,
+            0.0,
+            height as f64 / client.height as f64,
+            0.0,
+            0.0,
+        );
+        let pen = Pen::default();
+        for line in self.line_v.iter().chain(&self.open) {
+            self.draw_line(line, &pen, client);
+        }
+        Ok(())
+    }
+
+    fn push_point(&mut self, pt: Point) {
+        if let Some(line) = &mut self.open {
+            line.push(pt);
+        }
+    }
+
+    fn start_line(&mut self, pt: Point) {
+        self.open = Some(vec![pt]);
+    }
+
+    fn end_line(&mut self) {
+        if let Some(line) = self.open.take() {
+            self.line_v.push(line);
+        }
+    }
+
+    fn cancle_line(&mut self) {
+        self.open = None;
+    }
+
+    fn set_aspect(&mut self, _aspect: f32) {}
+
+    fn clear(&mut self) {
+        self.line_v.clear();
+        self.open = None;
+    }
+
+    fn get_size(&self) -> &PhysicalSize<u32> {
+        &self.size
+    }
+
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        self.size = new_size;
+        self.html_canvas.set_width(new_size.width);
+        self.html_canvas.set_height(new_size.height);
+    }
+
+    fn move_content(&mut self, x: f32, y: f32, z: f32) {
+        self.offset += Vector3::new(x, y, z);
+    }
+
+    fn scacle(&mut self, x: f32, y: f32, z: f32) {
+        let k = Vector3::new(x, y, z);
+        self.scale = self.scale.mul_element_wise(k);
+        self.offset = self.offset.mul_element_wise(k);
+    }
+}
+
+impl Drop for FlatCanvas {
+    fn drop(&mut self) {
+        self.html_canvas.remove();
+    }
+}
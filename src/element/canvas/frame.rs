@@ -0,0 +1,57 @@
+use std::{cell::Cell, rc::Rc};
+
+use js_sys::Array;
+use wasm_bindgen::{closure::Closure, JsValue};
+
+use super::view::call;
+
+// Public
+/// Renders at most once per animation frame, and only when asked to.
+///
+/// The first request of a frame asks the browser for an animation frame,
+/// later ones ride along. With nothing asked for no frame comes, so an idle
+/// board costs nothing. Works in a worker too, which has animation frames
+/// of its own.
+pub struct FrameScheduler {
+    on_frame: Closure<dyn FnMut(f64)>,
+    /// Handle of the animation frame asked for, until it comes.
+    pending: Rc<Cell<Option<f64>>>,
+}
+
+impl FrameScheduler {
+    /// `on_frame` is called in each animation frame asked for.
+    pub fn new(mut on_frame: impl FnMut() + 'static) -> Self {
+        let pending = Rc::new(Cell::new(None));
+        let frame_pending = pending.clone();
+        let on_frame = Closure::<dyn FnMut(f64)>::new(move |_time: f64| {
+            frame_pending.set(None);
+            on_frame();
+        });
+        Self { on_frame, pending }
+    }
+
+    /// Ask for a frame, unless one is coming already.
+    pub fn request(&self) {
+        if self.pending.get().is_some() {
+            return;
+        }
+        let handle = call(
+            &js_sys::global(),
+            "requestAnimationFrame",
+            &Array::of1(self.on_frame.as_ref()),
+        );
+        self.pending.set(handle.and_then(|handle| handle.as_f64()));
+    }
+}
+
+impl Drop for FrameScheduler {
+    fn drop(&mut self) {
+        if let Some(handle) = self.pending.take() {
+            call(
+                &js_sys::global(),
+                "cancelAnimationFrame",
+                &Array::of1(&JsValue::from_f64(handle)),
+            );
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use cgmath::{Point2, Vector2};
+
+use super::Rect;
+
+/// Items a node holds before it splits.
+const NODE_CAPACITY: usize = 8;
+const MAX_DEPTH: usize = 16;
+
+struct Node<T> {
+    bounds: Rect,
+    /// Items that don't fit in a single child.
+    item_v: Vec<(Rect, T)>,
+    child_v: Option<Box<[Node<T>; 4]>>,
+}
+
+impl<T: PartialEq> Node<T> {
+    fn new(bounds: Rect) -> Self {
+        Self {
+            bounds,
+            item_v: Vec::new(),
+            child_v: None,
+        }
+    }
+
+    fn quadrant_v(&self) -> [Rect; 4] {
+        let c = self.bounds.center();
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        [
+            Rect::from_corners(min, c),
+            Rect::from_corners(Point2::new(c.x, min.y), Point2::new(max.x, c.y)),
+            Rect::from_corners(Point2::new(min.x, c.y), Point2::new(c.x, max.y)),
+            Rect::from_corners(c, max),
+        ]
+    }
+
+    /// The child that holds all of `rect`, if any.
+    fn child_for(&self, rect: &Rect) -> Option<usize> {
+        self.quadrant_v()
+            .iter()
+            .position(|q| q.contains(rect.min) && q.contains(rect.max))
+    }
+
+    fn insert(&mut self, rect: Rect, item: T, depth: usize) {
+        if let Some(child_v) = &mut self.child_v {
+            let slot = child_v.iter().position(|child| {
+                child.bounds.contains(rect.min) && child.bounds.contains(rect.max)
+            });
+            if let Some(i) = slot {
+                child_v[i].insert(rect, item, depth + 1);
+                return;
+            }
+        }
+        self.item_v.push((rect, item));
+
+        if self.child_v.is_none() && self.item_v.len() > NODE_CAPACITY && depth < MAX_DEPTH {
+            let [a, b, c, d] = self.quadrant_v();
+            self.child_v = Some(Box::new([
+                Node::new(a),
+                Node::new(b),
+                Node::new(c),
+                Node::new(d),
+            ]));
+            for (rect, item) in std::mem::take(&mut self.item_v) {
+                self.insert(rect, item, depth);
+            }
+        }
+    }
+
+    fn remove(&mut self, rect: &Rect, item: &T) -> bool {
+        if let Some(i) = self.item_v.iter().position(|(_, it)| it == item) {
+            self.item_v.swap_remove(i);
+            return true;
+        }
+        match &mut self.child_v {
+            Some(child_v) => child_v
+                .iter_mut()
+                .filter(|child| child.bounds.intersects(rect))
+                .any(|child| child.remove(rect, item)),
+            None => false,
+        }
+    }
+
+    fn query<'a>(&'a self, rect: &Rect, out: &mut Vec<&'a T>) {
+        for (bounds, item) in &self.item_v {
+            if bounds.intersects(rect) {
+                out.push(item);
+            }
+        }
+        if let Some(child_v) = &self.child_v {
+            for child in child_v.iter() {
+                if child.bounds.intersects(rect) {
+                    child.query(rect, out);
+                }
+            }
+        }
+    }
+}
+
+// Public
+/// Quadtree over the bounds of items on an unbounded plane.
+///
+/// The root doubles towards anything inserted outside of it, so the tree
+/// covers whatever part of the board is in use.
+pub struct Index<T> {
+    root: Option<Node<T>>,
+    len: usize,
+}
+
+impl<T: PartialEq> Default for Index<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PartialEq> Index<T> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    pub fn insert(&mut self, rect: Rect, item: T) {
+        let root = self.root.get_or_insert_with(|| {
+            let half = rect.width().max(rect.height()).max(1.0);
+            Node::new(Rect::from_corners(
+                rect.center() - Vector2::new(half, half),
+                rect.center() + Vector2::new(half, half),
+            ))
+        });
+        while !(root.bounds.contains(rect.min) && root.bounds.contains(rect.max)) {
+            Self::grow(root, &rect);
+        }
+        root.insert(rect, item, 0);
+        self.len += 1;
+    }
+
+    /// Remove `item`, which was inserted with `rect`.
+    pub fn remove(&mut self, rect: &Rect, item: &T) -> bool {
+        let removed = match &mut self.root {
+            Some(root) => root.remove(rect, item),
+            None => false,
+        };
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Every item whose bounds touch `rect`.
+    pub fn query(&self, rect: &Rect) -> Vec<&T> {
+        let mut out = Vec::new();
+        if let Some(root) = &self.root {
+            root.query(rect, &mut out);
+        }
+        out
+    }
+
+    /// Double the root towards `rect`, keeping the old root as one quadrant.
+    fn grow(root: &mut Node<T>, rect: &Rect) {
+        let old = &root.bounds;
+        let size = Vector2::new(old.width(), old.height());
+        let left = rect.min.x < old.min.x;
+        let up = rect.min.y < old.min.y;
+        let min = Point2::new(
+            if left { old.min.x - size.x } else { old.min.x },
+            if up { old.min.y - size.y } else { old.min.y },
+        );
+        let bounds = Rect::from_corners(min, min + size * 2.0);
+
+        let old_root = std::mem::replace(root, Node::new(bounds));
+        let slot = root.child_for(&old_root.bounds);
+        match slot {
+            Some(i) => {
+                let quadrant_v = root.quadrant_v();
+                let mut child_v = quadrant_v.map(Node::new);
+                child_v[i] = old_root;
+                root.child_v = Some(Box::new(child_v));
+            }
+            None => {
+                // Rounding kept the old root from lining up with a quadrant.
+                for (rect, item) in Self::drain(old_root) {
+                    root.insert(rect, item, 0);
+                }
+            }
+        }
+    }
+
+    fn drain(node: Node<T>) -> Vec<(Rect, T)> {
+        let mut item_v = node.item_v;
+        if let Some(child_v) = node.child_v {
+            let [a, b, c, d] = *child_v;
+            for child in [a, b, c, d] {
+                item_v.extend(Self::drain(child));
+            }
+        }
+        item_v
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Numbers in [0, 1) from a xorshift, so every run sees the same ones.
+    struct Noise(u32);
+
+    impl Noise {
+        fn next(&mut self) -> f32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            (self.0 >> 8) as f32 / (1 << 24) as f32
+        }
+
+        fn rect(&mut self, spread: f32, max_size: f32) -> Rect {
+            let min = Point2::new((self.next() - 0.5) * spread, (self.next() - 0.5) * spread);
+            let size = Vector2::new(self.next() * max_size, self.next() * max_size);
+            Rect::from_corners(min, min + size)
+        }
+    }
+
+    fn square(x: f32, y: f32, side: f32) -> Rect {
+        Rect::from_corners(Point2::new(x, y), Point2::new(x + side, y + side))
+    }
+
+    /// Ids of `item_v` touching `rect`, by looking at every one, sorted.
+    fn scan(item_v: &[(Rect, usize)], rect: &Rect) -> Vec<usize> {
+        let mut id_v: Vec<usize> = item_v
+            .iter()
+            .filter(|(bounds, _)| bounds.intersects(rect))
+            .map(|(_, id)| *id)
+            .collect();
+        id_v.sort_unstable();
+        id_v
+    }
+
+    fn query(index: &Index<usize>, rect: &Rect) -> Vec<usize> {
+        let mut id_v: Vec<usize> = index.query(rect).into_iter().copied().collect();
+        id_v.sort_unstable();
+        id_v
+    }
+
+    #[test]
+    fn grows_towards_items_outside() {
+        let mut index = Index::new();
+        let rect_v = [
+            square(0.0, 0.0, 1.0),
+            square(1000.0, -1000.0, 5.0),
+            square(-5000.0, 3000.0, 2.0),
+            square(-0.5, -0.5, 20000.0),
+        ];
+        for (id, rect) in rect_v.iter().enumerate() {
+            index.insert(*rect, id);
+        }
+        assert_eq!(index.len(), rect_v.len());
+        let bounds = index.root.as_ref().unwrap().bounds;
+        for (id, rect) in rect_v.iter().enumerate() {
+            assert!(bounds.contains(rect.min) && bounds.contains(rect.max));
+            assert!(query(&index, &rect.inflate(-0.1)).contains(&id));
+        }
+        assert_eq!(query(&index, &square(500.0, 500.0, 1.0)), vec![3]);
+    }
+
+    #[test]
+    fn removes_from_split_nodes() {
+        let mut index = Index::new();
+        let mut item_v = Vec::new();
+        for i in 0..100 {
+            let rect = square((i % 10) as f32 * 10.0, (i / 10) as f32 * 10.0, 4.0);
+            index.insert(rect, i);
+            item_v.push((rect, i));
+        }
+        assert!(index.root.as_ref().unwrap().child_v.is_some());
+
+        let (gone_v, kept_v): (Vec<_>, Vec<_>) = item_v.into_iter().partition(|(_, i)| i % 3 == 0);
+        for (rect, id) in &gone_v {
+            assert!(index.remove(rect, id));
+            assert!(!index.remove(rect, id));
+        }
+        assert_eq!(index.len(), kept_v.len());
+        let all = square(-10.0, -10.0, 120.0);
+        assert_eq!(query(&index, &all), scan(&kept_v, &all));
+        for (rect, id) in &gone_v {
+            assert!(!query(&index, rect).contains(id));
+        }
+    }
+
+    #[test]
+    fn queries_match_a_scan() {
+        let mut noise = Noise(0x9e37_79b9);
+        let mut index = Index::new();
+        let mut item_v = Vec::new();
+        for id in 0..600 {
+            // Mostly small, like strokes, with a few that span a lot of the board.
+            let max_size = if id % 50 == 0 { 800.0 } else { 20.0 };
+            let rect = noise.rect(2000.0, max_size);
+            index.insert(rect, id);
+            item_v.push((rect, id));
+        }
+        for _ in 0..100 {
+            let rect = noise.rect(2400.0, 300.0);
+            assert_eq!(query(&index, &rect), scan(&item_v, &rect));
+        }
+
+        item_v.retain(|(rect, id)| id % 4 != 1 || !index.remove(rect, id));
+        assert_eq!(index.len(), item_v.len());
+        for _ in 0..100 {
+            let rect = noise.rect(2400.0, 300.0);
+            assert_eq!(query(&index, &rect), scan(&item_v, &rect));
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use super::Edge;
+
+/// Milliseconds a laser stroke takes to fade once finished.
+const FADE_MS: f64 = 1200.0;
+
+// Public
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InkKind {
+    /// Glows, then fades away.
+    Laser,
+    /// Stays until the view moves.
+    Ephemeral,
+}
+
+/// A stroke shown while presenting and never committed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ink {
+    pub kind: InkKind,
+    pub edge: Edge,
+    /// When it was finished, in milliseconds since the page loaded, or
+    /// `None` while it is still being drawn.
+    pub end: Option<f64>,
+}
+
+impl Ink {
+    /// How much of it still shows at `now`, in [0, 1].
+    pub fn opacity(&self, now: f64) -> f32 {
+        match (self.kind, self.end) {
+            (InkKind::Laser, Some(end)) => (1.0 - (now - end) / FADE_MS).clamp(0.0, 1.0) as f32,
+            _ => 1.0,
+        }
+    }
+
+    /// True if it is a finished laser, still fading.
+    pub fn is_fading(&self) -> bool {
+        self.kind == InkKind::Laser && self.end.is_some()
+    }
+
+    pub fn is_gone(&self, now: f64) -> bool {
+        self.opacity(now) <= 0.0
+    }
+}
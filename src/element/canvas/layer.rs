@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use super::new_id;
+
+// Public
+/// Layer of the edges drawn before there were layers.
+pub const DEFAULT_LAYER_ID: &str = "default";
+
+/// A sheet of edges drawn above the ones before it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    pub id: String,
+    pub name: String,
+    pub visible: bool,
+    /// Locked layers can't be drawn on, erased or selected.
+    pub locked: bool,
+    /// In [0, 1].
+    pub opacity: f32,
+}
+
+impl Layer {
+    pub fn new(name: String) -> Self {
+        Self {
+            id: new_id(),
+            name,
+            visible: true,
+            locked: false,
+            opacity: 1.0,
+        }
+    }
+
+    /// True if edges can be added to or taken from this layer.
+    pub fn is_editable(&self) -> bool {
+        self.visible && !self.locked
+    }
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Self {
+            id: DEFAULT_LAYER_ID.to_string(),
+            ..Self::new("Layer 1".to_string())
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use cgmath::{InnerSpace, Point2};
+use painting::point::Point;
+
+/// How far, in world units, the first simplified level may stray from the edge.
+const BASE_TOLERANCE: f32 = 0.5;
+/// Each level strays twice as far as the one before; this many at most.
+const MAX_LEVEL: usize = 16;
+
+fn pos(pt: &Point) -> Point2<f32> {
+    Point2::new(pt.pos.x, pt.pos.y)
+}
+
+/// Distance from `p` to the segment from `a` to `b`.
+pub(super) fn segment_distance(p: Point2<f32>, a: Point2<f32>, b: Point2<f32>) -> f32 {
+    let ab = b - a;
+    let len2 = ab.magnitude2();
+    if len2 == 0.0 {
+        return (p - a).magnitude();
+    }
+    let t = ((p - a).dot(ab) / len2).clamp(0.0, 1.0);
+    (p - (a + ab * t)).magnitude()
+}
+
+/// The positions among `i_v` that Douglas-Peucker keeps within `tolerance`.
+pub(super) fn simplify(point_v: &[Point], i_v: &[usize], tolerance: f32) -> Vec<usize> {
+    if i_v.len() <= 2 {
+        return i_v.to_vec();
+    }
+    let mut keep_v = vec![false; i_v.len()];
+    keep_v[0] = true;
+    keep_v[i_v.len() - 1] = true;
+    let mut span_v = vec![(0, i_v.len() - 1)];
+    while let Some((first, last)) = span_v.pop() {
+        let (a, b) = (pos(&point_v[i_v[first]]), pos(&point_v[i_v[last]]));
+        let farthest = (first + 1..last)
+            .map(|k| (k, segment_distance(pos(&point_v[i_v[k]]), a, b)))
+            .max_by(|x, y| x.1.total_cmp(&y.1));
+        if let Some((k, d)) = farthest {
+            if d > tolerance {
+                keep_v[k] = true;
+                span_v.push((first, k));
+                span_v.push((k, last));
+            }
+        }
+    }
+    i_v.iter()
+        .zip(keep_v)
+        .filter_map(|(i, keep)| keep.then_some(*i))
+        .collect()
+}
+
+// Public
+/// Coarser and coarser versions of one edge, made as they are asked for.
+///
+/// Level 0 is every point; level `k` strays about `BASE_TOLERANCE * 2^(k-1)`
+/// world units from it, and is made from level `k - 1`.
+#[derive(Clone, Debug, Default)]
+pub struct Lod {
+    /// Positions in the edge's `point_v` of the points kept, by level.
+    level_v: Vec<Vec<usize>>,
+}
+
+impl Lod {
+    /// Positions of the points of `point_v` to draw when straying up to
+    /// `tolerance` world units from the edge is fine.
+    pub fn point_i_v(&mut self, point_v: &[Point], tolerance: f32) -> &[usize] {
+        if self.level_v.is_empty() {
+            self.level_v.push((0..point_v.len()).collect());
+        }
+        let level = if tolerance < BASE_TOLERANCE {
+            0
+        } else {
+            ((tolerance / BASE_TOLERANCE).log2().floor() as usize + 1).min(MAX_LEVEL)
+        };
+        while self.level_v.len() <= level {
+            let last = self.level_v.last().unwrap();
+            // Nothing is left to take out.
+            if last.len() <= 2 {
+                break;
+            }
+            let k = self.level_v.len();
+            let next = simplify(point_v, last, BASE_TOLERANCE * 2f32.powi(k as i32 - 1));
+            self.level_v.push(next);
+        }
+        &self.level_v[level.min(self.level_v.len() - 1)]
+    }
+}
@@ -0,0 +1,21 @@
+use super::{new_id, Rect};
+
+// Public
+/// A named part of the board, exported as a page of its own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageFrame {
+    pub id: String,
+    pub name: String,
+    /// In world coordinates.
+    pub rect: Rect,
+}
+
+impl PageFrame {
+    pub fn new(name: String, rect: Rect) -> Self {
+        Self {
+            id: new_id(),
+            name,
+            rect,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use cgmath::Point2;
+use serde::{Deserialize, Serialize};
+
+use super::Rect;
+
+/// Horizontal step of an isometric line per unit of height.
+const ISOMETRIC_SLOPE: f32 = 0.577_350_26;
+
+/// `step` apart, every multiple of it from `min` to `max`.
+fn multiple_v(min: f32, max: f32, step: f32) -> impl Iterator<Item = f32> {
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(move |k| k as f32 * step)
+}
+
+// Public
+/// Pattern printed on the paper.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum PaperKind {
+    #[default]
+    Blank,
+    Grid,
+    Dots,
+    /// Ruled lines, for handwriting.
+    Lined,
+    /// Triangles of equal sides.
+    Isometric,
+}
+
+impl PaperKind {
+    pub const ALL: [Self; 5] = [
+        Self::Blank,
+        Self::Grid,
+        Self::Dots,
+        Self::Lined,
+        Self::Isometric,
+    ];
+
+    /// Name used when storing the paper.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Blank => "blank",
+            Self::Grid => "grid",
+            Self::Dots => "dots",
+            Self::Lined => "lined",
+            Self::Isometric => "isometric",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// What a board is drawn on.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Paper {
+    pub kind: PaperKind,
+    pub color: [f32; 4],
+    pub line_color: [f32; 4],
+    /// World units between lines or dots.
+    pub spacing: f32,
+}
+
+impl Default for Paper {
+    fn default() -> Self {
+        Self {
+            kind: PaperKind::Blank,
+            color: [1.0, 1.0, 1.0, 1.0],
+            line_color: [0.75, 0.8, 0.9, 1.0],
+            spacing: 32.0,
+        }
+    }
+}
+
+impl Paper {
+    /// A point of the pattern next to `pos`: a crossing, a dot, or the
+    /// closest ruled line. None on blank paper.
+    pub fn nearest(&self, pos: Point2<f32>) -> Option<Point2<f32>> {
+        let s = self.spacing.max(1.0);
+        let round = |v: f32, step: f32| (v / step).round() * step;
+        match self.kind {
+            PaperKind::Blank => None,
+            PaperKind::Grid | PaperKind::Dots => {
+                Some(Point2::new(round(pos.x, s), round(pos.y, s)))
+            }
+            PaperKind::Lined => Some(Point2::new(pos.x, round(pos.y, s))),
+            PaperKind::Isometric => {
+                // Every other row is shifted by half a side.
+                let h = s / ISOMETRIC_SLOPE / 2.0;
+                let row = (pos.y / h).round();
+                let shift = if row as i64 % 2 == 0 { 0.0 } else { s / 2.0 };
+                Some(Point2::new(round(pos.x - shift, s) + shift, row * h))
+            }
+        }
+    }
+
+    /// Lines of the pattern crossing `rect`, in world coordinates; a dot is
+    /// a line that starts where it ends.
+    ///
+    /// The spacing doubles until it is at least `min_spacing`, so zooming
+    /// out thins the pattern instead of filling the screen with it.
+    pub fn mark_v(&self, rect: &Rect, min_spacing: f32) -> Vec<[Point2<f32>; 2]> {
+        let mut s = self.spacing.max(1.0);
+        while s < min_spacing {
+            s *= 2.0;
+        }
+        let (min, max) = (rect.min, rect.max);
+        let horizontal = |y: f32| [Point2::new(min.x, y), Point2::new(max.x, y)];
+        match self.kind {
+            PaperKind::Blank => Vec::new(),
+            PaperKind::Grid => multiple_v(min.x, max.x, s)
+                .map(|x| [Point2::new(x, min.y), Point2::new(x, max.y)])
+                .chain(multiple_v(min.y, max.y, s).map(horizontal))
+                .collect(),
+            PaperKind::Dots => multiple_v(min.y, max.y, s)
+                .flat_map(|y| multiple_v(min.x, max.x, s).map(move |x| Point2::new(x, y)))
+                .map(|pt| [pt, pt])
+                .collect(),
+            PaperKind::Lined => multiple_v(min.y, max.y, s).map(horizontal).collect(),
+            PaperKind::Isometric => {
+                // Rows `h` apart, crossed by lines at 60° either way, `s` apart along a row.
+                let h = s / ISOMETRIC_SLOPE / 2.0;
+                let mut mark_v: Vec<[Point2<f32>; 2]> =
+                    multiple_v(min.y, max.y, h).map(horizontal).collect();
+                // Each line is x = c ± y * slope; c steps by s.
+                for sign in [1.0, -1.0] {
+                    let dx = [min.y, max.y].map(|y| sign * y * ISOMETRIC_SLOPE);
+                    let (c_min, c_max) = (min.x - dx[0].max(dx[1]), max.x - dx[0].min(dx[1]));
+                    for c in multiple_v(c_min, c_max, s) {
+                        mark_v.push([Point2::new(c + dx[0], min.y), Point2::new(c + dx[1], max.y)]);
+                    }
+                }
+                mark_v
+            }
+        }
+    }
+}
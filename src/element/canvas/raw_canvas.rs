@@ -1,8 +1,6 @@
-use std::io;
-
 use painting::AsCanvas;
+use wasm_bindgen::JsCast;
 use web_sys::HtmlCanvasElement;
-use wgpu::SurfaceError;
 use winit::{
     dpi::PhysicalSize,
     event::{Event, WindowEvent},
@@ -11,68 +9,159 @@ use winit::{
     window::{Window, WindowBuilder},
 };
 
+use crate::{err, util};
+
+use super::{counted_canvas::CountedCanvas, flat_canvas::FlatCanvas};
+
+/// Largest side of the drawing surface, which WebGL2 is sure to take.
+const MAX_SIDE: u32 = 2048;
+/// Most pixels the surface gets, to bound memory on very large screens.
+const MAX_PIXEL_N: u32 = 2048 * 1536;
+
+/// Surface size for `client` CSS pixels at `ratio` device pixels each,
+/// scaled down evenly to stay within the caps.
+pub(super) fn surface_size(client: PhysicalSize<u32>, ratio: f64) -> PhysicalSize<u32> {
+    let w = (client.width as f64 * ratio).max(1.0);
+    let h = (client.height as f64 * ratio).max(1.0);
+    let k = (MAX_SIDE as f64 / w)
+        .min(MAX_SIDE as f64 / h)
+        .min((MAX_PIXEL_N as f64 / (w * h)).sqrt())
+        .min(1.0);
+    PhysicalSize::new(
+        ((w * k).round() as u32).max(1),
+        ((h * k).round() as u32).max(1),
+    )
+}
+
+pub(super) fn device_pixel_ratio() -> f64 {
+    web_sys::window().map_or(1.0, |window| window.device_pixel_ratio())
+}
+
+/// `painting` on the GPU behind `window`.
+async fn create_gpu(window: &Window) -> err::Result<painting::Canvas> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        dx12_shader_compiler: Default::default(),
+    });
+    log::info!("instance: {:?}", instance);
+
+    let surface = unsafe { instance.create_surface(window) }
+        .map_err(|e| err::Error::Gpu(format!("failed to create surface: {e}")))?;
+    log::info!("surface: {:?}", surface);
+
+    painting::Canvas::create(&instance, surface, window.inner_size())
+        .await
+        .map_err(|e| err::Error::Gpu(format!("failed to set up the GPU: {e}")))
+}
+
 // Public
+/// A new canvas laid over `under`, at its size, letting the pointer through
+/// to it. `under` must sit in an element positioned for it.
+pub(super) fn overlay(under: &HtmlCanvasElement) -> err::Result<HtmlCanvasElement> {
+    let html_canvas = util::get_document()
+        .ok_or(err::Error::Gpu("failed to get document".to_string()))?
+        .create_element("canvas")
+        .map_err(util::map_js_error)?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|_| err::Error::Gpu("failed to create canvas".to_string()))?;
+    html_canvas.set_class_name(&under.class_name());
+    html_canvas
+        .style()
+        .set_css_text("position: absolute;left: 0;top: 0;pointer-events: none;");
+    html_canvas.set_width(under.width());
+    html_canvas.set_height(under.height());
+    under
+        .after_with_node_1(&html_canvas)
+        .map_err(util::map_js_error)?;
+    Ok(html_canvas)
+}
+
+/// Draws on the page, on the GPU or with the Canvas 2D API.
 pub struct RawCanvas {
-    canvas: painting::Canvas,
+    pub canvas: CountedCanvas,
     html_canvas: HtmlCanvasElement,
-    pub window: Window,
-    pub pen: painting::point::Pen,
+    window: Window,
 }
 
 impl RawCanvas {
+    /// Draw through `painting` on the GPU, or with the Canvas 2D API if the
+    /// GPU can't be had. Fails only if neither works.
     pub async fn create(
         html_canvas: HtmlCanvasElement,
         event_loop: &EventLoop<()>,
-    ) -> io::Result<Self> {
-        let sz = PhysicalSize::new(
-            2048,
-            2048,
+    ) -> err::Result<Self> {
+        let client = PhysicalSize::new(
+            html_canvas.client_width() as u32,
+            html_canvas.client_height() as u32,
         );
+        let size = surface_size(client, device_pixel_ratio());
+
         let window = WindowBuilder::new()
             .with_canvas(Some(html_canvas.clone()))
             .build(&event_loop)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-        window.set_inner_size(sz);
+            .map_err(|e| err::Error::Other(e.to_string()))?;
+        window.set_inner_size(size);
+        // winit sizes the canvas with CSS too, which would fight the page's layout.
         html_canvas.style().set_css_text("");
 
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            dx12_shader_compiler: Default::default(),
-        });
-        log::info!("instance: {:?}", instance);
-
-        let surface = unsafe { instance.create_surface(&window) }
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-        log::info!("surface: {:?}", surface);
-
-        let canvas = painting::Canvas::create(&instance, surface, window.inner_size()).await?;
-
-        Ok(Self {
-            canvas,
+        let canvas: Box<dyn AsCanvas> = match create_gpu(&window).await {
+            Ok(canvas) => Box::new(canvas),
+            Err(e) => {
+                log::warn!("{e}, falling back to the Canvas 2D API");
+                // A canvas whose WebGL context was asked for has no 2D one.
+                Box::new(FlatCanvas::create(&html_canvas).map_err(|_| e)?)
+            }
+        };
+        let mut this = Self {
+            canvas: CountedCanvas::new(canvas),
             html_canvas,
             window,
-            pen: painting::point::Pen::default(),
-        })
+        };
+        this.fit();
+        Ok(this)
+    }
+
+    /// Size of the canvas as laid out, in CSS pixels.
+    fn client_size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(
+            self.html_canvas.client_width() as u32,
+            self.html_canvas.client_height() as u32,
+        )
+    }
+
+    /// Size the surface to the canvas as laid out, in device pixels, and
+    /// match its aspect. Returns the size of the surface.
+    pub fn fit(&mut self) -> PhysicalSize<u32> {
+        let client = self.client_size();
+        let size = surface_size(client, device_pixel_ratio());
+        if size != *self.canvas.get_size() {
+            self.window.set_inner_size(size);
+            self.html_canvas.style().set_css_text("");
+            self.canvas.resize(size);
+        }
+        self.canvas
+            .set_aspect(client.width.max(1) as f32 / client.height.max(1) as f32);
+        size
     }
 
     pub fn on_event(
         &mut self,
-        event: Event<()>,
+        mut event: Event<()>,
         _target: &EventLoopWindowTarget<()>,
         control_flow: &mut ControlFlow,
     ) {
         match event {
             Event::WindowEvent {
-                ref event,
+                ref mut event,
                 window_id,
             } if window_id == self.window.id() => match event {
-                WindowEvent::Resized(physical_size) => {
-                    self.resize(*physical_size);
-                    let _ = self.render();
+                WindowEvent::Resized(_) => {
+                    self.fit();
+                    let _ = self.canvas.render();
                 }
                 WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                    self.resize(**new_inner_size);
-                    let _ = self.render();
+                    **new_inner_size = self.fit();
+                    let _ = self.canvas.render();
                 }
                 WindowEvent::CloseRequested | WindowEvent::Destroyed => {
                     *control_flow = ControlFlow::Exit
@@ -80,61 +169,9 @@ impl RawCanvas {
                 _ => {}
             },
             Event::RedrawRequested(window_id) if window_id == self.window.id() => {
-                let sz = PhysicalSize::new(
-                    self.html_canvas.client_width() as u32,
-                    self.html_canvas.client_height() as u32,
-                );
-                self.canvas
-                    .set_aspect((sz.width as f32) / (sz.height as f32));
-                let _ = self.render();
+                let _ = self.canvas.render();
             }
             _ => {}
         }
     }
 }
-
-impl painting::AsCanvas for RawCanvas {
-    fn render(&mut self) -> Result<(), SurfaceError> {
-        self.canvas.render()
-    }
-
-    fn push_point(&mut self, pt: painting::point::Point) {
-        self.canvas.push_point(pt)
-    }
-
-    fn start_line(&mut self, pt: painting::point::Point) {
-        self.canvas.start_line(pt)
-    }
-
-    fn end_line(&mut self) {
-        self.canvas.end_line()
-    }
-
-    fn cancle_line(&mut self) {
-        self.canvas.cancle_line()
-    }
-
-    fn set_aspect(&mut self, aspect: f32) {
-        self.canvas.set_aspect(aspect)
-    }
-
-    fn clear(&mut self) {
-        self.canvas.clear()
-    }
-
-    fn get_size(&self) -> &PhysicalSize<u32> {
-        self.canvas.get_size()
-    }
-
-    fn resize(&mut self, new_size: PhysicalSize<u32>) {
-        self.canvas.resize(new_size)
-    }
-
-    fn move_content(&mut self, x: f32, y: f32, z: f32) {
-        self.canvas.move_content(x, y, z)
-    }
-
-    fn scacle(&mut self, x: f32, y: f32, z: f32) {
-        self.canvas.scacle(x, y, z)
-    }
-}
@@ -0,0 +1,87 @@
+use cgmath::{Point2, Vector2};
+
+// Public
+/// Axis-aligned rectangle, `min` being the top left corner on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub min: Point2<f32>,
+    pub max: Point2<f32>,
+}
+
+impl Rect {
+    /// The smallest rectangle holding both corners, in any order.
+    pub fn from_corners(a: Point2<f32>, b: Point2<f32>) -> Self {
+        Self {
+            min: Point2::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Point2::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    /// The smallest rectangle holding every point, None if there are none.
+    pub fn bound<I: IntoIterator<Item = Point2<f32>>>(pt_v: I) -> Option<Self> {
+        let mut iter = pt_v.into_iter();
+        let first = iter.next()?;
+        let mut rect = Self {
+            min: first,
+            max: first,
+        };
+        for pt in iter {
+            rect.min.x = rect.min.x.min(pt.x);
+            rect.min.y = rect.min.y.min(pt.y);
+            rect.max.x = rect.max.x.max(pt.x);
+            rect.max.y = rect.max.y.max(pt.y);
+        }
+        Some(rect)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: Point2::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point2::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    pub fn contains(&self, pt: Point2<f32>) -> bool {
+        self.min.x <= pt.x && pt.x <= self.max.x && self.min.y <= pt.y && pt.y <= self.max.y
+    }
+
+    /// Grow by `margin` on every side.
+    pub fn inflate(&self, margin: f32) -> Self {
+        Self {
+            min: self.min - Vector2::new(margin, margin),
+            max: self.max + Vector2::new(margin, margin),
+        }
+    }
+
+    /// Corners clockwise on screen, starting at `min`.
+    pub fn corner_v(&self) -> [Point2<f32>; 4] {
+        [
+            self.min,
+            Point2::new(self.max.x, self.min.y),
+            self.max,
+            Point2::new(self.min.x, self.max.y),
+        ]
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    pub fn center(&self) -> Point2<f32> {
+        Point2::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+        )
+    }
+}
@@ -0,0 +1,93 @@
+use cgmath::{InnerSpace, Point2, Vector2};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::PointerEvent;
+
+use super::Stylus;
+
+/// Mouse speed, in pixels per event, at which the made up force drops to zero.
+const MOUSE_FULL_SPEED: f32 = 10.0;
+
+/// Calls a method of `e` returning a list of pointer events, if the browser has it.
+fn event_list(e: &PointerEvent, method: &str) -> Vec<PointerEvent> {
+    let f = match js_sys::Reflect::get(e, &JsValue::from_str(method)) {
+        Ok(f) => f,
+        Err(_) => return Vec::new(),
+    };
+    let f = match f.dyn_into::<js_sys::Function>() {
+        Ok(f) => f,
+        Err(_) => return Vec::new(),
+    };
+    match f.call0(e) {
+        Ok(list) => js_sys::Array::from(&list)
+            .iter()
+            .filter_map(|e| e.dyn_into::<PointerEvent>().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// Public
+/// One position reported by a pointer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// Movement since the previous sample, in screen pixels.
+    pub movement: Vector2<f32>,
+    pub x: f32,
+    pub y: f32,
+    /// None for a mouse, which has no pressure of its own.
+    pub force: Option<f32>,
+    pub stylus: Stylus,
+    /// Milliseconds since the page loaded.
+    pub time: f64,
+    pub shift: bool,
+}
+
+impl Sample {
+    pub fn from_event(e: &PointerEvent) -> Self {
+        let force = match e.pointer_type().as_str() {
+            "mouse" => None,
+            "touch" => Some(e.pressure() / 10.),
+            _ => Some(e.pressure()),
+        };
+        Self {
+            movement: Vector2::new(e.movement_x() as f32, e.movement_y() as f32),
+            x: e.offset_x() as f32,
+            y: e.offset_y() as f32,
+            force,
+            stylus: Stylus::from_event(e),
+            time: e.time_stamp(),
+            shift: e.shift_key(),
+        }
+    }
+
+    /// Every sample the browser merged into `e`, oldest first.
+    pub fn coalesced(e: &PointerEvent) -> Vec<Self> {
+        let e_v = event_list(e, "getCoalescedEvents");
+        if e_v.is_empty() {
+            return vec![Self::from_event(e)];
+        }
+        e_v.iter().map(Self::from_event).collect()
+    }
+
+    /// Where the browser expects the pointer to be in the next few frames.
+    pub fn predicted(e: &PointerEvent) -> Vec<Self> {
+        event_list(e, "getPredictedEvents")
+            .iter()
+            .map(Self::from_event)
+            .collect()
+    }
+
+    /// Position in screen pixels.
+    pub fn pos(&self) -> Point2<f32> {
+        Point2::new(self.x, self.y)
+    }
+
+    /// The reported force, or one made up from the speed for a mouse.
+    pub fn force(&self) -> f32 {
+        match self.force {
+            Some(force) => force,
+            None => (1. - self.movement.magnitude() / MOUSE_FULL_SPEED).clamp(0., 1.),
+        }
+    }
+}
@@ -0,0 +1,1393 @@
+use std::collections::{HashMap, HashSet};
+
+use cgmath::*;
+use painting::{
+    point::{Pen, Point},
+    AsCanvas,
+};
+use serde::{Deserialize, Serialize};
+use winit::dpi::PhysicalSize;
+
+use crate::metrics;
+
+use super::{
+    counted_canvas::CountedCanvas, hold_ms, lod::Lod, recognize, snap_angle, snap_point, Edge,
+    Guide, Index, Ink, InkKind, Layer, Paper, PaperKind, Rect, Sample, Shape, Snap, Stylus, Tool,
+    Viewport, DEFAULT_LAYER_ID,
+};
+
+/// Distance in pixels within which the eraser picks up an edge.
+const ERASER_RADIUS: f32 = 8.0;
+const SELECTION_COLOR: [f32; 4] = [0.2, 0.5, 1.0, 1.0];
+/// Pixels around the screen within which edges are still drawn, for their width.
+const CULL_MARGIN: f32 = 32.0;
+/// Closest, in pixels, that paper lines get before every other one is left out.
+const MIN_PAPER_SPACING: f32 = 8.0;
+/// Same for dots, which cost a line each.
+const MIN_DOT_SPACING: f32 = 16.0;
+/// Width of paper lines, in pixels.
+const PAPER_LINE_WIDTH: f32 = 1.0;
+/// Pixels within which points snap.
+const SNAP_RADIUS: f32 = 12.0;
+const GUIDE_COLOR: [f32; 4] = [1.0, 0.3, 0.6, 1.0];
+/// Half the size of the marks on snapped points, in pixels.
+const GUIDE_MARK: f32 = 6.0;
+/// Force lines are drawn with, as by a mouse held still.
+const LINE_FORCE: f32 = 1.0;
+/// Pixels an edge may stray from its points when drawn zoomed out.
+const LOD_TOLERANCE: f32 = 0.5;
+/// Pixels below which an edge is drawn as a dot, or not at all if it is thinner too.
+const MIN_EDGE_SIZE: f32 = 1.0;
+/// Milliseconds the pen has to rest at the end of a stroke for it to be taken for a shape.
+const HOLD_MS: f64 = 400.0;
+/// Pixels the pen may wander while resting.
+const HOLD_RADIUS: f32 = 4.0;
+/// Least confidence, in [0, 1], for a stroke to be replaced by a shape.
+const SHAPE_CONFIDENCE: f32 = 0.6;
+const LASER_COLOR: [f32; 4] = [1.0, 0.15, 0.1, 1.0];
+/// Width of the laser, in pixels, and of its glow over that.
+const LASER_WIDTH: f32 = 3.0;
+const GLOW_RATIO: f32 = 4.0;
+const GLOW_OPACITY: f32 = 0.25;
+
+/// Drawing order and layer of every layer the owner knows, by id.
+fn layer_m(layer_v: &[Layer]) -> HashMap<&str, (usize, &Layer)> {
+    layer_v
+        .iter()
+        .enumerate()
+        .map(|(rank, layer)| (layer.id.as_str(), (rank, layer)))
+        .collect()
+}
+
+/// True if edges on layer `id` can be erased or selected. Unknown layers can.
+fn is_editable(layer_m: &HashMap<&str, (usize, &Layer)>, id: &str) -> bool {
+    layer_m.get(id).is_none_or(|(_, layer)| layer.is_editable())
+}
+
+/// Drawing order and position in `edge_v` of the shown edges among `i_v`, sorted.
+fn order(
+    layer_m: &HashMap<&str, (usize, &Layer)>,
+    edge_v: &[Edge],
+    i_v: Vec<usize>,
+) -> Vec<(usize, usize)> {
+    let mut order_v: Vec<(usize, usize)> = i_v
+        .into_iter()
+        .filter_map(|i| match layer_m.get(edge_v[i].layer_id.as_str()) {
+            Some((_, layer)) if !layer.visible => None,
+            Some((rank, _)) => Some((*rank, i)),
+            None => Some((0, i)),
+        })
+        .collect();
+    order_v.sort_unstable();
+    order_v
+}
+
+fn opacity(layer_m: &HashMap<&str, (usize, &Layer)>, id: &str) -> f32 {
+    layer_m.get(id).map_or(1.0, |(_, layer)| layer.opacity)
+}
+
+/// Bring `edge_v` to the list `Delta::edges` was made from.
+fn apply_edges(edge_v: &mut Vec<Edge>, gone_v: Vec<String>, put_v: Vec<(usize, Edge)>) {
+    let gone_v: HashSet<String> = gone_v.into_iter().collect();
+    edge_v.retain(|edge| !gone_v.contains(&edge.id));
+    for (i, edge) in put_v {
+        edge_v.insert(i.min(edge_v.len()), edge);
+    }
+}
+
+enum Command {
+    None,
+    Paint,
+    Erase,
+    Select,
+    Line,
+    /// Drawing `Tool::Laser` or `Tool::Ephemeral` ink.
+    Ink(InkKind),
+    Move,
+}
+
+/// Work held back until the line being drawn is done.
+enum Deferred {
+    Refresh,
+    Sync,
+}
+
+// Public
+/// What the pointer and the keyboard did over the canvas.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Down(Sample),
+    /// The pointer was let go, at this many milliseconds since the page loaded.
+    Up(f64),
+    /// Coalesced samples of one pointer event, then the predicted ones.
+    Move(Vec<Sample>, Vec<Sample>),
+    /// Zoom factor and the pixel to zoom around.
+    Wheel(f32, f32, f32),
+    /// Space was held down, for moving, or let go.
+    Moving(bool),
+}
+
+/// A change to what the owner of the canvas shows.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Delta {
+    /// Ids of the edges gone or changed, then the edges that came or
+    /// changed with their positions in the new list, in order.
+    Edges {
+        gone_v: Vec<String>,
+        put_v: Vec<(usize, Edge)>,
+    },
+    /// Bottom layer first.
+    Layers(Vec<Layer>),
+    /// Layer new edges go on.
+    LayerId(String),
+    Selected(Vec<String>),
+    Tool(Tool),
+    Paper(Paper),
+    Snap(Snap),
+    Viewport(Viewport),
+    /// Laser and ephemeral strokes of other users, shown over the board.
+    PeerInks(Vec<Ink>),
+    /// Size of the canvas as laid out, in CSS pixels.
+    Size(Vector2<f32>),
+}
+
+impl Delta {
+    /// What turns `old_v` into `new_v`, if they differ.
+    ///
+    /// Edges that stay keep their order; if they didn't, every edge goes
+    /// and comes back.
+    pub fn edges(old_v: &[Edge], new_v: &[Edge]) -> Option<Self> {
+        let old_m: HashMap<&str, (usize, &Edge)> = old_v
+            .iter()
+            .enumerate()
+            .map(|(i, edge)| (edge.id.as_str(), (i, edge)))
+            .collect();
+        let new_id_v: HashSet<&str> = new_v.iter().map(|edge| edge.id.as_str()).collect();
+        let mut gone_v: Vec<String> = old_v
+            .iter()
+            .filter(|edge| !new_id_v.contains(edge.id.as_str()))
+            .map(|edge| edge.id.clone())
+            .collect();
+        let mut put_v = Vec::new();
+        let mut last = None;
+        for (i, edge) in new_v.iter().enumerate() {
+            match old_m.get(edge.id.as_str()) {
+                Some((old_i, old)) if *old == edge => {
+                    if last.is_some_and(|last| last > *old_i) {
+                        let gone_v = old_v.iter().map(|edge| edge.id.clone()).collect();
+                        let put_v = new_v.iter().cloned().enumerate().collect();
+                        return Some(Self::Edges { gone_v, put_v });
+                    }
+                    last = Some(*old_i);
+                }
+                Some(_) => {
+                    gone_v.push(edge.id.clone());
+                    put_v.push((i, edge.clone()));
+                }
+                None => put_v.push((i, edge.clone())),
+            }
+        }
+        if gone_v.is_empty() && put_v.is_empty() {
+            return None;
+        }
+        Some(Self::Edges { gone_v, put_v })
+    }
+}
+
+/// What the canvas tells its owner.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Out {
+    Commit(Edge),
+    /// An edge the eraser touched.
+    Erase(String),
+    /// The edges inside a selection rectangle.
+    Select(Vec<String>),
+    /// Panning, zooming or resizing changed the viewport.
+    Viewport(Viewport),
+    /// A laser or ephemeral stroke, once every frame while it is drawn and
+    /// once more when it is finished.
+    Ink(Ink),
+    /// Ephemeral ink was wiped, as the view moved away.
+    ClearInk,
+    /// Where the pointer is over the board, in world space.
+    Pointer(Point2<f32>),
+    /// A frame was rendered in `ms` milliseconds.
+    Frame {
+        ms: f32,
+        edge_n: usize,
+        point_n: usize,
+        upload_bytes: usize,
+    },
+}
+
+/// The board as `painting` draws it: strokes, culling, levels of detail
+/// and projection to the screen.
+///
+/// It keeps its own copy of what the owner shows, kept up by `Delta`s, and
+/// turns `Input` into lines. It runs on the page or in the render worker,
+/// where it draws on the canvas of its own, and tells the owner what it has
+/// to know as `Out`s.
+pub struct Scene {
+    pen: Pen,
+    edge_v: Vec<Edge>,
+    layer_v: Vec<Layer>,
+    layer_id: String,
+    selected_id_v: Vec<String>,
+    tool: Tool,
+    paper: Paper,
+    snap: Snap,
+    last_edge: Edge,
+    /// Number of predicted points drawn after `last_edge`.
+    tail_len: usize,
+    /// Opacity of the layer `last_edge` goes on.
+    paint_opacity: f32,
+    /// When `last_edge` started, in milliseconds since the page loaded.
+    paint_start: f64,
+    erased_id_v: Vec<String>,
+    /// Where the selection rectangle started, in screen pixels.
+    select_from: Point2<f32>,
+    select_to: Point2<f32>,
+    /// Ends of the line being drawn, in world space, and what they snapped to.
+    line_from: (Point2<f32>, Guide),
+    line_to: (Point2<f32>, Guide),
+    /// The preview of the line is open on the GPU.
+    line_drawn: bool,
+    viewport: Viewport,
+    /// Bounds of every edge in `edge_v`, keyed by id.
+    index: Index<String>,
+    /// Position in `edge_v` and bounds, by id.
+    edge_m: HashMap<String, (usize, Rect)>,
+    /// Simplified versions of the edges drawn zoomed out, by id.
+    lod_m: HashMap<String, Lod>,
+    /// Laser and ephemeral strokes still showing, oldest first.
+    ink_v: Vec<Ink>,
+    /// Those of other users.
+    peer_ink_v: Vec<Ink>,
+    /// Ink was drawn since it was last told.
+    ink_untold: bool,
+    /// Ids of the edges on the GPU.
+    drawn_id_v: HashSet<String>,
+    /// Drawing order of the highest layer on the GPU; lines only go on top.
+    top_rank: usize,
+    deferred: Option<Deferred>,
+    /// Everything has to be drawn again in the next frame.
+    stale: bool,
+    /// Something was drawn since the last frame.
+    dirty: bool,
+    enabled_moving: bool,
+    cmd: Command,
+    out_v: Vec<Out>,
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self {
+            pen: Pen::default(),
+            edge_v: Vec::new(),
+            layer_v: Vec::new(),
+            layer_id: DEFAULT_LAYER_ID.to_string(),
+            selected_id_v: Vec::new(),
+            tool: Tool::default(),
+            paper: Paper::default(),
+            snap: Snap::default(),
+            last_edge: Edge::new(),
+            tail_len: 0,
+            paint_opacity: 1.0,
+            paint_start: 0.0,
+            erased_id_v: Vec::new(),
+            select_from: Point2::new(0.0, 0.0),
+            select_to: Point2::new(0.0, 0.0),
+            line_from: (Point2::new(0.0, 0.0), Guide::None),
+            line_to: (Point2::new(0.0, 0.0), Guide::None),
+            line_drawn: false,
+            viewport: Viewport::default(),
+            index: Index::new(),
+            edge_m: HashMap::new(),
+            lod_m: HashMap::new(),
+            ink_v: Vec::new(),
+            peer_ink_v: Vec::new(),
+            ink_untold: false,
+            drawn_id_v: HashSet::new(),
+            top_rank: 0,
+            deferred: None,
+            stale: false,
+            dirty: false,
+            enabled_moving: false,
+            cmd: Command::None,
+            out_v: Vec::new(),
+        }
+    }
+
+    /// What the owner has to hear of since the last call.
+    pub fn take_out_v(&mut self) -> Vec<Out> {
+        std::mem::take(&mut self.out_v)
+    }
+
+    /// True if there is something for the next frame to render.
+    pub fn wants_frame(&self) -> bool {
+        self.stale || self.dirty
+    }
+
+    pub fn apply(&mut self, canvas: &mut CountedCanvas, delta: Delta) {
+        match delta {
+            Delta::Edges { gone_v, put_v } => {
+                // Changed edges come back under the same id, with nothing of the old kept.
+                for id in &gone_v {
+                    self.forget(id);
+                }
+                apply_edges(&mut self.edge_v, gone_v, put_v);
+                self.sync_index();
+                self.sync(canvas);
+            }
+            Delta::Layers(layer_v) => {
+                self.layer_v = layer_v;
+                self.refresh();
+            }
+            Delta::LayerId(layer_id) => self.layer_id = layer_id,
+            Delta::Selected(selected_id_v) => {
+                self.selected_id_v = selected_id_v;
+                self.refresh();
+            }
+            Delta::Tool(tool) => self.tool = tool,
+            Delta::Paper(paper) => {
+                self.paper = paper;
+                self.refresh();
+            }
+            Delta::Snap(snap) => self.snap = snap,
+            Delta::Viewport(viewport) => {
+                self.move_view(Viewport {
+                    size: self.viewport.size,
+                    ..viewport
+                });
+                self.refresh();
+            }
+            Delta::PeerInks(peer_ink_v) => {
+                self.peer_ink_v = peer_ink_v;
+                self.refresh();
+            }
+            Delta::Size(size) => {
+                if self.viewport.size != size {
+                    self.viewport.size = size;
+                    self.out_v.push(Out::Viewport(self.viewport));
+                }
+                self.refresh();
+            }
+        }
+    }
+
+    pub fn input(&mut self, canvas: &mut CountedCanvas, input: InputEvent) {
+        match input {
+            InputEvent::Down(sample) => self.down(canvas, sample),
+            InputEvent::Up(release) => self.up(canvas, release),
+            InputEvent::Move(sample_v, predicted_v) => self.drag(canvas, &sample_v, &predicted_v),
+            InputEvent::Wheel(s, x, y) => {
+                if let Command::None = self.cmd {
+                    let viewport = self
+                        .viewport
+                        .zoom_at(self.viewport.zoom * s, Point2::new(x, y));
+                    self.set_viewport(viewport);
+                }
+            }
+            InputEvent::Moving(enabled_moving) => self.enabled_moving = enabled_moving,
+        }
+    }
+
+    /// Render what changed since the last frame. `now` is in milliseconds
+    /// since the page loaded.
+    pub fn frame(&mut self, canvas: &mut CountedCanvas, now: f64) {
+        let start = metrics::now();
+        if let Command::Ink(kind) = self.cmd {
+            if std::mem::take(&mut self.ink_untold) {
+                self.out_v.push(Out::Ink(Ink {
+                    kind,
+                    edge: self.last_edge.clone(),
+                    end: None,
+                }));
+            }
+        }
+        let mut dirty = std::mem::take(&mut self.dirty);
+        if std::mem::take(&mut self.stale) {
+            if self.is_drawing() {
+                self.deferred = Some(Deferred::Refresh);
+            } else {
+                self.redraw(canvas, now);
+                dirty = true;
+            }
+        }
+        if dirty {
+            let _ = canvas.render();
+            self.out_v.push(Out::Frame {
+                ms: (metrics::now() - start) as f32,
+                edge_n: self.drawn_id_v.len(),
+                point_n: canvas.point_n(),
+                upload_bytes: canvas.take_upload_n() * std::mem::size_of::<Point>(),
+            });
+        }
+        // Lasers fade a little every frame until they are gone.
+        let fading = self
+            .ink_v
+            .iter()
+            .chain(&self.peer_ink_v)
+            .any(Ink::is_fading);
+        if fading && !self.is_drawing() {
+            self.stale = true;
+        }
+    }
+
+    /// Where `painting` draws screen pixel `pt`, with the pen's color and width.
+    fn px2point(&self, pt: Point2<f32>, force: f32) -> Point {
+        let size = self.viewport.size;
+        let client = PhysicalSize::new(size.x as u32, size.y as u32);
+        self.pen.px2point(pt.x, pt.y, force, client)
+    }
+
+    /// Width in clip space, where the screen is 2 high, of `px` screen pixels.
+    fn clip_len(&self, px: f32) -> f32 {
+        px * 2.0 / self.viewport.size.y.max(1.0)
+    }
+
+    /// World width of a clip space width, like those `px2point` gives.
+    fn world_len(&self, clip: f32) -> f32 {
+        self.viewport
+            .screen_to_world_len(clip * self.viewport.size.y.max(1.0) / 2.0)
+    }
+
+    /// Turn a sample into the next point of the edge being painted, in world space.
+    fn sample2point(&self, sample: &Sample) -> Point {
+        let mut pt = self.px2point(sample.pos(), sample.force());
+        let world = self.viewport.screen_to_world(sample.pos());
+        pt.pos = Point3::new(world.x, world.y, -1.0);
+        pt.width = self.world_len(pt.width);
+        // The nib is held in screen space, whatever the rotation of the board.
+        let direction = match self.last_edge.point_v.last() {
+            Some(last) => {
+                sample.pos()
+                    - self
+                        .viewport
+                        .world_to_screen(Point2::new(last.pos.x, last.pos.y))
+            }
+            None => sample.movement,
+        };
+        pt.width *= sample.stylus.width_factor(direction);
+        pt
+    }
+
+    /// A point of a line at `world`, with the pen's color and width.
+    fn line_point(&self, world: Point2<f32>) -> Point {
+        let mut pt = self.px2point(self.viewport.world_to_screen(world), LINE_FORCE);
+        pt.pos = Point3::new(world.x, world.y, -1.0);
+        pt.width = self.world_len(pt.width);
+        pt
+    }
+
+    /// The next point of a stroke of `kind` ink, in world space.
+    fn ink_point(&self, kind: InkKind, sample: &Sample) -> Point {
+        match kind {
+            InkKind::Laser => {
+                let mut pt = self.line_point(self.viewport.screen_to_world(sample.pos()));
+                pt.color = LASER_COLOR;
+                pt.width = self.viewport.screen_to_world_len(LASER_WIDTH);
+                pt
+            }
+            InkKind::Ephemeral => self.sample2point(sample),
+        }
+    }
+
+    /// Draw `ink` as it shows at `now`; a laser gets a glow under it.
+    fn draw_ink(&self, canvas: &mut CountedCanvas, ink: &Ink, now: f64) {
+        let opacity = ink.opacity(now);
+        let mut pass_v = vec![(1.0, opacity)];
+        if ink.kind == InkKind::Laser {
+            pass_v.insert(0, (GLOW_RATIO, opacity * GLOW_OPACITY));
+        }
+        for (width, opacity) in pass_v {
+            for (i, pt) in ink.edge.point_v.iter().enumerate() {
+                let mut pt = self.project(pt, opacity);
+                pt.width *= width;
+                if i == 0 {
+                    canvas.start_line(pt);
+                } else {
+                    canvas.push_point(pt);
+                }
+            }
+            canvas.end_line();
+        }
+    }
+
+    /// `world` pulled to what `snap` allows, within `SNAP_RADIUS`.
+    fn snap(&self, world: Point2<f32>) -> (Point2<f32>, Guide) {
+        let radius = self.viewport.screen_to_world_len(SNAP_RADIUS);
+        let layer_m = layer_m(&self.layer_v);
+        let endpoint_v = self
+            .query(&Rect::from_corners(world, world).inflate(radius))
+            .into_iter()
+            .map(|i| &self.edge_v[i])
+            .filter(|edge| {
+                layer_m
+                    .get(edge.layer_id.as_str())
+                    .is_none_or(|(_, l)| l.visible)
+            })
+            .flat_map(|edge| [edge.point_v.first(), edge.point_v.last()])
+            .flatten()
+            .map(|pt| Point2::new(pt.pos.x, pt.pos.y));
+        snap_point(world, self.snap, &self.paper, endpoint_v, radius)
+    }
+
+    /// Screen points of the mark for `guide` around `center`, starting and
+    /// ending there so it can sit in the middle of a line.
+    fn guide_mark(center: Point2<f32>, guide: Guide) -> Vec<Point2<f32>> {
+        let r = GUIDE_MARK;
+        let offset_v: Vec<(f32, f32)> = match guide {
+            Guide::Grid => vec![(r, 0.0), (-r, 0.0), (0.0, 0.0), (0.0, r), (0.0, -r)],
+            Guide::Endpoint => vec![(r, r), (r, -r), (-r, -r), (-r, r), (r, r)],
+            Guide::None | Guide::Angle => return Vec::new(),
+        };
+        std::iter::once(center)
+            .chain(
+                offset_v
+                    .into_iter()
+                    .map(|(x, y)| center + Vector2::new(x, y)),
+            )
+            .chain(std::iter::once(center))
+            .collect()
+    }
+
+    /// Start an open line showing the line being drawn and its guides.
+    ///
+    /// Guides are thin and retrace themselves, so they share one line with
+    /// the preview that `cancle_line` takes back at once.
+    fn preview_line(&self, canvas: &mut CountedCanvas) {
+        let guide_width = self.clip_len(PAPER_LINE_WIDTH);
+        let guide = |screen: Point2<f32>| {
+            let mut pt = self.px2point(screen, 1.0);
+            pt.pos.z = -1.0;
+            pt.color = GUIDE_COLOR;
+            pt.width = guide_width;
+            pt
+        };
+        let (from, from_guide) = self.line_from;
+        let (to, to_guide) = self.line_to;
+        let from_s = self.viewport.world_to_screen(from);
+        let to_s = self.viewport.world_to_screen(to);
+        // Long enough to cross the screen from anywhere on it.
+        let reach = self.viewport.size.magnitude();
+        let dir = if to_guide == Guide::Angle && to_s != from_s {
+            Some((to_s - from_s).normalize() * reach)
+        } else {
+            None
+        };
+
+        let mut pt_v: Vec<Point> = Vec::new();
+        if let Some(dir) = dir {
+            pt_v.push(guide(from_s - dir));
+        }
+        for screen in Self::guide_mark(from_s, from_guide) {
+            pt_v.push(guide(screen));
+        }
+        for world in [from, to] {
+            let pt = self.line_point(world);
+            pt_v.push(self.project(&pt, self.paint_opacity));
+        }
+        for screen in Self::guide_mark(to_s, to_guide) {
+            pt_v.push(guide(screen));
+        }
+        if let Some(dir) = dir {
+            pt_v.push(guide(to_s));
+            pt_v.push(guide(to_s + dir));
+        }
+        canvas.start_line(pt_v[0].clone());
+        for pt in pt_v.into_iter().skip(1) {
+            canvas.push_point(pt);
+        }
+    }
+
+    /// Keep `edge`, just drawn and ended, and hand it to the owner.
+    fn finish_edge(&mut self, edge: Edge) {
+        let rank = layer_m(&self.layer_v)
+            .get(edge.layer_id.as_str())
+            .map_or(0, |(rank, _)| *rank);
+        if rank < self.top_rank {
+            // Drawn over higher layers, so it has to be put in its place.
+            self.deferred = Some(Deferred::Refresh);
+        } else {
+            self.top_rank = rank;
+        }
+        self.drawn_id_v.insert(edge.id.clone());
+        self.out_v.push(Out::Commit(edge));
+    }
+
+    /// `edge` redrawn as `shape`, with its color and mean width.
+    fn shape_edge(edge: &Edge, shape: &Shape) -> Edge {
+        let mut template = edge.point_v[0].clone();
+        template.width =
+            edge.point_v.iter().map(|pt| pt.width).sum::<f32>() / edge.point_v.len() as f32;
+        let mut shaped = Edge {
+            id: edge.id.clone(),
+            layer_id: edge.layer_id.clone(),
+            ..Edge::new()
+        };
+        for world in shape.outline() {
+            let mut pt = template.clone();
+            pt.pos = Point3::new(world.x, world.y, template.pos.z);
+            shaped.push(pt, Stylus::default());
+        }
+        shaped
+    }
+
+    /// What the stylus reported for a sample of the edge being painted.
+    fn sample2stylus(&self, sample: &Sample) -> Stylus {
+        Stylus {
+            time: (sample.time - self.paint_start) as f32,
+            ..sample.stylus
+        }
+    }
+
+    /// Turn a point in world space into one `painting` draws on this screen.
+    fn project(&self, pt: &Point, opacity: f32) -> Point {
+        let screen = self
+            .viewport
+            .world_to_screen(Point2::new(pt.pos.x, pt.pos.y));
+        let mut device = pt.clone();
+        device.pos = self.px2point(screen, 1.0).pos;
+        device.pos.z = pt.pos.z;
+        device.width = self.clip_len(pt.width * self.viewport.zoom);
+        device.color[3] *= opacity;
+        device
+    }
+
+    /// Start a closed line through `corner_v`, given in screen pixels, left open.
+    fn outline(&self, canvas: &mut CountedCanvas, corner_v: &[Point2<f32>; 4]) {
+        for (i, corner) in corner_v.iter().chain(&corner_v[..1]).enumerate() {
+            let mut pt = self.px2point(*corner, 0.5);
+            pt.pos.z = -1.0;
+            pt.color = SELECTION_COLOR;
+            if i == 0 {
+                canvas.start_line(pt);
+            } else {
+                canvas.push_point(pt);
+            }
+        }
+    }
+
+    /// Redraw the edge being painted without its predicted points.
+    fn drop_tail(&mut self, canvas: &mut CountedCanvas) {
+        if self.tail_len == 0 {
+            return;
+        }
+        canvas.cancle_line();
+        canvas.start_line(self.project(&self.last_edge.point_v[0], self.paint_opacity));
+        for pt in &self.last_edge.point_v[1..] {
+            canvas.push_point(self.project(pt, self.paint_opacity));
+        }
+        self.tail_len = 0;
+    }
+
+    /// Bring the index in line with `edge_v`, touching only edges that came or went.
+    fn sync_index(&mut self) {
+        let mut edge_m = HashMap::with_capacity(self.edge_v.len());
+        for (i, edge) in self.edge_v.iter().enumerate() {
+            let rect = match self.edge_m.remove(&edge.id) {
+                Some((_, rect)) => rect,
+                None => match edge.bounds() {
+                    Some(rect) => {
+                        self.index.insert(rect, edge.id.clone());
+                        rect
+                    }
+                    None => continue,
+                },
+            };
+            edge_m.insert(edge.id.clone(), (i, rect));
+        }
+        for (id, (_, rect)) in self.edge_m.drain() {
+            self.index.remove(&rect, &id);
+            self.lod_m.remove(&id);
+        }
+        self.edge_m = edge_m;
+    }
+
+    /// Drop the bounds, levels of detail and drawing of edge `id`.
+    fn forget(&mut self, id: &str) {
+        if let Some((_, rect)) = self.edge_m.remove(id) {
+            self.index.remove(&rect, &id.to_string());
+        }
+        self.lod_m.remove(id);
+        // `painting` can't take a single line back, so losing one means drawing
+        // everything again. The next frame does it once, however many went.
+        if self.drawn_id_v.remove(id) {
+            self.refresh();
+        }
+    }
+
+    /// Positions in `edge_v` of the edges whose bounds touch `rect`, in order.
+    fn query(&self, rect: &Rect) -> Vec<usize> {
+        let mut i_v: Vec<usize> = self
+            .index
+            .query(rect)
+            .into_iter()
+            .filter_map(|id| self.edge_m.get(id).map(|(i, _)| *i))
+            .collect();
+        i_v.sort_unstable();
+        i_v
+    }
+
+    /// Drawing order, position in `edge_v` and opacity of the shown edges
+    /// on screen, sorted.
+    fn shown_v(&self) -> Vec<(usize, usize, f32)> {
+        let layer_m = layer_m(&self.layer_v);
+        order(&layer_m, &self.edge_v, self.query(&self.cull_rect()))
+            .into_iter()
+            .map(|(rank, i)| (rank, i, opacity(&layer_m, &self.edge_v[i].layer_id)))
+            .collect()
+    }
+
+    /// A line is open on the GPU, which redrawing would break.
+    fn is_drawing(&self) -> bool {
+        matches!(
+            self.cmd,
+            Command::Paint | Command::Select | Command::Line | Command::Ink(_)
+        )
+    }
+
+    fn cull_rect(&self) -> Rect {
+        let margin = self.viewport.screen_to_world_len(CULL_MARGIN);
+        self.viewport.world_rect().inflate(margin)
+    }
+
+    /// Cover the screen with the paper, under everything else.
+    fn draw_paper(&self, canvas: &mut CountedCanvas) {
+        let paper = &self.paper;
+        let size = self.viewport.size;
+        let line =
+            |canvas: &mut CountedCanvas, from: Point2<f32>, to: Point2<f32>, color, width| {
+                for (i, screen) in [from, to].into_iter().enumerate() {
+                    let mut pt = self.px2point(screen, 1.0);
+                    pt.pos.z = -1.0;
+                    pt.color = color;
+                    pt.width = width;
+                    if i == 0 {
+                        canvas.start_line(pt);
+                    } else {
+                        canvas.push_point(pt);
+                    }
+                }
+                canvas.end_line();
+            };
+
+        // One line wider than the screen is high fills it.
+        line(
+            canvas,
+            Point2::new(-size.y, size.y / 2.0),
+            Point2::new(size.x + size.y, size.y / 2.0),
+            paper.color,
+            self.clip_len(size.y * 1.5),
+        );
+        let min_spacing = match paper.kind {
+            PaperKind::Dots => MIN_DOT_SPACING,
+            _ => MIN_PAPER_SPACING,
+        };
+        let rect = self.viewport.world_rect();
+        for [from, to] in paper.mark_v(&rect, self.viewport.screen_to_world_len(min_spacing)) {
+            let from = self.viewport.world_to_screen(from);
+            let mut to = self.viewport.world_to_screen(to);
+            let width = if from == to {
+                // A dot: a short line as wide as two paper lines.
+                to.x += 0.01;
+                self.clip_len(2.0 * PAPER_LINE_WIDTH)
+            } else {
+                self.clip_len(PAPER_LINE_WIDTH)
+            };
+            line(canvas, from, to, paper.line_color, width);
+        }
+    }
+
+    /// Draw `edge` with no more detail than shows at this zoom.
+    fn draw_edge(&mut self, canvas: &mut CountedCanvas, edge: &Edge, opacity: f32) {
+        let pixel = self.viewport.screen_to_world_len(1.0);
+        let rect = match self.edge_m.get(&edge.id) {
+            Some((_, rect)) => *rect,
+            None => match edge.bounds() {
+                Some(rect) => rect,
+                None => return,
+            },
+        };
+        if rect.width().max(rect.height()) < pixel * MIN_EDGE_SIZE {
+            // Too small to have a shape; `painting` draws a lone point as a dot.
+            let width = edge.point_v.iter().map(|pt| pt.width).fold(0.0, f32::max);
+            if width * self.viewport.zoom >= MIN_EDGE_SIZE {
+                canvas.start_line(self.project(&edge.point_v[0], opacity));
+                canvas.end_line();
+            }
+            return;
+        }
+        let i_v = self
+            .lod_m
+            .entry(edge.id.clone())
+            .or_default()
+            .point_i_v(&edge.point_v, pixel * LOD_TOLERANCE)
+            .to_vec();
+        canvas.start_line(self.project(&edge.point_v[i_v[0]], opacity));
+        for i in &i_v[1..] {
+            canvas.push_point(self.project(&edge.point_v[*i], opacity));
+        }
+        canvas.end_line();
+    }
+
+    /// Draw the edges at `shown_v`, as `shown_v` gives them, over what is drawn.
+    fn draw_shown(&mut self, canvas: &mut CountedCanvas, shown_v: Vec<(usize, usize, f32)>) {
+        // Lent out, as drawing keeps levels of detail on `self`.
+        let edge_v = std::mem::take(&mut self.edge_v);
+        for (rank, i, opacity) in shown_v {
+            let edge = &edge_v[i];
+            self.draw_edge(canvas, edge, opacity);
+            self.drawn_id_v.insert(edge.id.clone());
+            self.top_rank = rank;
+        }
+        self.edge_v = edge_v;
+    }
+
+    /// Put everything back on the GPU, from the paper up, as it shows at `now`.
+    fn redraw(&mut self, canvas: &mut CountedCanvas, now: f64) {
+        canvas.clear();
+        self.draw_paper(canvas);
+        self.drawn_id_v.clear();
+        self.top_rank = 0;
+        self.draw_shown(canvas, self.shown_v());
+
+        self.ink_v.retain(|ink| !ink.is_gone(now));
+        self.peer_ink_v.retain(|ink| !ink.is_gone(now));
+        for ink in self.peer_ink_v.iter().chain(&self.ink_v) {
+            self.draw_ink(canvas, ink, now);
+        }
+
+        let selection = self
+            .selected_id_v
+            .iter()
+            .filter_map(|id| self.edge_m.get(id).map(|(_, rect)| *rect))
+            .reduce(|a, b| a.union(&b));
+        if let Some(rect) = selection {
+            let rect = rect.inflate(self.viewport.screen_to_world_len(ERASER_RADIUS));
+            let corner_v = rect.corner_v().map(|pt| self.viewport.world_to_screen(pt));
+            self.outline(canvas, &corner_v);
+            canvas.end_line();
+        }
+    }
+
+    /// Redraw everything from scratch, in the next frame.
+    fn refresh(&mut self) {
+        if self.is_drawing() {
+            self.deferred = Some(Deferred::Refresh);
+            return;
+        }
+        self.stale = true;
+    }
+
+    /// Draw the edges that came in since the last redraw.
+    fn sync(&mut self, canvas: &mut CountedCanvas) {
+        if self.is_drawing() {
+            self.deferred.get_or_insert(Deferred::Sync);
+            return;
+        }
+        // The full redraw on its way brings the new edges too.
+        if self.stale {
+            return;
+        }
+        let new_v: Vec<(usize, usize, f32)> = self
+            .shown_v()
+            .into_iter()
+            .filter(|(_, i, _)| !self.drawn_id_v.contains(&self.edge_v[*i].id))
+            .collect();
+        // Edges under what is already drawn can't be slid in below it.
+        if new_v.iter().any(|(rank, _, _)| *rank < self.top_rank) {
+            self.refresh();
+            return;
+        }
+        if !new_v.is_empty() {
+            self.draw_shown(canvas, new_v);
+            self.dirty = true;
+        }
+    }
+
+    fn erase_at(&mut self, x: f32, y: f32) {
+        let pos = self.viewport.screen_to_world(Point2::new(x, y));
+        let radius = self.viewport.screen_to_world_len(ERASER_RADIUS);
+        let layer_m = layer_m(&self.layer_v);
+        for i in self.query(&Rect::from_corners(pos, pos).inflate(radius)) {
+            let edge = &self.edge_v[i];
+            if self.erased_id_v.contains(&edge.id)
+                || !is_editable(&layer_m, &edge.layer_id)
+                || !edge.hit(pos, radius)
+            {
+                continue;
+            }
+            self.erased_id_v.push(edge.id.clone());
+            self.out_v.push(Out::Erase(edge.id.clone()));
+        }
+    }
+
+    /// Look through `viewport`, leaving ephemeral ink behind if it shows
+    /// another part of the board.
+    fn move_view(&mut self, viewport: Viewport) {
+        let ephemeral = self.ink_v.iter().any(|ink| ink.kind == InkKind::Ephemeral);
+        if ephemeral && !viewport.shows_same(&self.viewport) {
+            self.ink_v.retain(|ink| ink.kind != InkKind::Ephemeral);
+            self.out_v.push(Out::ClearInk);
+        }
+        self.viewport = viewport;
+    }
+
+    /// Take a new viewport and tell the owner about it.
+    fn set_viewport(&mut self, viewport: Viewport) {
+        if viewport == self.viewport {
+            return;
+        }
+        self.move_view(viewport);
+        self.out_v.push(Out::Viewport(viewport));
+        self.refresh();
+    }
+
+    fn down(&mut self, canvas: &mut CountedCanvas, sample: Sample) {
+        if !matches!(self.cmd, Command::None) {
+            return;
+        }
+        let tool = if sample.stylus.eraser {
+            Tool::Eraser
+        } else {
+            self.tool
+        };
+        if self.enabled_moving {
+            log::debug!("start moving");
+            self.cmd = Command::Move;
+        } else if tool == Tool::Eraser {
+            self.cmd = Command::Erase;
+            self.erase_at(sample.x, sample.y);
+        } else if tool == Tool::Select {
+            self.cmd = Command::Select;
+            self.select_from = sample.pos();
+            self.select_to = self.select_from;
+        } else if let Some(kind) = match tool {
+            Tool::Laser => Some(InkKind::Laser),
+            Tool::Ephemeral => Some(InkKind::Ephemeral),
+            _ => None,
+        } {
+            self.cmd = Command::Ink(kind);
+            self.paint_start = sample.time;
+            self.last_edge.layer_id = self.layer_id.clone();
+            let pt = self.ink_point(kind, &sample);
+            canvas.start_line(self.project(&pt, 1.0));
+            self.dirty = true;
+            self.ink_untold = true;
+            self.last_edge.push(pt, self.sample2stylus(&sample));
+        } else {
+            let layer_m = layer_m(&self.layer_v);
+            if !is_editable(&layer_m, &self.layer_id) {
+                return;
+            }
+            self.paint_opacity = opacity(&layer_m, &self.layer_id);
+            if tool == Tool::Line {
+                self.cmd = Command::Line;
+                let world = self.viewport.screen_to_world(sample.pos());
+                self.line_from = self.snap(world);
+                self.line_to = self.line_from;
+            } else {
+                self.cmd = Command::Paint;
+                self.paint_start = sample.time;
+                self.last_edge.layer_id = self.layer_id.clone();
+                let pt = self.sample2point(&sample);
+                canvas.start_line(self.project(&pt, self.paint_opacity));
+                self.dirty = true;
+                self.last_edge.push(pt, self.sample2stylus(&sample));
+            }
+        }
+    }
+
+    fn up(&mut self, canvas: &mut CountedCanvas, release: f64) {
+        match &self.cmd {
+            Command::Paint => {
+                if self.last_edge.is_empty() {
+                    return;
+                }
+                self.drop_tail(canvas);
+                let held = hold_ms(
+                    &self.last_edge.point_v,
+                    &self.last_edge.stylus_v,
+                    self.viewport.screen_to_world_len(HOLD_RADIUS),
+                    release - self.paint_start,
+                );
+                let shape = if held >= HOLD_MS {
+                    recognize(&self.last_edge.point_v, SHAPE_CONFIDENCE)
+                } else {
+                    None
+                };
+                let edge = std::mem::take(&mut self.last_edge);
+                let edge = match shape {
+                    Some(shape) => {
+                        log::debug!("stroke taken for {shape:?}");
+                        canvas.cancle_line();
+                        let edge = Self::shape_edge(&edge, &shape);
+                        self.draw_edge(canvas, &edge, self.paint_opacity);
+                        edge
+                    }
+                    None => {
+                        canvas.end_line();
+                        edge
+                    }
+                };
+                self.dirty = true;
+                self.finish_edge(edge);
+            }
+            Command::Line => {
+                if self.line_drawn {
+                    canvas.cancle_line();
+                    self.line_drawn = false;
+                    self.dirty = true;
+                }
+                let (from, to) = (self.line_from.0, self.line_to.0);
+                if from != to {
+                    let mut edge = Edge {
+                        layer_id: self.layer_id.clone(),
+                        ..Edge::new()
+                    };
+                    for world in [from, to] {
+                        edge.push(self.line_point(world), Stylus::default());
+                    }
+                    self.draw_edge(canvas, &edge, self.paint_opacity);
+                    self.finish_edge(edge);
+                }
+            }
+            Command::Ink(kind) => {
+                let kind = *kind;
+                canvas.end_line();
+                self.dirty = true;
+                self.ink_untold = false;
+                let ink = Ink {
+                    kind,
+                    edge: std::mem::take(&mut self.last_edge),
+                    end: Some(release),
+                };
+                self.out_v.push(Out::Ink(ink.clone()));
+                self.ink_v.push(ink);
+                // Redrawn with its glow, then again as it fades.
+                if kind == InkKind::Laser {
+                    self.deferred = Some(Deferred::Refresh);
+                }
+            }
+            Command::Erase => {
+                self.erased_id_v.clear();
+            }
+            Command::Select => {
+                if self.select_to != self.select_from {
+                    canvas.cancle_line();
+                    self.dirty = true;
+                }
+                let rect = Rect::from_corners(self.select_from, self.select_to);
+                let world =
+                    Rect::bound(rect.corner_v().map(|pt| self.viewport.screen_to_world(pt)))
+                        .unwrap();
+                let layer_m = layer_m(&self.layer_v);
+                let id_v = self
+                    .query(&world)
+                    .into_iter()
+                    .map(|i| &self.edge_v[i])
+                    .filter(|edge| is_editable(&layer_m, &edge.layer_id))
+                    .filter(|edge| {
+                        edge.point_v.iter().any(|pt| {
+                            let pt = Point2::new(pt.pos.x, pt.pos.y);
+                            rect.contains(self.viewport.world_to_screen(pt))
+                        })
+                    })
+                    .map(|edge| edge.id.clone())
+                    .collect();
+                self.out_v.push(Out::Select(id_v));
+            }
+            Command::Move => {
+                log::debug!("end moving");
+            }
+            Command::None => (),
+        }
+        self.cmd = Command::None;
+        match self.deferred.take() {
+            Some(Deferred::Refresh) => self.refresh(),
+            Some(Deferred::Sync) => self.sync(canvas),
+            None => (),
+        }
+    }
+
+    fn drag(&mut self, canvas: &mut CountedCanvas, sample_v: &[Sample], predicted_v: &[Sample]) {
+        if let Some(sample) = sample_v.last() {
+            self.out_v
+                .push(Out::Pointer(self.viewport.screen_to_world(sample.pos())));
+        }
+        match &self.cmd {
+            Command::Move => {
+                log::debug!("moving");
+                let mut viewport = self.viewport;
+                for sample in sample_v {
+                    viewport = viewport.pan(sample.movement);
+                }
+                self.set_viewport(viewport);
+            }
+            Command::Paint => {
+                self.drop_tail(canvas);
+                for sample in sample_v {
+                    let pt = self.sample2point(sample);
+                    canvas.push_point(self.project(&pt, self.paint_opacity));
+                    self.last_edge.push(pt, self.sample2stylus(sample));
+                }
+                // Drawn now, taken back by the next `drop_tail`.
+                for sample in predicted_v {
+                    let pt = self.sample2point(sample);
+                    canvas.push_point(self.project(&pt, self.paint_opacity));
+                }
+                self.tail_len = predicted_v.len();
+                self.dirty = true;
+            }
+            Command::Ink(kind) => {
+                let kind = *kind;
+                for sample in sample_v {
+                    let pt = self.ink_point(kind, sample);
+                    canvas.push_point(self.project(&pt, 1.0));
+                    self.last_edge.push(pt, self.sample2stylus(sample));
+                }
+                self.dirty = true;
+                self.ink_untold = true;
+            }
+            Command::Erase => {
+                for sample in sample_v {
+                    self.erase_at(sample.x, sample.y);
+                }
+            }
+            Command::Select => {
+                let Some(sample) = sample_v.last() else {
+                    return;
+                };
+                if self.select_to != self.select_from {
+                    canvas.cancle_line();
+                }
+                self.select_to = sample.pos();
+                let rect = Rect::from_corners(self.select_from, self.select_to);
+                self.outline(canvas, &rect.corner_v());
+                self.dirty = true;
+            }
+            Command::Line => {
+                let Some(sample) = sample_v.last() else {
+                    return;
+                };
+                let from = self.line_from.0;
+                let world = self.viewport.screen_to_world(sample.pos());
+                self.line_to = if sample.shift {
+                    (snap_angle(from, world), Guide::Angle)
+                } else {
+                    self.snap(world)
+                };
+                if self.line_drawn {
+                    canvas.cancle_line();
+                    self.line_drawn = false;
+                }
+                if self.line_to.0 != from {
+                    self.preview_line(canvas);
+                    self.line_drawn = true;
+                }
+                self.dirty = true;
+            }
+            Command::None => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use wgpu::SurfaceError;
+
+    use super::*;
+
+    /// Lines handed to a `LogCanvas`, cleared with it.
+    type LineV = Rc<RefCell<Vec<Vec<Point>>>>;
+
+    /// A canvas that only keeps the lines it is given.
+    struct LogCanvas {
+        line_v: LineV,
+        size: PhysicalSize<u32>,
+    }
+
+    impl AsCanvas for LogCanvas {
+        fn render(&mut self) -> Result<(), SurfaceError> {
+            Ok(())
+        }
+
+        fn push_point(&mut self, pt: Point) {
+            if let Some(line) = self.line_v.borrow_mut().last_mut() {
+                line.push(pt);
+            }
+        }
+
+        fn start_line(&mut self, pt: Point) {
+            self.line_v.borrow_mut().push(vec![pt]);
+        }
+
+        fn end_line(&mut self) {}
+
+        fn cancle_line(&mut self) {
+            self.line_v.borrow_mut().pop();
+        }
+
+        fn set_aspect(&mut self, _aspect: f32) {}
+
+        fn clear(&mut self) {
+            self.line_v.borrow_mut().clear();
+        }
+
+        fn get_size(&self) -> &PhysicalSize<u32> {
+            &self.size
+        }
+
+        fn resize(&mut self, new_size: PhysicalSize<u32>) {
+            self.size = new_size;
+        }
+
+        fn move_content(&mut self, _x: f32, _y: f32, _z: f32) {}
+
+        fn scacle(&mut self, _x: f32, _y: f32, _z: f32) {}
+    }
+
+    fn log_canvas() -> (CountedCanvas, LineV) {
+        let line_v = LineV::default();
+        let canvas = LogCanvas {
+            line_v: line_v.clone(),
+            size: PhysicalSize::new(800, 600),
+        };
+        (CountedCanvas::new(Box::new(canvas)), line_v)
+    }
+
+    /// An edge of `n` points along the x axis, `id` apart.
+    fn long_edge(id: &str, n: usize) -> Edge {
+        let point_v: Vec<Point> = (0..n)
+            .map(|i| Point {
+                pos: Point3::new(i as f32 * 10.0, (i % 2) as f32 * 10.0, -1.0),
+                color: [0.0, 0.0, 0.0, 1.0],
+                width: 2.0,
+            })
+            .collect();
+        Edge {
+            id: id.to_string(),
+            layer_id: DEFAULT_LAYER_ID.to_string(),
+            stylus_v: vec![Stylus::default(); n],
+            point_v,
+        }
+    }
+
+    fn edge(id: &str, x: f32) -> Edge {
+        Edge {
+            id: id.to_string(),
+            layer_id: DEFAULT_LAYER_ID.to_string(),
+            point_v: vec![Point {
+                pos: Point3::new(x, 0.0, -1.0),
+                color: [0.0, 0.0, 0.0, 1.0],
+                width: 2.0,
+            }],
+            stylus_v: vec![Stylus::default()],
+        }
+    }
+
+    fn check(old_v: Vec<Edge>, new_v: Vec<Edge>) {
+        let mut edge_v = old_v.clone();
+        match Delta::edges(&old_v, &new_v) {
+            Some(Delta::Edges { gone_v, put_v }) => apply_edges(&mut edge_v, gone_v, put_v),
+            Some(delta) => panic!("not an edge delta: {delta:?}"),
+            None => {}
+        }
+        assert_eq!(edge_v, new_v);
+    }
+
+    #[test]
+    fn edge_deltas_rebuild_the_list() {
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|id| edge(id, 0.0));
+        check(vec![], vec![a.clone(), b.clone()]);
+        check(
+            vec![a.clone(), b.clone()],
+            vec![a.clone(), c.clone(), b.clone()],
+        );
+        check(
+            vec![a.clone(), b.clone(), c.clone()],
+            vec![a.clone(), c.clone()],
+        );
+        check(
+            vec![a.clone(), b.clone(), c.clone()],
+            vec![d.clone(), a.clone(), edge("b", 1.0), c.clone()],
+        );
+        check(
+            vec![a.clone(), b.clone(), c.clone()],
+            vec![c.clone(), a.clone(), b.clone()],
+        );
+        check(vec![a.clone(), b.clone()], vec![]);
+    }
+
+    #[test]
+    fn no_delta_without_change() {
+        let edge_v = vec![edge("a", 0.0), edge("b", 1.0)];
+        assert_eq!(Delta::edges(&edge_v, &edge_v.clone()), None);
+    }
+
+    #[test]
+    fn deltas_survive_json() {
+        let delta = Delta::Edges {
+            gone_v: vec!["a".to_string()],
+            put_v: vec![(0, edge("b", 2.5))],
+        };
+        let s = serde_json::to_string(&delta).unwrap();
+        assert_eq!(serde_json::from_str::<Delta>(&s).unwrap(), delta);
+    }
+
+    #[test]
+    fn tilt_widens_the_first_point() {
+        let mut scene = Scene::new();
+        scene.viewport.size = Vector2::new(800.0, 600.0);
+        let upright = Sample {
+            movement: Vector2::new(0.0, 0.0),
+            x: 100.0,
+            y: 100.0,
+            force: Some(0.5),
+            stylus: Stylus::default(),
+            time: 0.0,
+            shift: false,
+        };
+        let tilted = Sample {
+            stylus: Stylus {
+                tilt_x: 45.0,
+                ..Stylus::default()
+            },
+            ..upright.clone()
+        };
+        assert!(scene.last_edge.point_v.is_empty());
+        let upright_width = scene.sample2point(&upright).width;
+        let tilted_width = scene.sample2point(&tilted).width;
+        assert!((tilted_width - upright_width * 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn changed_edges_are_drawn_anew() {
+        let (mut canvas, line_v) = log_canvas();
+        let mut scene = Scene::new();
+        scene.apply(&mut canvas, Delta::Size(Vector2::new(800.0, 600.0)));
+        let long_v = vec![long_edge("a", 5)];
+        scene.apply(&mut canvas, Delta::edges(&[], &long_v).unwrap());
+        scene.frame(&mut canvas, 0.0);
+        assert_eq!(line_v.borrow().last().map(Vec::len), Some(5));
+
+        // Shorter under the same id, as when points are erased from it.
+        let short_v = vec![long_edge("a", 2)];
+        scene.apply(&mut canvas, Delta::edges(&long_v, &short_v).unwrap());
+        scene.frame(&mut canvas, 0.0);
+        assert_eq!(line_v.borrow().last().map(Vec::len), Some(2));
+        let short = short_v[0].bounds().unwrap();
+        assert_eq!(scene.edge_m["a"].1, short);
+        assert_eq!(scene.index.query(&short).len(), 1);
+    }
+}
@@ -0,0 +1,537 @@
+use cgmath::{
+    Basis2, EuclideanSpace, InnerSpace, MetricSpace, Point2, Rad, Rotation, Rotation2, Vector2,
+};
+use painting::point::Point;
+
+use super::{
+    lod::{segment_distance, simplify},
+    Stylus,
+};
+
+/// Points a stroke is spread over, evenly along its length, to be measured.
+const SAMPLE_N: usize = 64;
+/// Points of the outline of a circle or an ellipse.
+const ELLIPSE_N: usize = 48;
+/// A stroke ending closer than this, over its size, to where it started is closed.
+const CLOSE_RATIO: f32 = 0.25;
+/// How far, over its size, a stroke may stray from the corners found in it.
+const CORNER_RATIO: f32 = 0.08;
+/// Corners turning less than this, in radians, are taken out of closed strokes.
+const MIN_TURN: f32 = std::f32::consts::FRAC_PI_6;
+/// Stray from a line, over its length, at which confidence drops to zero.
+const LINE_TOLERANCE: f32 = 0.06;
+/// Mean stray from an ellipse, over its radius, at which confidence drops to zero.
+const ELLIPSE_TOLERANCE: f32 = 0.12;
+/// Same for polygons and arrows, over the size of the stroke.
+const POLYGON_TOLERANCE: f32 = 0.04;
+/// Ellipses rounder than this are circles.
+const CIRCLE_RATIO: f32 = 0.8;
+/// Longest the head of an arrow gets, over its shaft.
+const MAX_HEAD_RATIO: f32 = 0.6;
+/// Widest angle between a barb and the shaft, in radians.
+const MAX_BARB_ANGLE: f32 = std::f32::consts::FRAC_PI_3;
+/// Angle between the barbs of a cleaned arrow and its shaft.
+const BARB_ANGLE: f32 = std::f32::consts::FRAC_PI_6;
+
+fn pos(pt: &Point) -> Point2<f32> {
+    Point2::new(pt.pos.x, pt.pos.y)
+}
+
+fn length(pos_v: &[Point2<f32>]) -> f32 {
+    pos_v.windows(2).map(|w| w[0].distance(w[1])).sum()
+}
+
+/// `n` points evenly spaced along `pos_v`, its ends included.
+fn resample(pos_v: &[Point2<f32>], n: usize) -> Vec<Point2<f32>> {
+    let step = length(pos_v) / (n - 1) as f32;
+    let mut sample_v = vec![pos_v[0]];
+    // Length walked since the last sample.
+    let mut walked = 0.0;
+    for w in pos_v.windows(2) {
+        let mut from = w[0];
+        let mut d = from.distance(w[1]);
+        while walked + d >= step && sample_v.len() < n - 1 {
+            from += (w[1] - from) * ((step - walked) / d);
+            sample_v.push(from);
+            d = from.distance(w[1]);
+            walked = 0.0;
+        }
+        walked += d;
+    }
+    sample_v.push(*pos_v.last().unwrap());
+    sample_v
+}
+
+/// Mean distance from `sample_v` to the polyline through `corner_v`.
+fn polyline_error(sample_v: &[Point2<f32>], corner_v: &[Point2<f32>]) -> f32 {
+    let sum: f32 = sample_v
+        .iter()
+        .map(|p| {
+            corner_v
+                .windows(2)
+                .map(|w| segment_distance(*p, w[0], w[1]))
+                .fold(f32::MAX, f32::min)
+        })
+        .sum();
+    sum / sample_v.len() as f32
+}
+
+fn confidence(error: f32, tolerance: f32) -> f32 {
+    (1.0 - error / tolerance).max(0.0)
+}
+
+/// Corners of a closed stroke, from those of `corner_v` turning at least `MIN_TURN`.
+fn closed_corner_v(corner_v: &[Point2<f32>]) -> Vec<Point2<f32>> {
+    // The last point is the first again.
+    let mut corner_v = corner_v[..corner_v.len() - 1].to_vec();
+    loop {
+        let n = corner_v.len();
+        if n < 3 {
+            return corner_v;
+        }
+        let flat = (0..n).find(|i| {
+            let prev = corner_v[(i + n - 1) % n];
+            let next = corner_v[(i + 1) % n];
+            let (a, b) = (corner_v[*i] - prev, next - corner_v[*i]);
+            a.angle(b).0.abs() < MIN_TURN
+        });
+        match flat {
+            Some(i) => {
+                corner_v.remove(i);
+            }
+            None => return corner_v,
+        }
+    }
+}
+
+/// Best ellipse through `sample_v`, from its principal axes and its extent along them.
+fn fit_ellipse(sample_v: &[Point2<f32>]) -> (Shape, f32) {
+    let n = sample_v.len() as f32;
+    let mean = sample_v
+        .iter()
+        .fold(Vector2::new(0.0, 0.0), |sum, p| sum + p.to_vec())
+        / n;
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for p in sample_v {
+        let d = p.to_vec() - mean;
+        sxx += d.x * d.x;
+        sxy += d.x * d.y;
+        syy += d.y * d.y;
+    }
+    let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let frame: Basis2<f32> = Rotation2::from_angle(Rad(-angle));
+    let local_v: Vec<Vector2<f32>> = sample_v
+        .iter()
+        .map(|p| frame.rotate_vector(p.to_vec()))
+        .collect();
+    let (lo, hi) = local_v.iter().fold(
+        (
+            Vector2::new(f32::MAX, f32::MAX),
+            Vector2::new(f32::MIN, f32::MIN),
+        ),
+        |(lo, hi), v| {
+            (
+                Vector2::new(lo.x.min(v.x), lo.y.min(v.y)),
+                Vector2::new(hi.x.max(v.x), hi.y.max(v.y)),
+            )
+        },
+    );
+    let middle = (lo + hi) / 2.0;
+    let radii = (hi - lo) / 2.0;
+    if radii.x <= 0.0 || radii.y <= 0.0 {
+        return (Shape::Line(sample_v[0], sample_v[0]), 0.0);
+    }
+    let error = local_v
+        .iter()
+        .map(|v| {
+            let d = v - middle;
+            ((d.x / radii.x).powi(2) + (d.y / radii.y).powi(2)).sqrt() - 1.0
+        })
+        .map(f32::abs)
+        .sum::<f32>()
+        / n;
+    let center = Point2::from_vec(frame.invert().rotate_vector(middle));
+    let shape = if radii.x.min(radii.y) / radii.x.max(radii.y) >= CIRCLE_RATIO {
+        Shape::Circle {
+            center,
+            radius: (radii.x + radii.y) / 2.0,
+        }
+    } else {
+        Shape::Ellipse {
+            center,
+            radii,
+            angle,
+        }
+    };
+    (shape, confidence(error, ELLIPSE_TOLERANCE))
+}
+
+/// The rectangle closest to the four corners of `corner_v`, lined up with its longest side.
+fn fit_rectangle(corner_v: &[Point2<f32>]) -> [Point2<f32>; 4] {
+    let side = (0..4)
+        .map(|i| corner_v[(i + 1) % 4] - corner_v[i])
+        .max_by(|a, b| a.magnitude2().total_cmp(&b.magnitude2()))
+        .unwrap();
+    let frame: Basis2<f32> = Rotation2::from_angle(Rad(-side.y.atan2(side.x)));
+    let local_v = corner_v.iter().map(|p| frame.rotate_vector(p.to_vec()));
+    let (lo, hi) = local_v.fold(
+        (
+            Vector2::new(f32::MAX, f32::MAX),
+            Vector2::new(f32::MIN, f32::MIN),
+        ),
+        |(lo, hi), v| {
+            (
+                Vector2::new(lo.x.min(v.x), lo.y.min(v.y)),
+                Vector2::new(hi.x.max(v.x), hi.y.max(v.y)),
+            )
+        },
+    );
+    let back = frame.invert();
+    [lo, Vector2::new(hi.x, lo.y), hi, Vector2::new(lo.x, hi.y)]
+        .map(|v| Point2::from_vec(back.rotate_vector(v)))
+}
+
+/// An arrow in `corner_v`: a shaft, then one barb, or two drawn out from the tip and back.
+fn fit_arrow(corner_v: &[Point2<f32>]) -> Option<Shape> {
+    let (tail, tip) = (corner_v[0], corner_v[1]);
+    let shaft = tip.distance(tail);
+    let barb_v: Vec<Point2<f32>> = match corner_v.len() {
+        3 => vec![corner_v[2]],
+        5 if corner_v[3].distance(tip) < shaft * CORNER_RATIO * 2.0 => {
+            vec![corner_v[2], corner_v[4]]
+        }
+        _ => return None,
+    };
+    let back = tail - tip;
+    let fits = barb_v.iter().all(|barb| {
+        let v = barb - tip;
+        v.magnitude() < shaft * MAX_HEAD_RATIO && v.angle(back).0.abs() < MAX_BARB_ANGLE
+    });
+    // Two barbs go either side of the shaft.
+    let apart =
+        barb_v.len() == 1 || back.perp_dot(barb_v[0] - tip) * back.perp_dot(barb_v[1] - tip) < 0.0;
+    if !fits || !apart {
+        return None;
+    }
+    let head = barb_v.iter().map(|barb| barb.distance(tip)).sum::<f32>() / barb_v.len() as f32;
+    let dir = back.normalize() * head;
+    let barb = |angle: f32| {
+        let turn: Basis2<f32> = Rotation2::from_angle(Rad(angle));
+        tip + turn.rotate_vector(dir)
+    };
+    Some(Shape::Arrow {
+        tail,
+        tip,
+        barb_v: [barb(BARB_ANGLE), barb(-BARB_ANGLE)],
+    })
+}
+
+// Public
+/// A clean figure a freehand stroke was taken for, in world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Line(Point2<f32>, Point2<f32>),
+    Circle {
+        center: Point2<f32>,
+        radius: f32,
+    },
+    Ellipse {
+        center: Point2<f32>,
+        radii: Vector2<f32>,
+        /// Radians from the x axis to the first radius.
+        angle: f32,
+    },
+    Rectangle([Point2<f32>; 4]),
+    Triangle([Point2<f32>; 3]),
+    Arrow {
+        tail: Point2<f32>,
+        tip: Point2<f32>,
+        barb_v: [Point2<f32>; 2],
+    },
+}
+
+impl Shape {
+    /// The points to draw this shape through, as one line.
+    pub fn outline(&self) -> Vec<Point2<f32>> {
+        match *self {
+            Self::Line(from, to) => vec![from, to],
+            Self::Circle { center, radius } => Self::Ellipse {
+                center,
+                radii: Vector2::new(radius, radius),
+                angle: 0.0,
+            }
+            .outline(),
+            Self::Ellipse {
+                center,
+                radii,
+                angle,
+            } => {
+                let frame: Basis2<f32> = Rotation2::from_angle(Rad(angle));
+                (0..=ELLIPSE_N)
+                    .map(|i| {
+                        let t = std::f32::consts::TAU * i as f32 / ELLIPSE_N as f32;
+                        let v = Vector2::new(radii.x * t.cos(), radii.y * t.sin());
+                        center + frame.rotate_vector(v)
+                    })
+                    .collect()
+            }
+            Self::Rectangle(corner_v) => corner_v.iter().chain(&corner_v[..1]).copied().collect(),
+            Self::Triangle(corner_v) => corner_v.iter().chain(&corner_v[..1]).copied().collect(),
+            Self::Arrow { tail, tip, barb_v } => vec![tail, tip, barb_v[0], tip, barb_v[1]],
+        }
+    }
+}
+
+/// Milliseconds the pen rested within `radius` of the end of `point_v`
+/// before being let go `release` milliseconds after its first point.
+pub fn hold_ms(point_v: &[Point], stylus_v: &[Stylus], radius: f32, release: f64) -> f64 {
+    let end = match point_v.last() {
+        Some(pt) => pos(pt),
+        None => return 0.0,
+    };
+    let rest = point_v
+        .iter()
+        .rposition(|pt| pos(pt).distance(end) > radius)
+        .map_or(0, |i| i + 1);
+    release - stylus_v[rest].time as f64
+}
+
+/// The shape `point_v` was drawn as, if one fits with at least `min_confidence`, in [0, 1].
+pub fn recognize(point_v: &[Point], min_confidence: f32) -> Option<Shape> {
+    let mut pos_v: Vec<Point2<f32>> = point_v.iter().map(pos).collect();
+    pos_v.dedup();
+    let len = length(&pos_v);
+    if pos_v.len() < 2 || len <= 0.0 {
+        return None;
+    }
+    let (lo, hi) = pos_v.iter().fold((pos_v[0], pos_v[0]), |(lo, hi), p| {
+        (
+            Point2::new(lo.x.min(p.x), lo.y.min(p.y)),
+            Point2::new(hi.x.max(p.x), hi.y.max(p.y)),
+        )
+    });
+    let size = lo.distance(hi);
+    let sample_v = resample(&pos_v, SAMPLE_N);
+
+    let i_v: Vec<usize> = (0..point_v.len()).collect();
+    let corner_v: Vec<Point2<f32>> = simplify(point_v, &i_v, size * CORNER_RATIO)
+        .into_iter()
+        .map(|i| pos(&point_v[i]))
+        .collect();
+
+    let (first, last) = (pos_v[0], *pos_v.last().unwrap());
+    let mut candidate_v: Vec<(Shape, f32)> = Vec::new();
+    if first.distance(last) < size * CLOSE_RATIO {
+        candidate_v.push(fit_ellipse(&sample_v));
+        let closed_v = closed_corner_v(&corner_v);
+        let polygon = match closed_v.len() {
+            3 => Some(Shape::Triangle([closed_v[0], closed_v[1], closed_v[2]])),
+            4 => Some(Shape::Rectangle(fit_rectangle(&closed_v))),
+            _ => None,
+        };
+        if let Some(polygon) = polygon {
+            let error = polyline_error(&sample_v, &polygon.outline());
+            candidate_v.push((polygon, confidence(error, size * POLYGON_TOLERANCE)));
+        }
+    } else if corner_v.len() == 2 {
+        let error = sample_v
+            .iter()
+            .map(|p| segment_distance(*p, first, last))
+            .fold(0.0, f32::max);
+        let chord = first.distance(last);
+        candidate_v.push((
+            Shape::Line(first, last),
+            confidence(error, chord * LINE_TOLERANCE),
+        ));
+    } else if let Some(arrow) = fit_arrow(&corner_v) {
+        let error = polyline_error(&sample_v, &corner_v);
+        candidate_v.push((arrow, confidence(error, size * POLYGON_TOLERANCE)));
+    }
+    candidate_v
+        .into_iter()
+        .filter(|(_, confidence)| *confidence >= min_confidence)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(shape, _)| shape)
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::Point3;
+
+    use super::*;
+
+    const MIN_CONFIDENCE: f32 = 0.6;
+
+    /// A stroke through `pos_v`, with a hand's wobble of about `wobble`.
+    fn stroke(pos_v: impl IntoIterator<Item = Point2<f32>>, wobble: f32) -> Vec<Point> {
+        pos_v
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| Point {
+                pos: Point3::new(
+                    p.x + wobble * (i as f32 * 1.7).sin(),
+                    p.y + wobble * (i as f32 * 2.3).cos(),
+                    -1.0,
+                ),
+                color: [0.0, 0.0, 0.0, 1.0],
+                width: 0.01,
+            })
+            .collect()
+    }
+
+    /// `n` points from `from` to `to`, `to` left out.
+    fn segment(from: Point2<f32>, to: Point2<f32>, n: usize) -> Vec<Point2<f32>> {
+        (0..n)
+            .map(|i| from + (to - from) * (i as f32 / n as f32))
+            .collect()
+    }
+
+    /// A closed stroke through `corner_v` and back to the first.
+    fn polygon(corner_v: &[Point2<f32>]) -> Vec<Point2<f32>> {
+        let mut pos_v: Vec<Point2<f32>> = (0..corner_v.len())
+            .flat_map(|i| segment(corner_v[i], corner_v[(i + 1) % corner_v.len()], 20))
+            .collect();
+        pos_v.push(corner_v[0]);
+        pos_v
+    }
+
+    fn ellipse(center: Point2<f32>, rx: f32, ry: f32) -> Vec<Point2<f32>> {
+        (0..=60)
+            .map(|i| {
+                let t = std::f32::consts::TAU * i as f32 / 60.0;
+                Point2::new(center.x + rx * t.cos(), center.y + ry * t.sin())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lines() {
+        let point_v = stroke(
+            segment(Point2::new(0.0, 0.0), Point2::new(100.0, 40.0), 30),
+            0.5,
+        );
+        assert!(matches!(
+            recognize(&point_v, MIN_CONFIDENCE),
+            Some(Shape::Line(..))
+        ));
+    }
+
+    #[test]
+    fn rectangles() {
+        let corner_v = [
+            Point2::new(0.0, 0.0),
+            Point2::new(120.0, 0.0),
+            Point2::new(120.0, 60.0),
+            Point2::new(0.0, 60.0),
+        ];
+        let point_v = stroke(polygon(&corner_v), 0.8);
+        match recognize(&point_v, MIN_CONFIDENCE) {
+            Some(Shape::Rectangle(fit_v)) => {
+                for corner in corner_v {
+                    assert!(fit_v.iter().any(|fit| fit.distance(corner) < 5.0));
+                }
+            }
+            shape => panic!("not a rectangle: {shape:?}"),
+        }
+    }
+
+    #[test]
+    fn triangles() {
+        let corner_v = [
+            Point2::new(0.0, 0.0),
+            Point2::new(100.0, 0.0),
+            Point2::new(50.0, 80.0),
+        ];
+        let point_v = stroke(polygon(&corner_v), 0.8);
+        assert!(matches!(
+            recognize(&point_v, MIN_CONFIDENCE),
+            Some(Shape::Triangle(_))
+        ));
+    }
+
+    #[test]
+    fn ellipses() {
+        let point_v = stroke(ellipse(Point2::new(50.0, 50.0), 100.0, 40.0), 1.0);
+        match recognize(&point_v, MIN_CONFIDENCE) {
+            Some(Shape::Ellipse { center, radii, .. }) => {
+                assert!(center.distance(Point2::new(50.0, 50.0)) < 5.0);
+                let (long, short) = (radii.x.max(radii.y), radii.x.min(radii.y));
+                assert!((long - 100.0).abs() < 5.0 && (short - 40.0).abs() < 5.0);
+            }
+            shape => panic!("not an ellipse: {shape:?}"),
+        }
+    }
+
+    #[test]
+    fn circles() {
+        let point_v = stroke(ellipse(Point2::new(0.0, 0.0), 60.0, 58.0), 1.0);
+        match recognize(&point_v, MIN_CONFIDENCE) {
+            Some(Shape::Circle { center, radius }) => {
+                assert!(center.distance(Point2::new(0.0, 0.0)) < 5.0);
+                assert!((radius - 59.0).abs() < 5.0);
+            }
+            shape => panic!("not a circle: {shape:?}"),
+        }
+    }
+
+    #[test]
+    fn scribbles_are_no_shape() {
+        let zigzag =
+            (0..40).map(|i| Point2::new(i as f32 * 5.0, if i % 2 == 0 { 0.0 } else { 40.0 }));
+        let spiral = (0..120).map(|i| {
+            let t = i as f32 * 0.15;
+            Point2::new(t * 8.0 * t.cos(), t * 8.0 * t.sin())
+        });
+        let wave = (0..80).map(|i| {
+            let x = i as f32 * 2.0;
+            Point2::new(x, 30.0 * (x / 25.0).sin())
+        });
+        for (name, pos_v) in [
+            ("zigzag", zigzag.collect::<Vec<_>>()),
+            ("spiral", spiral.collect()),
+            ("wave", wave.collect()),
+        ] {
+            let shape = recognize(&stroke(pos_v, 0.5), MIN_CONFIDENCE);
+            assert_eq!(shape, None, "{name} taken for a shape");
+        }
+    }
+
+    fn stylus_v(time_v: &[f32]) -> Vec<Stylus> {
+        time_v
+            .iter()
+            .map(|time| Stylus {
+                time: *time,
+                ..Stylus::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn holds_through_jitter() {
+        // Drawn to (100, 0) by 300 ms, then shaking in place.
+        let mut pos_v = segment(Point2::new(0.0, 0.0), Point2::new(100.0, 0.0), 10);
+        pos_v.extend([
+            Point2::new(100.0, 0.0),
+            Point2::new(101.5, -1.0),
+            Point2::new(99.0, 2.0),
+            Point2::new(100.5, 0.5),
+        ]);
+        let time_v: Vec<f32> = (0..10)
+            .map(|i| i as f32 * 30.0)
+            .chain([300.0, 450.0, 600.0, 750.0])
+            .collect();
+        let point_v = stroke(pos_v, 0.0);
+        assert_eq!(hold_ms(&point_v, &stylus_v(&time_v), 4.0, 800.0), 500.0);
+        // A tighter radius sees the shake as moving.
+        assert_eq!(hold_ms(&point_v, &stylus_v(&time_v), 1.0, 800.0), 50.0);
+    }
+
+    #[test]
+    fn no_hold_without_rest() {
+        let pos_v = segment(Point2::new(0.0, 0.0), Point2::new(100.0, 0.0), 10);
+        let time_v: Vec<f32> = (0..10).map(|i| i as f32 * 30.0).collect();
+        let point_v = stroke(pos_v, 0.0);
+        assert_eq!(hold_ms(&point_v, &stylus_v(&time_v), 4.0, 290.0), 20.0);
+        assert_eq!(hold_ms(&[], &[], 4.0, 290.0), 0.0);
+    }
+}
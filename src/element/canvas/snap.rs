@@ -0,0 +1,66 @@
+use cgmath::{InnerSpace, MetricSpace, Point2, Vector2};
+use serde::{Deserialize, Serialize};
+
+use super::Paper;
+
+/// Angles lines snap to while Shift is held.
+const ANGLE_STEP: f32 = std::f32::consts::PI / 12.0;
+
+// Public
+/// Which kinds of snapping are on; angles snap whenever Shift is held.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snap {
+    /// Snap to where the lines of the paper cross.
+    pub grid: bool,
+    /// Snap to the ends of other edges.
+    pub endpoint: bool,
+}
+
+/// What a point snapped to, shown while dragging.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Guide {
+    #[default]
+    None,
+    Grid,
+    Endpoint,
+    Angle,
+}
+
+/// `pos` pulled to the closest of `endpoint_v`, or failing that to the
+/// paper grid, whichever is within `radius`. All in world units.
+pub fn snap_point(
+    pos: Point2<f32>,
+    snap: Snap,
+    paper: &Paper,
+    endpoint_v: impl IntoIterator<Item = Point2<f32>>,
+    radius: f32,
+) -> (Point2<f32>, Guide) {
+    if snap.endpoint {
+        let closest = endpoint_v
+            .into_iter()
+            .map(|pt| (pt.distance2(pos), pt))
+            .filter(|(d2, _)| *d2 <= radius * radius)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, pt)) = closest {
+            return (pt, Guide::Endpoint);
+        }
+    }
+    if snap.grid {
+        if let Some(pt) = paper.nearest(pos).filter(|pt| pt.distance(pos) <= radius) {
+            return (pt, Guide::Grid);
+        }
+    }
+    (pos, Guide::None)
+}
+
+/// `to` turned around `from` to the closest multiple of 15°, keeping its
+/// distance along that direction.
+pub fn snap_angle(from: Point2<f32>, to: Point2<f32>) -> Point2<f32> {
+    let v = to - from;
+    if v.magnitude2() == 0.0 {
+        return to;
+    }
+    let angle = (v.y.atan2(v.x) / ANGLE_STEP).round() * ANGLE_STEP;
+    let dir = Vector2::new(angle.cos(), angle.sin());
+    from + dir * v.dot(dir).max(0.0)
+}
@@ -0,0 +1,483 @@
+use cgmath::*;
+use js_sys::Math::exp;
+use yew::{Callback, KeyboardEvent, WheelEvent};
+
+use std::sync::{Arc, Mutex};
+
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{HtmlCanvasElement, PointerEvent};
+use winit::{event_loop::EventLoop, platform::web::EventLoopExtWebSys};
+
+use crate::{
+    element::{FeedListener, ViewportFeed},
+    err, metrics,
+};
+
+use super::{raw_canvas::RawCanvas, worker_scene::WorkerScene, *};
+
+/// Calls method `name` of `target` with `arg_v`, if it has one.
+pub(super) fn call(target: &JsValue, name: &str, arg_v: &js_sys::Array) -> Option<JsValue> {
+    js_sys::Reflect::get(target, &JsValue::from_str(name))
+        .ok()?
+        .dyn_into::<js_sys::Function>()
+        .ok()?
+        .apply(target, arg_v)
+        .ok()
+}
+
+/// A `ResizeObserver` calling `on_resize` whenever `element` is laid out at
+/// another size. Looked up by name, as web-sys only has it as unstable.
+fn observe_resize(
+    element: &HtmlCanvasElement,
+    on_resize: &Closure<dyn FnMut()>,
+) -> Option<JsValue> {
+    let class = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("ResizeObserver"))
+        .ok()?
+        .dyn_into::<js_sys::Function>()
+        .ok()?;
+    let observer =
+        js_sys::Reflect::construct(&class, &js_sys::Array::of1(on_resize.as_ref())).ok()?;
+    call(&observer, "observe", &js_sys::Array::of1(element))?;
+    Some(observer)
+}
+
+/// What changed from `old` to `new` besides the viewport, or all of it
+/// without `old`. Edges come last, so they are drawn with the rest in place.
+fn delta_v(old: Option<&Props>, new: &Props) -> Vec<Delta> {
+    let mut delta_v = Vec::new();
+    if old.is_none_or(|old| old.tool != new.tool) {
+        delta_v.push(Delta::Tool(new.tool));
+    }
+    if old.is_none_or(|old| old.layer_id != new.layer_id) {
+        delta_v.push(Delta::LayerId(new.layer_id.clone()));
+    }
+    if old.is_none_or(|old| old.snap != new.snap) {
+        delta_v.push(Delta::Snap(new.snap));
+    }
+    if old.is_none_or(|old| old.paper != new.paper) {
+        delta_v.push(Delta::Paper(new.paper));
+    }
+    if old.is_none_or(|old| old.layer_v != new.layer_v) {
+        delta_v.push(Delta::Layers(new.layer_v.clone()));
+    }
+    if old.is_none_or(|old| old.selected_id_v != new.selected_id_v) {
+        delta_v.push(Delta::Selected(new.selected_id_v.clone()));
+    }
+    if old.is_none_or(|old| old.peer_ink_v != new.peer_ink_v) {
+        delta_v.push(Delta::PeerInks(new.peer_ink_v.clone()));
+    }
+    if old.is_none_or(|old| old.edge_v != new.edge_v) {
+        let old_edge_v = old.map_or(&[][..], |old| &old.edge_v[..]);
+        delta_v.extend(Delta::edges(old_edge_v, &new.edge_v));
+    }
+    delta_v
+}
+
+/// Where the scene is drawn.
+enum Drawer {
+    /// Nothing is set up to draw with yet.
+    None,
+    /// On the page, on the canvas in `p_canvas`.
+    Here(Box<Scene>),
+    InWorker(WorkerScene),
+}
+
+// Public
+pub enum Message {
+    /// Draw on the page, on the canvas just set up.
+    Create(EventLoop<()>),
+    /// Let this worker draw.
+    CreateInWorker(WorkerScene),
+    /// Nothing could be set up to draw with.
+    Fail(err::Error),
+    /// The canvas was laid out at another size.
+    Resize,
+    /// An animation frame asked for came.
+    Frame,
+    Input(InputEvent),
+    /// What the scene told, from wherever it runs.
+    Out(Vec<Out>),
+    /// The owner moved the view.
+    View(Viewport),
+}
+
+#[derive(Clone, Debug, yew::Properties, PartialEq)]
+pub struct Props {
+    #[prop_or_default]
+    pub commit: Callback<Edge>,
+    /// Called with the id of every edge the eraser touches.
+    #[prop_or_default]
+    pub erase: Callback<String>,
+    /// Called with the ids of the edges inside a selection rectangle.
+    #[prop_or_default]
+    pub select: Callback<Vec<String>>,
+    #[prop_or_default]
+    pub edge_v: EdgeList,
+    #[prop_or_default]
+    pub selected_id_v: Vec<String>,
+    #[prop_or_default]
+    pub tool: Tool,
+    /// Bottom layer first. Edges on layers not listed here go below them all.
+    #[prop_or_default]
+    pub layer_v: Vec<Layer>,
+    /// Layer new edges go on.
+    #[prop_or(DEFAULT_LAYER_ID.to_string())]
+    pub layer_id: String,
+    #[prop_or_default]
+    pub paper: Paper,
+    #[prop_or_default]
+    pub snap: Snap,
+    /// Where the board is seen from. The canvas follows it, and tells its own
+    /// moves through `on_viewport`.
+    #[prop_or_default]
+    pub viewport: ViewportFeed,
+    /// Called whenever panning, zooming or resizing changes the viewport.
+    #[prop_or_default]
+    pub on_viewport: Callback<Viewport>,
+    /// Called if nothing could be set up to draw with.
+    #[prop_or_default]
+    pub on_error: Callback<err::Error>,
+    /// Draw in a worker where the browser allows it. Only read when the
+    /// canvas is first shown.
+    #[prop_or_default]
+    pub offscreen: bool,
+    /// Called with the laser or ephemeral stroke being drawn once every
+    /// frame, then once finished, for showing live to others. These never
+    /// reach `commit`.
+    #[prop_or_default]
+    pub on_ink: Callback<Ink>,
+    /// Called when ephemeral ink is wiped, as the view moved away.
+    #[prop_or_default]
+    pub on_clear_ink: Callback<()>,
+    /// Laser and ephemeral strokes of others, shown over the board.
+    #[prop_or_default]
+    pub peer_ink_v: Vec<Ink>,
+    /// Called with where the pointer is over the board, in world space, as it moves.
+    #[prop_or_default]
+    pub on_pointer: Callback<Point2<f32>>,
+}
+
+pub struct Canvas {
+    canvas: yew::NodeRef,
+    p_canvas: Arc<Mutex<Option<RawCanvas>>>,
+    drawer: Drawer,
+    /// The viewport as last told to the owner.
+    viewport: Viewport,
+    view_listener: FeedListener,
+    frames: FrameScheduler,
+    /// Watches the layout size of the canvas, for as long as it lives.
+    resize_observer: Option<(JsValue, Closure<dyn FnMut()>)>,
+}
+
+impl Canvas {
+    fn canvas_size(&self) -> Vector2<f32> {
+        self.canvas
+            .cast::<HtmlCanvasElement>()
+            .map_or(Vector2::new(0.0, 0.0), |canvas| {
+                Vector2::new(canvas.client_width() as f32, canvas.client_height() as f32)
+            })
+    }
+
+    /// Hand `delta_v` to the scene, wherever it runs.
+    fn apply(&mut self, ctx: &yew::Context<Self>, delta_v: Vec<Delta>) {
+        match &mut self.drawer {
+            Drawer::None => return,
+            Drawer::Here(scene) => {
+                let mut op = self.p_canvas.lock().unwrap();
+                let Some(raw_canvas) = op.as_mut() else {
+                    return;
+                };
+                for delta in delta_v {
+                    scene.apply(&mut raw_canvas.canvas, delta);
+                }
+            }
+            Drawer::InWorker(worker) => {
+                for delta in delta_v {
+                    worker.apply(delta);
+                }
+            }
+        }
+        self.flush(ctx);
+    }
+
+    /// Hand `input` to the scene, wherever it runs.
+    fn input(&mut self, ctx: &yew::Context<Self>, input: InputEvent) {
+        match &mut self.drawer {
+            Drawer::None => return,
+            Drawer::Here(scene) => {
+                let mut op = self.p_canvas.lock().unwrap();
+                let Some(raw_canvas) = op.as_mut() else {
+                    return;
+                };
+                scene.input(&mut raw_canvas.canvas, input);
+            }
+            Drawer::InWorker(worker) => worker.input(input),
+        }
+        self.flush(ctx);
+    }
+
+    /// Size the surface to the canvas as laid out.
+    fn fit(&mut self, ctx: &yew::Context<Self>) {
+        match &self.drawer {
+            Drawer::None => (),
+            Drawer::Here(_) => {
+                if let Some(raw_canvas) = self.p_canvas.lock().unwrap().as_mut() {
+                    raw_canvas.fit();
+                }
+                let size = self.canvas_size();
+                self.apply(ctx, vec![Delta::Size(size)]);
+            }
+            Drawer::InWorker(worker) => worker.fit(),
+        }
+    }
+
+    /// Pass on what the scene running here told, and ask for a frame if it
+    /// has something to render.
+    fn flush(&mut self, ctx: &yew::Context<Self>) {
+        let Drawer::Here(scene) = &mut self.drawer else {
+            return;
+        };
+        let out_v = scene.take_out_v();
+        if scene.wants_frame() {
+            self.frames.request();
+        }
+        self.tell(ctx, out_v);
+    }
+
+    /// Tell the owner what the scene told.
+    fn tell(&mut self, ctx: &yew::Context<Self>, out_v: Vec<Out>) {
+        let props = ctx.props();
+        for out in out_v {
+            match out {
+                Out::Commit(edge) => props.commit.emit(edge),
+                Out::Erase(id) => props.erase.emit(id),
+                Out::Select(id_v) => props.select.emit(id_v),
+                Out::Viewport(viewport) => {
+                    self.viewport = viewport;
+                    props.on_viewport.emit(viewport);
+                }
+                Out::Ink(ink) => props.on_ink.emit(ink),
+                Out::ClearInk => props.on_clear_ink.emit(()),
+                Out::Pointer(pt) => props.on_pointer.emit(pt),
+                Out::Frame {
+                    ms,
+                    edge_n,
+                    point_n,
+                    upload_bytes,
+                } => {
+                    metrics::update(|m| {
+                        m.edge_n = edge_n;
+                        m.point_n = point_n;
+                        m.upload_bytes = upload_bytes;
+                    });
+                    metrics::record_frame(metrics::now() - ms as f64);
+                }
+            }
+        }
+    }
+
+    /// Watch the canvas for resizing and show the scene everything, once
+    /// there is something to draw with.
+    fn start(&mut self, ctx: &yew::Context<Self>) {
+        let link = ctx.link().clone();
+        let on_resize = Closure::<dyn FnMut()>::new(move || {
+            link.send_message(Message::Resize);
+        });
+        let observer = self
+            .canvas
+            .cast::<HtmlCanvasElement>()
+            .and_then(|html_canvas| observe_resize(&html_canvas, &on_resize));
+        match observer {
+            Some(observer) => self.resize_observer = Some((observer, on_resize)),
+            None => log::warn!("can't watch the canvas for resizing"),
+        }
+        self.apply(ctx, vec![Delta::Viewport(self.viewport)]);
+        self.fit(ctx);
+        self.apply(ctx, delta_v(None, ctx.props()));
+    }
+}
+
+impl yew::Component for Canvas {
+    type Message = Message;
+
+    type Properties = Props;
+
+    fn create(ctx: &yew::Context<Self>) -> Self {
+        let link = ctx.link().clone();
+        // The page has one thread; the 2D fallback holds browser objects.
+        #[allow(clippy::arc_with_non_send_sync)]
+        let p_canvas = Arc::new(Mutex::new(None));
+        Self {
+            canvas: yew::NodeRef::default(),
+            p_canvas,
+            drawer: Drawer::None,
+            viewport: ctx.props().viewport.get(),
+            view_listener: ctx
+                .props()
+                .viewport
+                .listen(ctx.link().callback(Message::View)),
+            frames: FrameScheduler::new(move || link.send_message(Message::Frame)),
+            resize_observer: None,
+        }
+    }
+
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let link = ctx.link().clone();
+        let canvas = self.canvas.clone();
+        let onpointerdown = yew::Callback::from(move |e: PointerEvent| {
+            if let Some(canvas) = canvas.cast::<HtmlCanvasElement>() {
+                let _ = canvas.set_pointer_capture(e.pointer_id());
+            }
+            link.send_message(Message::Input(InputEvent::Down(Sample::from_event(&e))));
+        });
+
+        let link = ctx.link().clone();
+        let onpointerup = yew::Callback::from(move |e: PointerEvent| {
+            link.send_message(Message::Input(InputEvent::Up(e.time_stamp())));
+        });
+
+        let link = ctx.link().clone();
+        let onpointermove = yew::Callback::from(move |e: PointerEvent| {
+            link.send_message(Message::Input(InputEvent::Move(
+                Sample::coalesced(&e),
+                Sample::predicted(&e),
+            )));
+        });
+
+        let link = ctx.link().clone();
+        let on_key_down = Callback::from(move |e: KeyboardEvent| {
+            if e.key() == " " {
+                link.send_message(Message::Input(InputEvent::Moving(true)));
+            }
+        });
+
+        let link = ctx.link().clone();
+        let on_key_up = Callback::from(move |e: KeyboardEvent| {
+            if e.key() == " " {
+                link.send_message(Message::Input(InputEvent::Moving(false)));
+            }
+        });
+
+        let link = ctx.link().clone();
+        let on_wheel = Callback::from(move |e: WheelEvent| {
+            let speed = e.delta_y() / 100.0;
+            log::debug!("wheel speed: {speed}");
+            link.send_message(Message::Input(InputEvent::Wheel(
+                exp(-speed) as f32,
+                e.offset_x() as f32,
+                e.offset_y() as f32,
+            )));
+        });
+
+        yew::html! {
+            <canvas ref={self.canvas.clone()}
+                class={"full"}
+                {onpointerdown}
+                {onpointerup}
+                {onpointermove}
+                onkeydown={on_key_down}
+                onkeyup={on_key_up}
+                onwheel={on_wheel} />
+        }
+    }
+
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Message::Create(event_loop) => {
+                let p_canvas = self.p_canvas.clone();
+                event_loop.spawn(move |event, target, control_flow| {
+                    let mut op = p_canvas.lock().unwrap();
+                    if let Some(raw_canvas) = op.as_mut() {
+                        raw_canvas.on_event(event, target, control_flow);
+                    }
+                });
+                self.drawer = Drawer::Here(Box::default());
+                self.start(ctx);
+            }
+            Message::CreateInWorker(worker) => {
+                self.drawer = Drawer::InWorker(worker);
+                self.start(ctx);
+            }
+            Message::Fail(e) => ctx.props().on_error.emit(e),
+            Message::Resize => self.fit(ctx),
+            Message::Frame => {
+                if let Drawer::Here(scene) = &mut self.drawer {
+                    if let Some(raw_canvas) = self.p_canvas.lock().unwrap().as_mut() {
+                        scene.frame(&mut raw_canvas.canvas, metrics::now());
+                    }
+                }
+                self.flush(ctx);
+            }
+            Message::Input(input) => self.input(ctx, input),
+            Message::Out(out_v) => self.tell(ctx, out_v),
+            // The owner usually hands back the viewport this canvas just told it.
+            Message::View(viewport) => {
+                if viewport != self.viewport {
+                    self.viewport = Viewport {
+                        size: self.viewport.size,
+                        ..viewport
+                    };
+                    self.apply(ctx, vec![Delta::Viewport(viewport)]);
+                }
+            }
+        }
+        false
+    }
+
+    fn changed(&mut self, ctx: &yew::prelude::Context<Self>, old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        if props.viewport != old_props.viewport {
+            self.view_listener = props.viewport.listen(ctx.link().callback(Message::View));
+            ctx.link().send_message(Message::View(props.viewport.get()));
+        }
+        let delta_v = delta_v(Some(old_props), props);
+        if !delta_v.is_empty() {
+            self.apply(ctx, delta_v);
+        }
+        false
+    }
+
+    fn destroy(&mut self, _ctx: &yew::Context<Self>) {
+        if let Some((observer, _)) = self.resize_observer.take() {
+            call(&observer, "disconnect", &js_sys::Array::new());
+        }
+    }
+
+    fn rendered(&mut self, ctx: &yew::Context<Self>, first_render: bool) {
+        if !first_render {
+            return;
+        }
+        let p_canvas = self.p_canvas.lock().unwrap();
+        if p_canvas.is_some() {
+            return;
+        }
+        drop(p_canvas);
+
+        let canvas = self.canvas.clone();
+        let p_canvas = self.p_canvas.clone();
+        let offscreen = ctx.props().offscreen;
+        let link = ctx.link().clone();
+        ctx.link().send_future(async move {
+            let rs: err::Result<Message> = async {
+                let html_canvas = canvas.cast::<HtmlCanvasElement>().ok_or(err::Error::Other(
+                    "'HtmlCanvasElement' not found".to_string(),
+                ))?;
+                if offscreen && WorkerScene::is_supported(&html_canvas) {
+                    let on_out_v = move |out_v| link.send_message(Message::Out(out_v));
+                    match WorkerScene::create(&html_canvas, on_out_v).await {
+                        Ok(worker) => return Ok(Message::CreateInWorker(worker)),
+                        Err(e) => log::warn!("{e}, drawing on the main thread"),
+                    }
+                } else if offscreen {
+                    log::info!("no OffscreenCanvas with WebGL2, drawing on the main thread");
+                }
+                let event_loop = EventLoop::new();
+                let raw_canvas = RawCanvas::create(html_canvas, &event_loop).await?;
+                *p_canvas.lock().unwrap() = Some(raw_canvas);
+                Ok(Message::Create(event_loop))
+            }
+            .await;
+            rs.unwrap_or_else(Message::Fail)
+        });
+    }
+}
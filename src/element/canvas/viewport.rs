@@ -0,0 +1,366 @@
+use cgmath::{Basis2, MetricSpace, Point2, Rad, Rotation, Rotation2, Vector2};
+use serde::{Deserialize, Serialize};
+
+use super::Rect;
+
+/// Zoom steps the toolbar walks through.
+const ZOOM_LEVEL_V: [f32; 13] = [
+    0.1, 0.2, 0.25, 0.33, 0.5, 0.67, 1.0, 1.5, 2.0, 3.0, 4.0, 6.0, 8.0,
+];
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 8.0;
+/// Part of the screen left around content that is zoomed to fit.
+const FIT_MARGIN: f32 = 0.05;
+/// Screen pixels the view may drift and still show the same part of the board.
+const SAME_DRIFT: f32 = 24.0;
+/// Zoom ratio, less one, and radians within which views show the same.
+const SAME_ZOOM: f32 = 0.02;
+const SAME_ROTATION: f32 = 0.01;
+
+// Public
+/// The camera looking at the board.
+///
+/// World coordinates are screen pixels at 100% zoom and no rotation. A world
+/// point `w` is shown at `size / 2 + rotate(w - center) * zoom`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Viewport {
+    /// World position shown in the middle of the screen.
+    pub center: Point2<f32>,
+    pub zoom: f32,
+    /// Clockwise rotation of the content on screen, in radians.
+    pub rotation: f32,
+    /// Screen size in pixels.
+    pub size: Vector2<f32>,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            center: Point2::new(0.0, 0.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            size: Vector2::new(0.0, 0.0),
+        }
+    }
+}
+
+impl Viewport {
+    pub fn screen_to_world(&self, pt: Point2<f32>) -> Point2<f32> {
+        self.center + self.unrotate(pt - self.screen_center()) / self.zoom
+    }
+
+    pub fn world_to_screen(&self, pt: Point2<f32>) -> Point2<f32> {
+        self.screen_center() + self.rotate(pt - self.center) * self.zoom
+    }
+
+    /// Length of a screen distance in world units.
+    pub fn screen_to_world_len(&self, len: f32) -> f32 {
+        len / self.zoom
+    }
+
+    /// The smallest world rectangle holding everything on screen.
+    pub fn world_rect(&self) -> Rect {
+        let corner_v = [
+            Point2::new(0.0, 0.0),
+            Point2::new(self.size.x, 0.0),
+            Point2::new(self.size.x, self.size.y),
+            Point2::new(0.0, self.size.y),
+        ];
+        Rect::bound(corner_v.iter().map(|pt| self.screen_to_world(*pt))).unwrap()
+    }
+
+    /// Move the content by `delta` screen pixels.
+    pub fn pan(&self, delta: Vector2<f32>) -> Self {
+        Self {
+            center: self.center - self.unrotate(delta) / self.zoom,
+            ..*self
+        }
+    }
+
+    /// Zoom to `zoom` keeping the world point under `anchor` in place.
+    pub fn zoom_at(&self, zoom: f32, anchor: Point2<f32>) -> Self {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        let world = self.screen_to_world(anchor);
+        Self {
+            center: world - self.unrotate(anchor - self.screen_center()) / zoom,
+            zoom,
+            ..*self
+        }
+    }
+
+    /// Rotate to `rotation` keeping the world point under `anchor` in place.
+    pub fn rotate_at(&self, rotation: f32, anchor: Point2<f32>) -> Self {
+        let world = self.screen_to_world(anchor);
+        let rotated = Self { rotation, ..*self };
+        Self {
+            center: world - rotated.unrotate(anchor - self.screen_center()) / self.zoom,
+            ..rotated
+        }
+    }
+
+    /// Rotate by `delta` radians around the middle of the screen.
+    pub fn rotate_by(&self, delta: f32) -> Self {
+        let rotation = (self.rotation + delta).rem_euclid(std::f32::consts::TAU);
+        self.rotate_at(rotation, self.screen_center())
+    }
+
+    /// Zoom to the next level up, around the middle of the screen.
+    pub fn zoom_in(&self) -> Self {
+        let zoom = ZOOM_LEVEL_V
+            .iter()
+            .find(|level| **level > self.zoom * 1.001)
+            .copied()
+            .unwrap_or(MAX_ZOOM);
+        self.zoom_at(zoom, self.screen_center())
+    }
+
+    /// Zoom to the next level down, around the middle of the screen.
+    pub fn zoom_out(&self) -> Self {
+        let zoom = ZOOM_LEVEL_V
+            .iter()
+            .rev()
+            .find(|level| **level < self.zoom / 1.001)
+            .copied()
+            .unwrap_or(MIN_ZOOM);
+        self.zoom_at(zoom, self.screen_center())
+    }
+
+    pub fn reset_zoom(&self) -> Self {
+        self.zoom_at(1.0, self.screen_center())
+    }
+
+    /// Center `rect` and zoom so it fills the screen at the current rotation.
+    ///
+    /// The zoom stays as it is when the screen has no size yet or `rect`
+    /// has none at all, as there is nothing to fit then.
+    pub fn fit(&self, rect: &Rect) -> Self {
+        let half_v = [
+            rect.max - rect.center(),
+            Vector2::new(rect.max.x - rect.center().x, rect.min.y - rect.center().y),
+        ];
+        let extent = half_v.iter().fold(Vector2::new(0.0f32, 0.0), |acc, half| {
+            let v = self.rotate(*half);
+            Vector2::new(acc.x.max(v.x.abs()), acc.y.max(v.y.abs()))
+        }) * 2.0;
+
+        let avail = self.size * (1.0 - 2.0 * FIT_MARGIN);
+        if avail.x <= 0.0 || avail.y <= 0.0 {
+            return Self {
+                center: rect.center(),
+                ..*self
+            };
+        }
+        let zoom = match (extent.x > 0.0, extent.y > 0.0) {
+            (true, true) => (avail.x / extent.x).min(avail.y / extent.y),
+            (true, false) => avail.x / extent.x,
+            (false, true) => avail.y / extent.y,
+            (false, false) => self.zoom,
+        };
+        Self {
+            center: rect.center(),
+            zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+            ..*self
+        }
+    }
+
+    /// Zoom as a whole percentage, for display.
+    pub fn percent(&self) -> u32 {
+        (self.zoom * 100.0).round() as u32
+    }
+
+    /// Rotation in whole degrees, for display.
+    pub fn degrees(&self) -> i32 {
+        self.rotation.to_degrees().round() as i32
+    }
+
+    /// True if both show about the same part of the board, whatever the
+    /// screen size: their centers are within `SAME_DRIFT` pixels of each
+    /// other in world space, and they barely differ in zoom and rotation.
+    pub fn shows_same(&self, other: &Self) -> bool {
+        let turn = (self.rotation - other.rotation).rem_euclid(std::f32::consts::TAU);
+        self.center.distance(other.center) <= self.screen_to_world_len(SAME_DRIFT)
+            && (self.zoom / other.zoom - 1.0).abs() <= SAME_ZOOM
+            && turn.min(std::f32::consts::TAU - turn) <= SAME_ROTATION
+    }
+
+    fn screen_center(&self) -> Point2<f32> {
+        Point2::new(self.size.x / 2.0, self.size.y / 2.0)
+    }
+
+    /// World direction to screen direction.
+    fn rotate(&self, v: Vector2<f32>) -> Vector2<f32> {
+        Basis2::from_angle(Rad(self.rotation)).rotate_vector(v)
+    }
+
+    /// Screen direction to world direction.
+    fn unrotate(&self, v: Vector2<f32>) -> Vector2<f32> {
+        Basis2::from_angle(Rad(-self.rotation)).rotate_vector(v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{MetricSpace, Point2, Vector2};
+
+    use super::{Rect, Viewport, MAX_ZOOM, MIN_ZOOM};
+
+    /// Numbers in [0, 1) from a fixed seed, so failures repeat.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        fn range(&mut self, lo: f32, hi: f32) -> f32 {
+            lo + (hi - lo) * self.next()
+        }
+
+        fn viewport(&mut self) -> Viewport {
+            Viewport {
+                center: Point2::new(self.range(-5000.0, 5000.0), self.range(-5000.0, 5000.0)),
+                zoom: self.range(MIN_ZOOM, MAX_ZOOM),
+                rotation: self.range(0.0, std::f32::consts::TAU),
+                size: Vector2::new(self.range(100.0, 3000.0), self.range(100.0, 3000.0)),
+            }
+        }
+    }
+
+    const CASE_N: usize = 1000;
+
+    /// Allowed error, in pixels, for points `scale` pixels from the origin.
+    fn tolerance(scale: f32) -> f32 {
+        1e-3 * scale.max(1.0)
+    }
+
+    #[test]
+    fn screen_world_round_trip() {
+        let mut rng = Lcg(1);
+        for _ in 0..CASE_N {
+            let viewport = rng.viewport();
+            let screen = Point2::new(
+                rng.range(0.0, viewport.size.x),
+                rng.range(0.0, viewport.size.y),
+            );
+            let back = viewport.world_to_screen(viewport.screen_to_world(screen));
+            assert!(
+                back.distance(screen) < tolerance(viewport.size.x.max(viewport.size.y)),
+                "{viewport:?}: {screen:?} came back as {back:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn round_trip_after_pan() {
+        let mut rng = Lcg(2);
+        for _ in 0..CASE_N {
+            let viewport = rng.viewport();
+            let delta = Vector2::new(rng.range(-500.0, 500.0), rng.range(-500.0, 500.0));
+            let panned = viewport.pan(delta);
+            let world = Point2::new(rng.range(-5000.0, 5000.0), rng.range(-5000.0, 5000.0));
+            // Panning moves every point by `delta` on screen.
+            let moved = panned.world_to_screen(world) - viewport.world_to_screen(world);
+            let scale = world.distance(viewport.center) * viewport.zoom;
+            assert!(
+                (moved - delta).x.abs() < tolerance(scale)
+                    && (moved - delta).y.abs() < tolerance(scale),
+                "{viewport:?} panned by {delta:?} moved {world:?} by {moved:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn zoom_at_keeps_anchor() {
+        let mut rng = Lcg(3);
+        for _ in 0..CASE_N {
+            let viewport = rng.viewport();
+            let anchor = Point2::new(
+                rng.range(0.0, viewport.size.x),
+                rng.range(0.0, viewport.size.y),
+            );
+            let zoomed = viewport.zoom_at(rng.range(MIN_ZOOM, MAX_ZOOM), anchor);
+            let before = viewport.screen_to_world(anchor);
+            let after = zoomed.screen_to_world(anchor);
+            assert!(
+                before.distance(after) * zoomed.zoom
+                    < tolerance(viewport.size.x.max(viewport.size.y)),
+                "{viewport:?}: anchor {anchor:?} moved from {before:?} to {after:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn fit_covers_rect() {
+        let mut rng = Lcg(4);
+        for _ in 0..CASE_N {
+            let viewport = rng.viewport();
+            let min = Point2::new(rng.range(-5000.0, 5000.0), rng.range(-5000.0, 5000.0));
+            let rect = Rect::from_corners(
+                min,
+                min + Vector2::new(rng.range(1.0, 2000.0), rng.range(1.0, 2000.0)),
+            );
+            let fitted = viewport.fit(&rect);
+            if fitted.zoom <= MIN_ZOOM || fitted.zoom >= MAX_ZOOM {
+                // Clamped, so it may not fit exactly.
+                continue;
+            }
+            let slack = tolerance(viewport.size.x.max(viewport.size.y));
+            for corner in rect.corner_v() {
+                let screen = fitted.world_to_screen(corner);
+                assert!(
+                    -slack <= screen.x
+                        && screen.x <= fitted.size.x + slack
+                        && -slack <= screen.y
+                        && screen.y <= fitted.size.y + slack,
+                    "{fitted:?} leaves {corner:?} of {rect:?} at {screen:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fit_without_size_keeps_zoom() {
+        let viewport = Viewport {
+            zoom: 2.0,
+            ..Viewport::default()
+        };
+        let rect = Rect::from_corners(Point2::new(10.0, 20.0), Point2::new(110.0, 220.0));
+        let fitted = viewport.fit(&rect);
+        assert_eq!(fitted.zoom, 2.0);
+        assert_eq!(fitted.center, rect.center());
+
+        let sized = Viewport {
+            size: Vector2::new(800.0, 600.0),
+            ..viewport
+        };
+        let point = Rect::from_corners(Point2::new(5.0, 5.0), Point2::new(5.0, 5.0));
+        assert_eq!(sized.fit(&point).zoom, 2.0);
+    }
+
+    #[test]
+    fn same_view_after_a_nudge() {
+        let viewport = Viewport {
+            center: Point2::new(300.0, -200.0),
+            zoom: 2.0,
+            rotation: 0.001,
+            size: Vector2::new(800.0, 600.0),
+        };
+        let resized = Viewport {
+            size: Vector2::new(1024.0, 768.0),
+            ..viewport
+        };
+        assert!(viewport.shows_same(&resized));
+        assert!(viewport.shows_same(&viewport.pan(Vector2::new(1.0, -1.0))));
+        assert!(viewport.shows_same(&Viewport {
+            rotation: std::f32::consts::TAU - 0.001,
+            ..viewport
+        }));
+        assert!(!viewport.shows_same(&viewport.pan(Vector2::new(100.0, 0.0))));
+        assert!(!viewport.shows_same(&viewport.zoom_in()));
+        assert!(!viewport.shows_same(&viewport.rotate_by(0.1)));
+    }
+}
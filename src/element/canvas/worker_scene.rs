@@ -0,0 +1,198 @@
+use cgmath::Vector2;
+use js_sys::{Array, Object, Promise, Reflect};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{HtmlCanvasElement, MessageEvent, OffscreenCanvas, Worker};
+use winit::dpi::PhysicalSize;
+
+use crate::{err, metrics, render_worker::ToWorker, util};
+
+use super::{
+    raw_canvas::{device_pixel_ratio, overlay, surface_size},
+    Delta, InputEvent, Out,
+};
+
+/// Script of the worker, next to the page.
+const WORKER_SCRIPT: &str = "render_worker_loader.js";
+/// Milliseconds the worker gets to set up `painting` before the page draws instead.
+const READY_TIMEOUT_MS: i32 = 10_000;
+
+/// What went wrong, from an answer of the worker, an error event or a message.
+fn reason(value: &JsValue) -> String {
+    value
+        .as_string()
+        .or_else(|| {
+            Reflect::get(value, &JsValue::from_str("message"))
+                .ok()
+                .and_then(|message| message.as_string())
+        })
+        .unwrap_or("the render worker failed".to_string())
+}
+
+/// Size of `html_canvas` as laid out, in CSS pixels.
+fn client_size(html_canvas: &HtmlCanvasElement) -> PhysicalSize<u32> {
+    PhysicalSize::new(
+        html_canvas.client_width() as u32,
+        html_canvas.client_height() as u32,
+    )
+}
+
+/// Give `html_canvas` to `worker` and wait until it can draw on it.
+async fn start(worker: &Worker, html_canvas: &HtmlCanvasElement) -> err::Result<()> {
+    let size = surface_size(client_size(html_canvas), device_pixel_ratio());
+    html_canvas.set_width(size.width);
+    html_canvas.set_height(size.height);
+    let offscreen: OffscreenCanvas = html_canvas
+        .transfer_control_to_offscreen()
+        .map_err(|_| err::Error::Gpu("failed to hand the canvas to the worker".to_string()))?;
+    let init = Object::new();
+    let _ = Reflect::set(&init, &JsValue::from_str("canvas"), &offscreen);
+    let _ = Reflect::set(
+        &init,
+        &JsValue::from_str("origin"),
+        &JsValue::from_f64(metrics::time_origin()),
+    );
+    worker
+        .post_message_with_transfer(&init, &Array::of1(&offscreen))
+        .map_err(util::map_js_error)?;
+
+    // Whichever comes first settles it: the answer, an error or the timeout.
+    let answer = JsFuture::from(Promise::new(&mut |resolve, reject| {
+        let on_answer = Closure::once_into_js(move |e: MessageEvent| {
+            let _ = resolve.call1(&JsValue::NULL, &e.data());
+        });
+        worker.set_onmessage(Some(on_answer.unchecked_ref()));
+        let fail = |reject: js_sys::Function| {
+            Closure::once_into_js(move |e: JsValue| {
+                let _ = reject.call1(&JsValue::NULL, &JsValue::from_str(&reason(&e)));
+            })
+        };
+        let on_error = fail(reject.clone());
+        worker.set_onerror(Some(on_error.unchecked_ref()));
+        let on_message_error = fail(reject.clone());
+        worker.set_onmessageerror(Some(on_message_error.unchecked_ref()));
+        let on_timeout = Closure::once_into_js(move || {
+            let message = "the render worker took too long to start";
+            let _ = reject.call1(&JsValue::NULL, &JsValue::from_str(message));
+        });
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(
+                on_timeout.unchecked_ref(),
+                READY_TIMEOUT_MS,
+            );
+        }
+    }))
+    .await;
+    worker.set_onmessage(None);
+    worker.set_onerror(None);
+    worker.set_onmessageerror(None);
+    let answer = answer.map_err(|e| err::Error::Gpu(reason(&e)))?;
+    if answer.as_bool() != Some(true) {
+        return Err(err::Error::Gpu(reason(&answer)));
+    }
+    Ok(())
+}
+
+// Public
+/// The scene as run by the render worker, which draws it on a canvas of its
+/// own through `painting`.
+///
+/// The page only sends what the pointer does and what changed in the board,
+/// and hears back what the scene tells.
+pub struct WorkerScene {
+    worker: Worker,
+    /// Laid over the page's canvas, which keeps taking the pointer.
+    html_canvas: HtmlCanvasElement,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl WorkerScene {
+    /// True if this browser can hand a canvas to a worker and draw on it there.
+    pub fn is_supported(html_canvas: &HtmlCanvasElement) -> bool {
+        let has_transfer = Reflect::has(
+            html_canvas,
+            &JsValue::from_str("transferControlToOffscreen"),
+        )
+        .unwrap_or(false);
+        has_transfer
+            && OffscreenCanvas::new(1, 1)
+                .and_then(|canvas| canvas.get_context("webgl2"))
+                .is_ok_and(|context| context.is_some())
+    }
+
+    /// Start a worker drawing over `under` and wait for it to be ready,
+    /// calling `on_out_v` with what its scene tells from then on. On failure
+    /// nothing is left behind, and `under` can still be drawn on.
+    pub async fn create(
+        under: &HtmlCanvasElement,
+        on_out_v: impl Fn(Vec<Out>) + 'static,
+    ) -> err::Result<Self> {
+        let base_uri = util::get_base_uri()?.unwrap_or_default();
+        let worker = Worker::new(&format!("{base_uri}{WORKER_SCRIPT}"))
+            .map_err(|_| err::Error::Gpu(format!("failed to start {WORKER_SCRIPT}")))?;
+        let html_canvas = match overlay(under) {
+            Ok(html_canvas) => html_canvas,
+            Err(e) => {
+                worker.terminate();
+                return Err(e);
+            }
+        };
+        if let Err(e) = start(&worker, &html_canvas).await {
+            worker.terminate();
+            html_canvas.remove();
+            return Err(e);
+        }
+
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |e: MessageEvent| {
+            match e
+                .data()
+                .as_string()
+                .map(|s| serde_json::from_str::<Vec<Out>>(&s))
+            {
+                Some(Ok(out_v)) => on_out_v(out_v),
+                _ => log::warn!("unexpected message from the render worker: {:?}", e.data()),
+            }
+        });
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        Ok(Self {
+            worker,
+            html_canvas,
+            _on_message: on_message,
+        })
+    }
+
+    fn post(&self, msg: &ToWorker) {
+        match serde_json::to_string(msg) {
+            Ok(s) => {
+                let _ = self.worker.post_message(&JsValue::from_str(&s));
+            }
+            Err(e) => log::error!("failed to encode a message for the render worker: {e}"),
+        }
+    }
+
+    pub fn input(&self, input: InputEvent) {
+        self.post(&ToWorker::Input(input));
+    }
+
+    pub fn apply(&self, delta: Delta) {
+        self.post(&ToWorker::Delta(delta));
+    }
+
+    /// Size the surface to the canvas under it, as laid out.
+    pub fn fit(&self) {
+        let client = client_size(&self.html_canvas);
+        let size = surface_size(client, device_pixel_ratio());
+        self.post(&ToWorker::Resize {
+            width: size.width,
+            height: size.height,
+            client: Vector2::new(client.width as f32, client.height as f32),
+        });
+    }
+}
+
+impl Drop for WorkerScene {
+    fn drop(&mut self) {
+        self.worker.terminate();
+        self.html_canvas.remove();
+    }
+}
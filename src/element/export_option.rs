@@ -0,0 +1,68 @@
+/// Which part of the board to export.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExportArea {
+    #[default]
+    Board,
+    View,
+    Selection,
+}
+
+/// Paper for PDF pages.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PageSize {
+    #[default]
+    A4,
+    A3,
+    Letter,
+}
+
+/// What each page of a PDF holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PdfPages {
+    /// The export area.
+    #[default]
+    One,
+    /// One layer of the export area.
+    PerLayer,
+    /// One page frame of the board.
+    PerFrame,
+    /// All of one board, this one first and then those of `board_v`.
+    PerBoard,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Png,
+    Svg,
+    Json,
+    InkMl,
+    Pdf,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportOption {
+    pub area: ExportArea,
+    /// Image pixels per screen pixel at the current zoom.
+    pub scale: f32,
+    /// Leave the background out, where the format allows it.
+    pub transparent: bool,
+    pub page_size: PageSize,
+    pub landscape: bool,
+    pub pages: PdfPages,
+    /// Ids of other boards to put on pages of their own.
+    pub board_v: Vec<String>,
+}
+
+impl Default for ExportOption {
+    fn default() -> Self {
+        Self {
+            area: ExportArea::Board,
+            scale: 1.0,
+            transparent: false,
+            page_size: PageSize::A4,
+            landscape: false,
+            pages: PdfPages::One,
+            board_v: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::component::{Column, Row};
+
+use super::{ExportArea, ExportFormat, ExportOption, PageFrame, PageSize, PdfPages};
+
+/// Image scales offered, in pixels per screen pixel.
+const SCALE_V: [f32; 3] = [1.0, 2.0, 4.0];
+const PAGE_SIZE_V: [(PageSize, &str); 3] = [
+    (PageSize::A4, "A4"),
+    (PageSize::A3, "A3"),
+    (PageSize::Letter, "Letter"),
+];
+const PDF_PAGES_V: [(PdfPages, &str); 4] = [
+    (PdfPages::One, "One page"),
+    (PdfPages::PerLayer, "Page per layer"),
+    (PdfPages::PerFrame, "Page per frame"),
+    (PdfPages::PerBoard, "Page per board"),
+];
+
+// Public
+#[derive(Clone, Debug, Properties, PartialEq)]
+pub struct ExportPanelProps {
+    #[prop_or_default]
+    pub on_export: Callback<(ExportFormat, ExportOption)>,
+    /// Called with a file picked for import.
+    #[prop_or_default]
+    pub on_import: Callback<web_sys::File>,
+    /// Offer exporting the selection.
+    #[prop_or_default]
+    pub has_selection: bool,
+    #[prop_or_default]
+    pub frame_v: Vec<PageFrame>,
+    /// Called to make a page frame of what is in view.
+    #[prop_or_default]
+    pub on_add_frame: Callback<()>,
+    /// Called with the id of a page frame to remove.
+    #[prop_or_default]
+    pub on_remove_frame: Callback<String>,
+}
+
+pub enum ExportPanelMessage {
+    SetArea(ExportArea),
+    SetScale(f32),
+    SetTransparent(bool),
+    SetPageSize(PageSize),
+    SetLandscape(bool),
+    SetPdfPages(PdfPages),
+    SetBoards(String),
+}
+
+pub struct ExportPanel {
+    option: ExportOption,
+}
+
+impl yew::Component for ExportPanel {
+    type Message = ExportPanelMessage;
+
+    type Properties = ExportPanelProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            option: ExportOption::default(),
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let option = &self.option;
+
+        let on_area = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_dyn_into().unwrap();
+            ExportPanelMessage::SetArea(match select.value().as_str() {
+                "view" => ExportArea::View,
+                "selection" => ExportArea::Selection,
+                _ => ExportArea::Board,
+            })
+        });
+
+        let on_scale = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_dyn_into().unwrap();
+            ExportPanelMessage::SetScale(select.value().parse().unwrap_or(1.0))
+        });
+
+        let on_transparent = ctx.link().callback(|e: Event| {
+            let input: HtmlInputElement = e.target_dyn_into().unwrap();
+            ExportPanelMessage::SetTransparent(input.checked())
+        });
+
+        let on_page_size = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_dyn_into().unwrap();
+            let name = select.value();
+            let size = PAGE_SIZE_V
+                .iter()
+                .find(|(_, n)| *n == name)
+                .map_or(PageSize::A4, |(size, _)| *size);
+            ExportPanelMessage::SetPageSize(size)
+        });
+
+        let on_landscape = ctx.link().callback(|e: Event| {
+            let input: HtmlInputElement = e.target_dyn_into().unwrap();
+            ExportPanelMessage::SetLandscape(input.checked())
+        });
+
+        let on_pdf_pages = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target_dyn_into().unwrap();
+            let name = select.value();
+            let pages = PDF_PAGES_V
+                .iter()
+                .find(|(_, n)| *n == name)
+                .map_or(PdfPages::One, |(pages, _)| *pages);
+            ExportPanelMessage::SetPdfPages(pages)
+        });
+
+        let on_boards = ctx.link().callback(|e: Event| {
+            let input: HtmlInputElement = e.target_dyn_into().unwrap();
+            ExportPanelMessage::SetBoards(input.value())
+        });
+
+        let export = |format: ExportFormat| {
+            let option = option.clone();
+            props
+                .on_export
+                .reform(move |_: MouseEvent| (format, option.clone()))
+        };
+        let on_png = export(ExportFormat::Png);
+        let on_svg = export(ExportFormat::Svg);
+        let on_json = export(ExportFormat::Json);
+        let on_inkml = export(ExportFormat::InkMl);
+        let on_pdf = export(ExportFormat::Pdf);
+
+        let on_add_frame = props.on_add_frame.reform(|_: MouseEvent| ());
+        let frame = |frame: &PageFrame| {
+            let id = frame.id.clone();
+            let on_remove = props
+                .on_remove_frame
+                .reform(move |_: MouseEvent| id.clone());
+            html! {
+                <Row key={frame.id.clone()}>
+                    {&frame.name}
+                    <button onclick={on_remove}>{"×"}</button>
+                </Row>
+            }
+        };
+
+        let on_import = props.on_import.clone();
+        let on_file = Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_dyn_into().unwrap();
+            if let Some(file) = input.files().and_then(|file_v| file_v.get(0)) {
+                on_import.emit(file);
+            }
+            // Picking the same file again should import it again.
+            input.set_value("");
+        });
+
+        html! {
+            <Column width={format!("12em")} padding={format!("0.25em")}>
+                <Row>
+                    <select onchange={on_area}>
+                        <option value="board" selected={option.area == ExportArea::Board}>{"Board"}</option>
+                        <option value="view" selected={option.area == ExportArea::View}>{"View"}</option>
+                        <option
+                            value="selection"
+                            selected={option.area == ExportArea::Selection}
+                            disabled={!props.has_selection}>{"Selection"}</option>
+                    </select>
+                    <select onchange={on_scale}>
+                        {for SCALE_V.iter().map(|scale| html! {
+                            <option value={scale.to_string()} selected={*scale == option.scale}>{format!("{scale}x")}</option>
+                        })}
+                    </select>
+                </Row>
+                <label>
+                    <input type="checkbox" checked={option.transparent} onchange={on_transparent} />
+                    {"Transparent"}
+                </label>
+                <Row>
+                    <button onclick={on_png}>{"PNG"}</button>
+                    <button onclick={on_svg}>{"SVG"}</button>
+                    <button onclick={on_json}>{"JSON"}</button>
+                    <button onclick={on_inkml}>{"InkML"}</button>
+                </Row>
+                <Row>
+                    <select onchange={on_page_size}>
+                        {for PAGE_SIZE_V.iter().map(|(size, name)| html! {
+                            <option value={*name} selected={*size == option.page_size}>{*name}</option>
+                        })}
+                    </select>
+                    <button onclick={on_pdf}>{"PDF"}</button>
+                </Row>
+                <label>
+                    <input type="checkbox" checked={option.landscape} onchange={on_landscape} />
+                    {"Landscape"}
+                </label>
+                <select onchange={on_pdf_pages}>
+                    {for PDF_PAGES_V.iter().map(|(pages, name)| html! {
+                        <option value={*name} selected={*pages == option.pages}>{*name}</option>
+                    })}
+                </select>
+                if option.pages == PdfPages::PerFrame {
+                    {for props.frame_v.iter().map(frame)}
+                    <button onclick={on_add_frame}>{"Frame the view"}</button>
+                }
+                if option.pages == PdfPages::PerBoard {
+                    <label>
+                        {"Boards "}
+                        <input
+                            placeholder={"ids, by commas"}
+                            value={option.board_v.join(",")}
+                            onchange={on_boards} />
+                    </label>
+                }
+                <label>
+                    {"Import "}
+                    <input type="file" accept=".json,.inkml,.xml" onchange={on_file} />
+                </label>
+            </Column>
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            ExportPanelMessage::SetArea(area) => self.option.area = area,
+            ExportPanelMessage::SetScale(scale) => self.option.scale = scale,
+            ExportPanelMessage::SetTransparent(transparent) => {
+                self.option.transparent = transparent
+            }
+            ExportPanelMessage::SetPageSize(size) => self.option.page_size = size,
+            ExportPanelMessage::SetLandscape(landscape) => self.option.landscape = landscape,
+            ExportPanelMessage::SetPdfPages(pages) => self.option.pages = pages,
+            ExportPanelMessage::SetBoards(s) => {
+                self.option.board_v = s
+                    .split(',')
+                    .map(|board| board.trim().to_string())
+                    .filter(|board| !board.is_empty())
+                    .collect()
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,117 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::component::{Column, Row};
+
+use super::Layer;
+
+#[derive(Clone, Debug, Properties, PartialEq)]
+pub struct LayersProps {
+    /// Bottom layer first; shown top layer first.
+    pub layer_v: Vec<Layer>,
+    /// The layer new edges go on.
+    pub layer_id: String,
+    #[prop_or_default]
+    pub on_select: Callback<String>,
+    /// Called with a layer after it is renamed, hidden, locked or faded.
+    #[prop_or_default]
+    pub on_change: Callback<Layer>,
+    /// Called with a layer id and how many places to move it up.
+    #[prop_or_default]
+    pub on_move: Callback<(String, isize)>,
+    #[prop_or_default]
+    pub on_add: Callback<()>,
+}
+
+pub struct Layers {}
+
+impl yew::Component for Layers {
+    type Message = ();
+
+    type Properties = LayersProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {}
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let top = props.layer_v.len().saturating_sub(1);
+
+        let on_add = props.on_add.reform(|_: MouseEvent| ());
+
+        html! {
+            <Column width={format!("12em")} overflow_y={format!("auto")}>
+                <Row height={format!("1.5em")}>
+                    <button onclick={on_add}>{"New layer"}</button>
+                </Row>
+                {for props.layer_v.iter().enumerate().rev().map(|(rank, layer)| {
+                    let active = layer.id == props.layer_id;
+
+                    let id = layer.id.clone();
+                    let on_select = props.on_select.reform(move |_: MouseEvent| id.clone());
+
+                    let on_change = props.on_change.clone();
+                    let base = layer.clone();
+                    let on_rename = Callback::from(move |e: Event| {
+                        let input: HtmlInputElement = e.target_dyn_into().unwrap();
+                        let name = input.value().trim().to_string();
+                        if !name.is_empty() && name != base.name {
+                            on_change.emit(Layer { name, ..base.clone() });
+                        }
+                    });
+
+                    let base = layer.clone();
+                    let on_visible = props.on_change.reform(move |_: MouseEvent| Layer {
+                        visible: !base.visible,
+                        ..base.clone()
+                    });
+
+                    let base = layer.clone();
+                    let on_lock = props.on_change.reform(move |_: MouseEvent| Layer {
+                        locked: !base.locked,
+                        ..base.clone()
+                    });
+
+                    let base = layer.clone();
+                    let on_opacity = props.on_change.reform(move |e: InputEvent| {
+                        let input: HtmlInputElement = e.target_dyn_into().unwrap();
+                        let percent: f32 = input.value().parse().unwrap_or(100.0);
+                        Layer {
+                            opacity: (percent / 100.0).clamp(0.0, 1.0),
+                            ..base.clone()
+                        }
+                    });
+
+                    let id = layer.id.clone();
+                    let on_up = props.on_move.reform(move |_: MouseEvent| (id.clone(), 1));
+                    let id = layer.id.clone();
+                    let on_down = props.on_move.reform(move |_: MouseEvent| (id.clone(), -1));
+
+                    html! {
+                        <Column
+                            padding={format!("0.25em")}
+                            bk_color={if active { format!("#dde8ff") } else { String::new() }}>
+                            <Row>
+                                <button onclick={on_select} disabled={active}>{if active { "●" } else { "○" }}</button>
+                                <input value={layer.name.clone()} onchange={on_rename} />
+                            </Row>
+                            <Row>
+                                <button onclick={on_visible}>{if layer.visible { "Hide" } else { "Show" }}</button>
+                                <button onclick={on_lock}>{if layer.locked { "Unlock" } else { "Lock" }}</button>
+                                <button onclick={on_up} disabled={rank == top}>{"↑"}</button>
+                                <button onclick={on_down} disabled={rank == 0}>{"↓"}</button>
+                            </Row>
+                            <input
+                                type="range"
+                                min="0"
+                                max="100"
+                                value={format!("{}", (layer.opacity * 100.0).round())}
+                                oninput={on_opacity} />
+                        </Column>
+                    }
+                })}
+            </Column>
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use cgmath::{Point2, Vector2};
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, PointerEvent};
+use yew::prelude::*;
+
+use super::{Edge, EdgeList, FeedListener, Layer, Rect, Viewport, ViewportFeed};
+
+/// Size of the minimap, in pixels.
+const WIDTH: f32 = 200.0;
+const HEIGHT: f32 = 150.0;
+/// Share of the shown area left around the board.
+const MARGIN: f32 = 0.05;
+/// Most points drawn per edge.
+const MAX_POINT_N: usize = 16;
+const VIEW_COLOR: &str = "rgba(51, 128, 255, 1)";
+
+fn context_2d(canvas: &NodeRef) -> Option<CanvasRenderingContext2d> {
+    canvas
+        .cast::<HtmlCanvasElement>()?
+        .get_context("2d")
+        .ok()??
+        .dyn_into::<CanvasRenderingContext2d>()
+        .ok()
+}
+
+fn css_color(color: [f32; 4]) -> String {
+    let [r, g, b] = [color[0], color[1], color[2]].map(|c| (c.clamp(0.0, 1.0) * 255.0).round());
+    format!("rgba({r}, {g}, {b}, {})", color[3].clamp(0.0, 1.0))
+}
+
+/// Bounds of every edge on a visible layer.
+fn content(edge_v: &[Edge], layer_v: &[Layer]) -> Option<Rect> {
+    edge_v
+        .iter()
+        .filter(|edge| {
+            layer_v
+                .iter()
+                .find(|layer| layer.id == edge.layer_id)
+                .is_none_or(|layer| layer.visible)
+        })
+        .filter_map(|edge| edge.bounds())
+        .reduce(|a, b| a.union(&b))
+}
+
+/// Maps world coordinates onto the minimap, keeping their aspect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Frame {
+    rect: Rect,
+    scale: f32,
+    offset: Vector2<f32>,
+}
+
+impl Frame {
+    fn new(rect: Rect) -> Self {
+        let rect = rect.inflate(rect.width().max(rect.height()) * MARGIN);
+        let scale = (WIDTH / rect.width().max(1.0)).min(HEIGHT / rect.height().max(1.0));
+        let offset = Vector2::new(
+            (WIDTH - rect.width() * scale) / 2.0,
+            (HEIGHT - rect.height() * scale) / 2.0,
+        );
+        Self {
+            rect,
+            scale,
+            offset,
+        }
+    }
+
+    fn to_map(self, world: Point2<f32>) -> Point2<f32> {
+        Point2::new(
+            (world.x - self.rect.min.x) * self.scale + self.offset.x,
+            (world.y - self.rect.min.y) * self.scale + self.offset.y,
+        )
+    }
+
+    fn to_world(self, map: Point2<f32>) -> Point2<f32> {
+        Point2::new(
+            (map.x - self.offset.x) / self.scale + self.rect.min.x,
+            (map.y - self.offset.y) / self.scale + self.rect.min.y,
+        )
+    }
+}
+
+// Public
+#[derive(Clone, Debug, Properties, PartialEq)]
+pub struct MinimapProps {
+    pub edge_v: EdgeList,
+    #[prop_or_default]
+    pub layer_v: Vec<Layer>,
+    pub viewport: ViewportFeed,
+    /// Called with the viewport moved where the minimap was clicked or dragged.
+    #[prop_or_default]
+    pub on_viewport: Callback<Viewport>,
+}
+
+pub enum MinimapMessage {
+    Down(PointerEvent),
+    Move(PointerEvent),
+    Up(PointerEvent),
+    View(Viewport),
+}
+
+/// Overview of the whole board, with the part in view outlined.
+///
+/// Edges go on one canvas and are only drawn again when they or the area
+/// shown change; the outline of the view goes on another above it.
+pub struct Minimap {
+    edge_canvas: NodeRef,
+    view_canvas: NodeRef,
+    /// Bounds of the visible edges.
+    content: Option<Rect>,
+    /// The frame edges were last drawn in.
+    drawn: Option<Frame>,
+    /// Where the view was grabbed, from its center in world units, while dragging.
+    grab: Option<Vector2<f32>>,
+    viewport: Viewport,
+    view_listener: FeedListener,
+}
+
+impl Minimap {
+    fn frame(&self, viewport: &Viewport) -> Frame {
+        // Keep the map still under a dragging pointer.
+        if let (Some(drawn), Some(_)) = (self.drawn, self.grab) {
+            return drawn;
+        }
+        let view = viewport.world_rect();
+        Frame::new(match self.content {
+            Some(content) => content.union(&view),
+            None => view,
+        })
+    }
+
+    fn draw_edges(&self, props: &MinimapProps, frame: &Frame) {
+        let context = match context_2d(&self.edge_canvas) {
+            Some(context) => context,
+            None => return,
+        };
+        context.clear_rect(0.0, 0.0, WIDTH as f64, HEIGHT as f64);
+        context.set_line_width(1.0);
+        for edge in props.edge_v.iter() {
+            let layer = props.layer_v.iter().find(|layer| layer.id == edge.layer_id);
+            if layer.is_some_and(|layer| !layer.visible) || edge.point_v.is_empty() {
+                continue;
+            }
+            let mut color = edge.point_v[0].color;
+            color[3] *= layer.map_or(1.0, |layer| layer.opacity);
+            context.set_stroke_style(&JsValue::from_str(&css_color(color)));
+
+            // Every few points, and always the last.
+            let step = edge.point_v.len().div_ceil(MAX_POINT_N).max(1);
+            let last = edge.point_v.len() - 1;
+            context.begin_path();
+            for (i, pt) in edge.point_v.iter().enumerate() {
+                if i % step != 0 && i != last {
+                    continue;
+                }
+                let map = frame.to_map(Point2::new(pt.pos.x, pt.pos.y));
+                if i == 0 {
+                    context.move_to(map.x as f64, map.y as f64);
+                    // A dot still shows.
+                    context.line_to(map.x as f64 + 0.5, map.y as f64);
+                } else {
+                    context.line_to(map.x as f64, map.y as f64);
+                }
+            }
+            context.stroke();
+        }
+    }
+
+    fn draw_view(&self, viewport: &Viewport, frame: &Frame) {
+        let context = match context_2d(&self.view_canvas) {
+            Some(context) => context,
+            None => return,
+        };
+        context.clear_rect(0.0, 0.0, WIDTH as f64, HEIGHT as f64);
+        context.set_line_width(1.5);
+        context.set_stroke_style(&JsValue::from_str(VIEW_COLOR));
+        let size = viewport.size;
+        let corner_v = [
+            Point2::new(0.0, 0.0),
+            Point2::new(size.x, 0.0),
+            Point2::new(size.x, size.y),
+            Point2::new(0.0, size.y),
+        ];
+        context.begin_path();
+        for (i, corner) in corner_v.iter().enumerate() {
+            let map = frame.to_map(viewport.screen_to_world(*corner));
+            if i == 0 {
+                context.move_to(map.x as f64, map.y as f64);
+            } else {
+                context.line_to(map.x as f64, map.y as f64);
+            }
+        }
+        context.close_path();
+        context.stroke();
+    }
+
+    /// The world point under the pointer of `e`.
+    fn world_at(&self, e: &PointerEvent) -> Point2<f32> {
+        let map = Point2::new(e.offset_x() as f32, e.offset_y() as f32);
+        self.frame(&self.viewport).to_world(map)
+    }
+}
+
+impl yew::Component for Minimap {
+    type Message = MinimapMessage;
+
+    type Properties = MinimapProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            edge_canvas: NodeRef::default(),
+            view_canvas: NodeRef::default(),
+            content: content(&ctx.props().edge_v, &ctx.props().layer_v),
+            drawn: None,
+            grab: None,
+            viewport: ctx.props().viewport.get(),
+            view_listener: ctx
+                .props()
+                .viewport
+                .listen(ctx.link().callback(MinimapMessage::View)),
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let onpointerdown = ctx.link().callback(MinimapMessage::Down);
+        let onpointermove = ctx.link().callback(MinimapMessage::Move);
+        let onpointerup = ctx.link().callback(MinimapMessage::Up);
+        let layer_style = "position: absolute;left: 0;top: 0;";
+
+        html! {
+            <div style={format!(
+                "position: relative;width: {WIDTH}px;height: {HEIGHT}px;\
+                 background-color: rgba(255, 255, 255, 0.85);border: 1px solid #888;"
+            )}>
+                <canvas
+                    ref={self.edge_canvas.clone()}
+                    width={WIDTH.to_string()}
+                    height={HEIGHT.to_string()}
+                    style={layer_style} />
+                <canvas
+                    ref={self.view_canvas.clone()}
+                    width={WIDTH.to_string()}
+                    height={HEIGHT.to_string()}
+                    style={format!("{layer_style}cursor: move;touch-action: none;")}
+                    {onpointerdown}
+                    {onpointermove}
+                    {onpointerup} />
+            </div>
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let viewport = self.viewport;
+        match msg {
+            MinimapMessage::Down(e) => {
+                let world = self.world_at(&e);
+                let screen = viewport.world_to_screen(world);
+                let in_view = (0.0..=viewport.size.x).contains(&screen.x)
+                    && (0.0..=viewport.size.y).contains(&screen.y);
+                // Grabbing the view drags it; anywhere else brings it there first.
+                let grab = if in_view {
+                    world - viewport.center
+                } else {
+                    Vector2::new(0.0, 0.0)
+                };
+                if let Some(canvas) = self.view_canvas.cast::<HtmlCanvasElement>() {
+                    let _ = canvas.set_pointer_capture(e.pointer_id());
+                }
+                self.grab = Some(grab);
+                if !in_view {
+                    ctx.props().on_viewport.emit(Viewport {
+                        center: world,
+                        ..viewport
+                    });
+                }
+            }
+            MinimapMessage::Move(e) => {
+                if let Some(grab) = self.grab {
+                    let world = self.world_at(&e);
+                    ctx.props().on_viewport.emit(Viewport {
+                        center: world - grab,
+                        ..viewport
+                    });
+                }
+            }
+            MinimapMessage::Up(e) => {
+                if let Some(canvas) = self.view_canvas.cast::<HtmlCanvasElement>() {
+                    let _ = canvas.release_pointer_capture(e.pointer_id());
+                }
+                self.grab = None;
+                // The frame may have been held still while dragging.
+                return true;
+            }
+            MinimapMessage::View(viewport) => {
+                self.viewport = viewport;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        if props.edge_v != old_props.edge_v || props.layer_v != old_props.layer_v {
+            self.content = content(&props.edge_v, &props.layer_v);
+            self.drawn = None;
+        }
+        if props.viewport != old_props.viewport {
+            self.viewport = props.viewport.get();
+            self.view_listener = props
+                .viewport
+                .listen(ctx.link().callback(MinimapMessage::View));
+        }
+        true
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        let props = ctx.props();
+        let frame = self.frame(&self.viewport);
+        if self.drawn != Some(frame) {
+            self.draw_edges(props, &frame);
+            self.drawn = Some(frame);
+        }
+        self.draw_view(&self.viewport, &frame);
+    }
+}
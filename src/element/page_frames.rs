@@ -0,0 +1,75 @@
+use cgmath::Point2;
+use yew::prelude::*;
+
+use super::{FeedListener, PageFrame, Viewport, ViewportFeed};
+
+// Public
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct PageFramesProps {
+    pub frame_v: Vec<PageFrame>,
+    pub viewport: ViewportFeed,
+}
+
+/// The page frames of the board, outlined over the canvas with their names.
+pub struct PageFrames {
+    viewport: Viewport,
+    view_listener: FeedListener,
+}
+
+impl Component for PageFrames {
+    type Message = Viewport;
+
+    type Properties = PageFramesProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            viewport: ctx.props().viewport.get(),
+            view_listener: ctx.props().viewport.listen(ctx.link().callback(|v| v)),
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, viewport: Self::Message) -> bool {
+        self.viewport = viewport;
+        true
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        if props.viewport != old_props.viewport {
+            self.viewport = props.viewport.get();
+            self.view_listener = props.viewport.listen(ctx.link().callback(|v| v));
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let viewport = self.viewport;
+        let outline = |frame: &PageFrame| {
+            let rect = &frame.rect;
+            let corner = viewport.world_to_screen(rect.min);
+            // The board turns under the screen, and the frames with it.
+            let right = viewport.world_to_screen(Point2::new(rect.max.x, rect.min.y)) - corner;
+            let angle = right.y.atan2(right.x);
+            html! {
+                <div key={frame.id.clone()}
+                    style={format!("position: absolute;left: {}px;top: {}px;width: {}px;height: {}px;\
+                        transform: rotate({angle}rad);transform-origin: 0 0;\
+                        border: 1px dashed #888;box-sizing: border-box;",
+                        corner.x,
+                        corner.y,
+                        rect.width() * viewport.zoom,
+                        rect.height() * viewport.zoom)}>
+                    <div style={"position: absolute;bottom: 100%;white-space: nowrap;font-size: 12px;color: #888;"}>
+                        {&frame.name}
+                    </div>
+                </div>
+            }
+        };
+        html! {
+            <div style={"position: absolute;left: 0;top: 0;width: 100%;height: 100%;\
+                overflow: hidden;pointer-events: none;"}>
+                {for ctx.props().frame_v.iter().map(outline)}
+            </div>
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::component::{Column, Row};
+
+use super::{Paper, PaperKind};
+
+/// Spacings offered, in world units.
+const SPACING_V: [f32; 5] = [16.0, 24.0, 32.0, 48.0, 64.0];
+
+fn label(kind: PaperKind) -> &'static str {
+    match kind {
+        PaperKind::Blank => "Blank",
+        PaperKind::Grid => "Grid",
+        PaperKind::Dots => "Dots",
+        PaperKind::Lined => "Lined",
+        PaperKind::Isometric => "Isometric",
+    }
+}
+
+/// `#rrggbb`, as a color input takes it.
+fn to_hex(color: [f32; 4]) -> String {
+    let [r, g, b] =
+        [color[0], color[1], color[2]].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn from_hex(s: &str, alpha: f32) -> Option<[f32; 4]> {
+    let hex = s.strip_prefix('#')?;
+    let c = |i: usize| Some(u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()? as f32 / 255.0);
+    Some([c(0)?, c(2)?, c(4)?, alpha])
+}
+
+// Public
+#[derive(Clone, Debug, Properties, PartialEq)]
+pub struct PaperPanelProps {
+    pub paper: Paper,
+    #[prop_or_default]
+    pub on_change: Callback<Paper>,
+}
+
+pub struct PaperPanel {}
+
+impl yew::Component for PaperPanel {
+    type Message = ();
+
+    type Properties = PaperPanelProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {}
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let paper = props.paper;
+
+        let on_kind = props.on_change.reform(move |e: Event| {
+            let select: HtmlSelectElement = e.target_dyn_into().unwrap();
+            Paper {
+                kind: PaperKind::from_name(&select.value()).unwrap_or_default(),
+                ..paper
+            }
+        });
+
+        let on_spacing = props.on_change.reform(move |e: Event| {
+            let select: HtmlSelectElement = e.target_dyn_into().unwrap();
+            Paper {
+                spacing: select.value().parse().unwrap_or(paper.spacing),
+                ..paper
+            }
+        });
+
+        let on_color = props.on_change.reform(move |e: Event| {
+            let input: HtmlInputElement = e.target_dyn_into().unwrap();
+            Paper {
+                color: from_hex(&input.value(), paper.color[3]).unwrap_or(paper.color),
+                ..paper
+            }
+        });
+
+        let on_line_color = props.on_change.reform(move |e: Event| {
+            let input: HtmlInputElement = e.target_dyn_into().unwrap();
+            Paper {
+                line_color: from_hex(&input.value(), paper.line_color[3])
+                    .unwrap_or(paper.line_color),
+                ..paper
+            }
+        });
+
+        html! {
+            <Column width={format!("12em")} padding={format!("0.25em")}>
+                <Row>
+                    <select onchange={on_kind}>
+                        {for PaperKind::ALL.iter().map(|kind| html! {
+                            <option value={kind.name()} selected={*kind == paper.kind}>{label(*kind)}</option>
+                        })}
+                    </select>
+                    <select onchange={on_spacing} disabled={paper.kind == PaperKind::Blank}>
+                        {for SPACING_V.iter().map(|spacing| html! {
+                            <option value={spacing.to_string()} selected={*spacing == paper.spacing}>{format!("{spacing}")}</option>
+                        })}
+                    </select>
+                </Row>
+                <Row>
+                    <label>
+                        {"Paper "}
+                        <input type="color" value={to_hex(paper.color)} onchange={on_color} />
+                    </label>
+                    <label>
+                        {"Lines "}
+                        <input
+                            type="color"
+                            value={to_hex(paper.line_color)}
+                            onchange={on_line_color}
+                            disabled={paper.kind == PaperKind::Blank} />
+                    </label>
+                </Row>
+            </Column>
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use yew::prelude::*;
+
+use crate::metrics::{self, Metrics};
+
+/// How often the numbers are read again.
+const TICK: Duration = Duration::from_millis(500);
+
+fn kib(bytes: usize) -> String {
+    format!("{:.1} KiB", bytes as f32 / 1024.0)
+}
+
+// Public
+pub enum PerfHudMessage {
+    Tick,
+}
+
+/// Overlay with the numbers of `metrics`, read twice a second.
+pub struct PerfHud {
+    metrics: Metrics,
+}
+
+impl yew::Component for PerfHud {
+    type Message = PerfHudMessage;
+
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(PerfHudMessage::Tick);
+        Self {
+            metrics: metrics::snapshot(),
+        }
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        let m = &self.metrics;
+        let row_v = [
+            ("FPS", format!("{}", m.fps)),
+            ("Frame", format!("{:.1} ms", m.frame_ms)),
+            ("Edges", format!("{}", m.edge_n)),
+            ("Points", format!("{}", m.point_n)),
+            ("Upload", kib(m.upload_bytes)),
+            ("Pull", format!("{:.0} ms", m.pull_ms)),
+            ("Decode", format!("{:.0} ms", m.decode_ms)),
+            ("Sync queue", format!("{}", m.sync_n)),
+        ];
+        html! {
+            <table style={"font: 12px monospace;color: #fff;background-color: rgba(0, 0, 0, 0.6);\
+                padding: 0.25em 0.5em;pointer-events: none;"}>
+                {for row_v.into_iter().map(|(name, value)| html! {
+                    <tr>
+                        <td>{name}</td>
+                        <td style={"text-align: right;padding-left: 1em;"}>{value}</td>
+                    </tr>
+                })}
+            </table>
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            PerfHudMessage::Tick => {
+                // Stops with the component, whose messages then go nowhere.
+                ctx.link().send_future(async {
+                    yew::platform::time::sleep(TICK).await;
+                    PerfHudMessage::Tick
+                });
+                let metrics = metrics::snapshot();
+                if metrics == self.metrics {
+                    return false;
+                }
+                self.metrics = metrics;
+                true
+            }
+        }
+    }
+}
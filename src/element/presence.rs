@@ -0,0 +1,128 @@
+use cgmath::Point2;
+use yew::prelude::*;
+
+use crate::service::Peer;
+
+use super::{FeedListener, Viewport, ViewportFeed};
+
+/// Size of an avatar, in pixels.
+const AVATAR_SIZE: u32 = 24;
+
+/// Up to two letters standing for `name`.
+fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .take(2)
+        .collect::<String>()
+        .to_uppercase()
+}
+
+// Public
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct CursorsProps {
+    pub peer_v: Vec<Peer>,
+    pub viewport: ViewportFeed,
+}
+
+/// The pointers of other users, laid over the canvas, each with a name tag.
+pub struct Cursors {
+    viewport: Viewport,
+    view_listener: FeedListener,
+}
+
+impl Component for Cursors {
+    type Message = Viewport;
+
+    type Properties = CursorsProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self {
+            viewport: ctx.props().viewport.get(),
+            view_listener: ctx.props().viewport.listen(ctx.link().callback(|v| v)),
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, viewport: Self::Message) -> bool {
+        self.viewport = viewport;
+        true
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        let props = ctx.props();
+        if props.viewport != old_props.viewport {
+            self.viewport = props.viewport.get();
+            self.view_listener = props.viewport.listen(ctx.link().callback(|v| v));
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let viewport = self.viewport;
+        let cursor = |peer: &Peer| {
+            let (x, y) = peer.pos?;
+            let screen = viewport.world_to_screen(Point2::new(x, y));
+            if screen.x < 0.0
+                || screen.y < 0.0
+                || screen.x > viewport.size.x
+                || screen.y > viewport.size.y
+            {
+                return None;
+            }
+            let color = &peer.color;
+            Some(html! {
+                <div key={peer.id.clone()}
+                    style={format!("position: absolute;left: {}px;top: {}px;", screen.x, screen.y)}>
+                    <div style={format!("width: 0.6em;height: 0.6em;margin: -0.3em;\
+                        border-radius: 50%;background-color: {color};")} />
+                    <div style={format!("margin: 0.3em 0 0 0.3em;padding: 0 0.3em;white-space: nowrap;\
+                        font-size: 12px;color: #fff;background-color: {color};")}>
+                        {format!("{} · {}", peer.name, peer.tool)}
+                    </div>
+                </div>
+            })
+        };
+        html! {
+            <div style={"position: absolute;left: 0;top: 0;width: 100%;height: 100%;\
+                overflow: hidden;pointer-events: none;"}>
+                {for ctx.props().peer_v.iter().filter_map(cursor)}
+            </div>
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct AvatarsProps {
+    pub peer_v: Vec<Peer>,
+}
+
+/// A row of circles, one for every user on the board.
+pub struct Avatars;
+
+impl Component for Avatars {
+    type Message = ();
+
+    type Properties = AvatarsProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let avatar = |peer: &Peer| {
+            html! {
+                <div key={peer.id.clone()}
+                    title={format!("{} · {}", peer.name, peer.tool)}
+                    style={format!("display: inline-flex;align-items: center;justify-content: center;\
+                        width: {AVATAR_SIZE}px;height: {AVATAR_SIZE}px;margin-left: 0.25em;\
+                        border-radius: 50%;font-size: 11px;color: #fff;background-color: {};", peer.color)}>
+                    {initials(&peer.name)}
+                </div>
+            }
+        };
+        html! {
+            <div style={"display: flex;align-items: center;"}>
+                {for ctx.props().peer_v.iter().map(avatar)}
+            </div>
+        }
+    }
+}
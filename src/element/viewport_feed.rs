@@ -0,0 +1,93 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use yew::Callback;
+
+use super::Viewport;
+
+#[derive(Default)]
+struct Feed {
+    viewport: Viewport,
+    /// Listeners by the id their `FeedListener` holds.
+    listener_m: HashMap<usize, Callback<Viewport>>,
+    next_id: usize,
+}
+
+// Public
+/// The viewport of the board, shared by the page with what is drawn over
+/// the board, so that moving the view only renders those.
+///
+/// Clones share one viewport, and compare equal only to each other.
+#[derive(Clone, Default)]
+pub struct ViewportFeed(Rc<RefCell<Feed>>);
+
+impl ViewportFeed {
+    pub fn new(viewport: Viewport) -> Self {
+        Self(Rc::new(RefCell::new(Feed {
+            viewport,
+            ..Feed::default()
+        })))
+    }
+
+    pub fn get(&self) -> Viewport {
+        self.0.borrow().viewport
+    }
+
+    /// Take `viewport`, telling every listener if it moved.
+    pub fn set(&self, viewport: Viewport) {
+        let listener_v: Vec<Callback<Viewport>> = {
+            let mut feed = self.0.borrow_mut();
+            if feed.viewport == viewport {
+                return;
+            }
+            feed.viewport = viewport;
+            feed.listener_m.values().cloned().collect()
+        };
+        for listener in listener_v {
+            listener.emit(viewport);
+        }
+    }
+
+    /// Call `on_viewport` with every viewport set until the returned
+    /// listener is dropped.
+    pub fn listen(&self, on_viewport: Callback<Viewport>) -> FeedListener {
+        let mut feed = self.0.borrow_mut();
+        let id = feed.next_id;
+        feed.next_id += 1;
+        feed.listener_m.insert(id, on_viewport);
+        FeedListener {
+            feed: Rc::downgrade(&self.0),
+            id,
+        }
+    }
+}
+
+impl PartialEq for ViewportFeed {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for ViewportFeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ViewportFeed").field(&self.get()).finish()
+    }
+}
+
+/// Keeps a listener of a `ViewportFeed` called while it lives.
+pub struct FeedListener {
+    feed: Weak<RefCell<Feed>>,
+    id: usize,
+}
+
+impl Drop for FeedListener {
+    fn drop(&mut self) {
+        if let Some(feed) = self.feed.upgrade() {
+            feed.borrow_mut().listener_m.remove(&self.id);
+        }
+    }
+}
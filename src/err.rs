@@ -1,9 +1,11 @@
 use std::fmt::Display;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Error {
     Other(String),
     NotLogin(String),
+    /// The GPU could not be set up for drawing.
+    Gpu(String),
 }
 
 impl Error {
@@ -11,6 +13,7 @@ impl Error {
         match self {
             Error::Other(msg) => msg,
             Error::NotLogin(msg) => msg,
+            Error::Gpu(msg) => msg,
         }
     }
 }
@@ -22,6 +25,7 @@ impl Display for Error {
         match self {
             Error::Other(msg) => write!(f, "Other: {msg}"),
             Error::NotLogin(msg) => write!(f, "NotLogin: {msg}"),
+            Error::Gpu(msg) => write!(f, "Gpu: {msg}"),
         }
     }
 }
@@ -0,0 +1,410 @@
+//! The Huiwen board file, a JSON document.
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "meta": { "generator": "huiwen 0.1.0", "created": "2024-01-01T00:00:00.000Z" },
+//!   "layers": [
+//!     { "id": "default", "name": "Layer 1", "visible": true, "locked": false, "opacity": 1.0 }
+//!   ],
+//!   "edges": [
+//!     {
+//!       "id": "00ff00ff00ff00ff",
+//!       "layer": "default",
+//!       "points": [
+//!         { "x": 10.0, "y": 20.0, "z": -1.0, "width": 2.0, "color": [0.0, 0.0, 0.0, 1.0] }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Positions and widths are world coordinates, screen pixels at 100% zoom, and
+//! colors are RGBA in [0, 1]. Layers and edges are listed
+//! bottom first. Point fields from `tilt_x` on describe the stylus and may be
+//! left out; `time` counts milliseconds from the first point of the edge.
+//!
+//! Every change that older readers would get wrong bumps `version`, and adds
+//! a step to `MIGRATION_V` that brings documents of the version before it up.
+
+use cgmath::Point3;
+use painting::point::Point;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    element::{Edge, Layer, Stylus, DEFAULT_LAYER_ID},
+    err,
+};
+
+type Migration = fn(Value) -> err::Result<Value>;
+
+/// Steps bringing a document of version `i + 1` up to version `i + 2`.
+const MIGRATION_V: [Migration; 1] = [widths_to_world];
+
+/// Version 1 kept widths in `painting` clip space, taken on a canvas 1080
+/// pixels high.
+fn widths_to_world(mut value: Value) -> err::Result<Value> {
+    for edge in value["edges"].as_array_mut().into_iter().flatten() {
+        for pt in edge["points"].as_array_mut().into_iter().flatten() {
+            if let Some(width) = pt["width"].as_f64() {
+                pt["width"] = (width * 540.0).into();
+            }
+        }
+    }
+    value["version"] = 2.into();
+    Ok(value)
+}
+
+/// Bring `value` up to the version after the last of `migration_v`.
+fn upgrade(mut value: Value, migration_v: &[Migration]) -> err::Result<Value> {
+    let latest = migration_v.len() as u64 + 1;
+    let version = value["version"].as_u64().ok_or(err::Error::Other(format!(
+        "when read document:\n\tno version"
+    )))?;
+    if version == 0 || version > latest {
+        return Err(err::Error::Other(format!(
+            "when read document:\n\tversion {version} is not supported, up to {latest} is"
+        )));
+    }
+    for migrate in &migration_v[version as usize - 1..] {
+        value = migrate(value)?;
+    }
+    Ok(value)
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+fn is_zero(f: &f32) -> bool {
+    *f == 0.0
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+fn default_layer() -> String {
+    DEFAULT_LAYER_ID.to_string()
+}
+
+#[derive(Serialize, Deserialize)]
+struct PointDoc {
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    z: f32,
+    width: f32,
+    color: [f32; 4],
+    #[serde(default, skip_serializing_if = "is_zero")]
+    tilt_x: f32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    tilt_y: f32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    twist: f32,
+    #[serde(default, skip_serializing_if = "is_false")]
+    barrel: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    eraser: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    time: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EdgeDoc {
+    id: String,
+    #[serde(default = "default_layer")]
+    layer: String,
+    points: Vec<PointDoc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LayerDoc {
+    id: String,
+    name: String,
+    #[serde(default = "default_visible")]
+    visible: bool,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default = "default_opacity")]
+    opacity: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DocumentDoc {
+    version: u64,
+    #[serde(default)]
+    meta: Meta,
+    #[serde(default)]
+    layers: Vec<LayerDoc>,
+    #[serde(default)]
+    edges: Vec<EdgeDoc>,
+}
+
+// Public
+/// Version of the documents this build writes.
+pub const DOCUMENT_VERSION: u64 = 2;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Meta {
+    /// The app and version that wrote the document.
+    pub generator: String,
+    /// When it was written, as an ISO 8601 date.
+    pub created: String,
+}
+
+/// A whole board, or part of one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    pub meta: Meta,
+    /// Bottom first.
+    pub layer_v: Vec<Layer>,
+    pub edge_v: Vec<Edge>,
+}
+
+impl Document {
+    pub fn to_json(&self) -> String {
+        let layers: Vec<LayerDoc> = self
+            .layer_v
+            .iter()
+            .map(|layer| LayerDoc {
+                id: layer.id.clone(),
+                name: layer.name.clone(),
+                visible: layer.visible,
+                locked: layer.locked,
+                opacity: layer.opacity,
+            })
+            .collect();
+        let edges: Vec<EdgeDoc> = self
+            .edge_v
+            .iter()
+            .map(|edge| EdgeDoc {
+                id: edge.id.clone(),
+                layer: edge.layer_id.clone(),
+                points: edge
+                    .point_v
+                    .iter()
+                    .zip(&edge.stylus_v)
+                    .map(|(pt, stylus)| PointDoc {
+                        x: pt.pos.x,
+                        y: pt.pos.y,
+                        z: pt.pos.z,
+                        width: pt.width,
+                        color: pt.color,
+                        tilt_x: stylus.tilt_x,
+                        tilt_y: stylus.tilt_y,
+                        twist: stylus.twist,
+                        barrel: stylus.barrel,
+                        eraser: stylus.eraser,
+                        time: stylus.time,
+                    })
+                    .collect(),
+            })
+            .collect();
+        let doc = DocumentDoc {
+            version: DOCUMENT_VERSION,
+            meta: self.meta.clone(),
+            layers,
+            edges,
+        };
+        serde_json::to_string_pretty(&doc).unwrap()
+    }
+
+    /// Read a document of any version up to `DOCUMENT_VERSION`.
+    pub fn from_json(s: &str) -> err::Result<Self> {
+        let map_e = |e: serde_json::Error| err::Error::Other(format!("when read document:\n\t{e}"));
+        let value: Value = serde_json::from_str(s).map_err(map_e)?;
+        let value = upgrade(value, &MIGRATION_V)?;
+        let doc: DocumentDoc = serde_json::from_value(value).map_err(map_e)?;
+
+        Ok(Self {
+            meta: doc.meta,
+            layer_v: doc
+                .layers
+                .into_iter()
+                .map(|layer| Layer {
+                    id: layer.id,
+                    name: layer.name,
+                    visible: layer.visible,
+                    locked: layer.locked,
+                    opacity: layer.opacity,
+                })
+                .collect(),
+            edge_v: doc
+                .edges
+                .into_iter()
+                .map(|edge| Edge {
+                    id: edge.id,
+                    layer_id: edge.layer,
+                    point_v: edge
+                        .points
+                        .iter()
+                        .map(|pt| Point {
+                            pos: Point3::new(pt.x, pt.y, pt.z),
+                            color: pt.color,
+                            width: pt.width,
+                        })
+                        .collect(),
+                    stylus_v: edge
+                        .points
+                        .iter()
+                        .map(|pt| Stylus {
+                            tilt_x: pt.tilt_x,
+                            tilt_y: pt.tilt_y,
+                            twist: pt.twist,
+                            barrel: pt.barrel,
+                            eraser: pt.eraser,
+                            time: pt.time,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn point(x: f32, y: f32, color: [f32; 4]) -> Point {
+        Point {
+            pos: Point3::new(x, y, 0.0),
+            color,
+            width: 2.0,
+        }
+    }
+
+    /// Leaves the name of each step run in `trail`.
+    fn step_a(mut value: Value) -> err::Result<Value> {
+        value["trail"].as_array_mut().unwrap().push("a".into());
+        Ok(value)
+    }
+
+    fn step_b(mut value: Value) -> err::Result<Value> {
+        value["trail"].as_array_mut().unwrap().push("b".into());
+        Ok(value)
+    }
+
+    fn step_c(mut value: Value) -> err::Result<Value> {
+        value["trail"].as_array_mut().unwrap().push("c".into());
+        Ok(value)
+    }
+
+    fn trail(version: u64) -> err::Result<Value> {
+        let value = json!({ "version": version, "trail": [] });
+        upgrade(value, &[step_a, step_b, step_c]).map(|value| value["trail"].clone())
+    }
+
+    #[test]
+    fn round_trip() {
+        let black = [0.0, 0.0, 0.0, 1.0];
+        let red = [1.0, 0.0, 0.0, 0.5];
+        let document = Document {
+            meta: Meta {
+                generator: "huiwen test".to_string(),
+                created: "2024-01-01T00:00:00.000Z".to_string(),
+            },
+            layer_v: vec![
+                Layer::default(),
+                Layer {
+                    id: "sketch".to_string(),
+                    name: "Sketch".to_string(),
+                    visible: false,
+                    locked: true,
+                    opacity: 0.25,
+                },
+            ],
+            edge_v: vec![
+                Edge {
+                    id: "0000000000000001".to_string(),
+                    layer_id: DEFAULT_LAYER_ID.to_string(),
+                    point_v: vec![point(0.0, 0.0, black), point(10.0, -5.5, red)],
+                    stylus_v: vec![Stylus::default(); 2],
+                },
+                Edge {
+                    id: "0000000000000002".to_string(),
+                    layer_id: "sketch".to_string(),
+                    point_v: vec![point(3.0, 4.0, black)],
+                    stylus_v: vec![Stylus {
+                        tilt_x: 30.0,
+                        tilt_y: -15.0,
+                        twist: 90.0,
+                        barrel: true,
+                        eraser: false,
+                        time: 16.0,
+                    }],
+                },
+            ],
+        };
+        let json = document.to_json();
+        assert_eq!(Document::from_json(&json).unwrap(), document);
+    }
+
+    #[test]
+    fn fills_in_left_out_fields() {
+        let json = r#"{
+            "version": 2,
+            "edges": [
+                { "id": "e", "points": [{ "x": 1.0, "y": 2.0, "width": 0.5, "color": [0.0, 0.0, 0.0, 1.0] }] }
+            ]
+        }"#;
+        let document = Document::from_json(json).unwrap();
+        assert!(document.layer_v.is_empty());
+        assert_eq!(document.edge_v[0].layer_id, DEFAULT_LAYER_ID);
+        assert_eq!(
+            document.edge_v[0].point_v[0].pos,
+            Point3::new(1.0, 2.0, 0.0)
+        );
+        assert_eq!(document.edge_v[0].stylus_v, vec![Stylus::default()]);
+    }
+
+    #[test]
+    fn widens_version_1() {
+        let json = r#"{
+            "version": 1,
+            "edges": [
+                { "id": "e", "points": [{ "x": 1.0, "y": 2.0, "width": 0.01, "color": [0.0, 0.0, 0.0, 1.0] }] }
+            ]
+        }"#;
+        let document = Document::from_json(json).unwrap();
+        let pt = &document.edge_v[0].point_v[0];
+        assert_eq!(pt.pos, Point3::new(1.0, 2.0, 0.0));
+        assert!((pt.width - 5.4).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let too_new = DOCUMENT_VERSION + 1;
+        for json in [
+            "{}".to_string(),
+            r#"{ "version": "1" }"#.to_string(),
+            r#"{ "version": 0 }"#.to_string(),
+            format!(r#"{{ "version": {too_new} }}"#),
+        ] {
+            assert!(Document::from_json(&json).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn migrates_from_the_version_read() {
+        assert_eq!(trail(1).unwrap(), json!(["a", "b", "c"]));
+        assert_eq!(trail(2).unwrap(), json!(["b", "c"]));
+        assert_eq!(trail(3).unwrap(), json!(["c"]));
+        assert_eq!(trail(4).unwrap(), json!([]));
+        assert!(trail(0).is_err());
+        assert!(trail(5).is_err());
+    }
+
+    #[test]
+    fn migrations_reach_the_current_version() {
+        assert_eq!(MIGRATION_V.len() as u64 + 1, DOCUMENT_VERSION);
+    }
+}
@@ -0,0 +1,953 @@
+//! W3C InkML, for trading ink with other apps.
+//!
+//! Every edge becomes a `<trace>`, and every layer a `<traceGroup>` whose
+//! annotations keep its name and settings. Points carry these channels:
+//!
+//! | Channel  | Holds                | Units                              |
+//! |----------|----------------------|------------------------------------|
+//! | X, Y     | position             | px, in world coordinates           |
+//! | W        | width                | px                                 |
+//! | T        | time                 | ms since the first point           |
+//! | OTx, OTy | tilt                 | deg                                |
+//! | OR       | twist                | deg                                |
+//! | B1       | barrel button        | boolean                            |
+//!
+//! Color and transparency go in the `<brush>` of each trace, along with the
+//! widest width so that apps ignoring W still draw a fair line.
+//!
+//! Reading takes these channels in whatever order and units the trace format
+//! declares, written out or difference coded. A trace without W gets the
+//! width of its brush, thinned by F where it has pressure. Any other channel,
+//! such as Z, S, OA or OE, is read past and dropped, as are `penUp` traces,
+//! which are hover and not ink. Huiwen's eraser flag has no channel and is
+//! not written. Trace and group ids that Huiwen couldn't have made are
+//! replaced with fresh ones.
+
+use std::{collections::HashMap, fmt::Write};
+
+use cgmath::Point3;
+use painting::point::Point;
+use web_sys::{DomParser, Element, SupportedType};
+
+use crate::{
+    element::{self, Edge, Layer, Stylus, DEFAULT_LAYER_ID},
+    err, util,
+};
+
+use super::{escape, rgb, Document, Meta};
+
+const INKML_NS: &str = "http://www.w3.org/2003/InkML";
+/// Width, in px, of a trace that has neither W nor a brush width.
+const DEFAULT_WIDTH: f32 = 2.0;
+/// Thinnest F leaves a line, as a share of its brush width.
+const MIN_FORCE: f32 = 0.1;
+
+/// One channel of a trace format.
+#[derive(Clone, Debug)]
+struct Channel {
+    name: String,
+    /// Turns a value into px, ms or degrees.
+    scale: f32,
+    /// Declared maximum, used to bring F into [0, 1].
+    max: Option<f32>,
+}
+
+impl Channel {
+    fn from_element(el: &impl Node) -> Self {
+        let name = el.attribute("name").unwrap_or_default();
+        let scale = match el.attribute("units").as_deref() {
+            Some("mm") => 96.0 / 25.4,
+            Some("cm") => 96.0 / 2.54,
+            Some("m") => 96.0 / 0.0254,
+            Some("in") => 96.0,
+            Some("pt") => 96.0 / 72.0,
+            Some("pc") => 16.0,
+            Some("himetric") => 96.0 / 2540.0,
+            Some("s") => 1000.0,
+            Some("rad") => 180.0 / std::f32::consts::PI,
+            // px, ms, deg, dev and whatever is left.
+            _ => 1.0,
+        };
+        let max = el
+            .attribute("max")
+            .and_then(|max| max.parse().ok())
+            .filter(|max: &f32| *max > 0.0);
+        Self { name, scale, max }
+    }
+
+    fn xy() -> Vec<Self> {
+        ["X", "Y"]
+            .map(|name| Self {
+                name: name.to_string(),
+                scale: 1.0,
+                max: None,
+            })
+            .to_vec()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Brush {
+    color: [f32; 4],
+    /// In px.
+    width: Option<f32>,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            color: [0.0, 0.0, 0.0, 1.0],
+            width: None,
+        }
+    }
+}
+
+impl Brush {
+    fn from_element(el: &impl Node) -> Self {
+        let mut brush = Self::default();
+        for prop in child_v(el, "brushProperty") {
+            let value = prop.attribute("value").unwrap_or_default();
+            match prop.attribute("name").as_deref() {
+                Some("color") => {
+                    if let Some([r, g, b]) = parse_rgb(&value) {
+                        brush.color = [r, g, b, brush.color[3]];
+                    }
+                }
+                Some("transparency") => {
+                    if let Ok(t) = value.parse::<f32>() {
+                        // Some writers count 0 to 255, as ISF does.
+                        let t = if t > 1.0 { t / 255.0 } else { t };
+                        brush.color[3] = 1.0 - t.clamp(0.0, 1.0);
+                    }
+                }
+                Some("width") => {
+                    let k = Channel::from_element(&prop).scale;
+                    brush.width = value.parse::<f32>().ok().map(|w| w * k);
+                }
+                _ => (),
+            }
+        }
+        brush
+    }
+}
+
+/// Format and brush in effect for a trace.
+#[derive(Clone, Debug, Default)]
+struct Scope {
+    channel_v: Vec<Channel>,
+    brush: Brush,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Context {
+    channel_v: Option<Vec<Channel>>,
+    brush: Option<Brush>,
+}
+
+/// One value as written in a trace.
+#[derive(Clone, Copy, Debug)]
+enum Value {
+    Number(f32),
+    /// `*`, the same step as before.
+    Repeat,
+    /// `?`.
+    Unknown,
+}
+
+/// Running state of one channel along a trace.
+#[derive(Clone, Copy, Debug, Default)]
+struct Decoder {
+    /// 0 for plain values, 1 for first differences, 2 for second.
+    order: u8,
+    value: f32,
+    /// Step from the value before.
+    delta: f32,
+}
+
+impl Decoder {
+    fn next(&mut self, order: Option<u8>, value: Value) -> Option<f32> {
+        if let Some(order) = order {
+            self.order = order;
+        }
+        match (value, self.order) {
+            (Value::Unknown, _) => return None,
+            (Value::Repeat, 0) => self.delta = 0.0,
+            (Value::Repeat, _) => self.value += self.delta,
+            (Value::Number(n), 0) => {
+                self.delta = n - self.value;
+                self.value = n;
+            }
+            (Value::Number(n), 1) => {
+                self.delta = n;
+                self.value += n;
+            }
+            (Value::Number(n), _) => {
+                self.delta += n;
+                self.value += self.delta;
+            }
+        }
+        Some(self.value)
+    }
+}
+
+fn parse_rgb(s: &str) -> Option<[f32; 3]> {
+    let hex = s.strip_prefix('#')?;
+    if hex.len() < 6 {
+        return None;
+    }
+    let mut rgb = [0.0; 3];
+    for (i, c) in rgb.iter_mut().enumerate() {
+        let byte = u8::from_str_radix(hex.get(2 * i..2 * i + 2)?, 16).ok()?;
+        *c = byte as f32 / 255.0;
+    }
+    Some(rgb)
+}
+
+/// Values of one point of a trace, each with the difference order it
+/// switches to, if it names one.
+fn token_v(s: &str) -> Vec<(Option<u8>, Value)> {
+    let b = s.as_bytes();
+    let mut token_v = Vec::new();
+    let mut i = 0;
+    while i < b.len() {
+        if b[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let order = match b[i] {
+            b'!' => Some(0),
+            b'\'' => Some(1),
+            b'"' => Some(2),
+            _ => None,
+        };
+        if order.is_some() {
+            i += 1;
+            while i < b.len() && b[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i == b.len() {
+                break;
+            }
+        }
+        let value = match b[i] {
+            b'?' => Value::Unknown,
+            b'*' => Value::Repeat,
+            b'T' => Value::Number(1.0),
+            b'F' => Value::Number(0.0),
+            _ => {
+                // Numbers may follow each other without a space, as in `3-4`.
+                let start = i;
+                if b[i] == b'-' || b[i] == b'+' {
+                    i += 1;
+                }
+                while i < b.len() && (b[i].is_ascii_digit() || b[i] == b'.') {
+                    i += 1;
+                }
+                if i < b.len() && (b[i] == b'e' || b[i] == b'E') {
+                    i += 1;
+                    if i < b.len() && (b[i] == b'-' || b[i] == b'+') {
+                        i += 1;
+                    }
+                    while i < b.len() && b[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                match s[start..i].parse() {
+                    Ok(n) => token_v.push((order, Value::Number(n))),
+                    // Skip what isn't a value at all.
+                    Err(_) => i = start + 1,
+                }
+                continue;
+            }
+        };
+        token_v.push((order, value));
+        i += 1;
+    }
+    token_v
+}
+
+/// The little of an XML element that reading needs, so that it runs on
+/// other DOMs than the browser's.
+trait Node: Sized {
+    fn local_name(&self) -> String;
+
+    /// Attribute by qualified name, as in `xml:id`.
+    fn attribute(&self, name: &str) -> Option<String>;
+
+    /// Child elements, in document order.
+    fn element_v(&self) -> Vec<Self>;
+
+    /// All the text inside.
+    fn text(&self) -> String;
+}
+
+impl Node for Element {
+    fn local_name(&self) -> String {
+        Element::local_name(self)
+    }
+
+    fn attribute(&self, name: &str) -> Option<String> {
+        self.get_attribute(name)
+    }
+
+    fn element_v(&self) -> Vec<Self> {
+        let children = self.children();
+        (0..children.length())
+            .filter_map(|i| children.item(i))
+            .collect()
+    }
+
+    fn text(&self) -> String {
+        self.text_content().unwrap_or_default()
+    }
+}
+
+/// Children of `el` named `name`, whatever their namespace prefix.
+fn child_v<N: Node>(el: &N, name: &str) -> Vec<N> {
+    el.element_v()
+        .into_iter()
+        .filter(|child| name.is_empty() || child.local_name() == name)
+        .collect()
+}
+
+/// Every element under `el` named `name`, in document order.
+fn descendant_v<N: Node>(el: &N, name: &str) -> Vec<N> {
+    let mut found_v = Vec::new();
+    for child in el.element_v() {
+        let below_v = descendant_v(&child, name);
+        if child.local_name() == name {
+            found_v.push(child);
+        }
+        found_v.extend(below_v);
+    }
+    found_v
+}
+
+fn xml_id(el: &impl Node) -> Option<String> {
+    el.attribute("xml:id").or_else(|| el.attribute("id"))
+}
+
+/// The id of `el` past `prefix`, if it is one `new_id` could have made.
+///
+/// Ids go on to the backend, so any other is left for a fresh one.
+fn own_id(el: &impl Node, prefix: &str) -> Option<String> {
+    xml_id(el)
+        .map(|id| id.strip_prefix(prefix).unwrap_or(&id).to_string())
+        .filter(|id| element::is_id(id))
+}
+
+/// The id a `#id` reference points to.
+fn ref_id(el: &impl Node, attr: &str) -> Option<String> {
+    el.attribute(attr)
+        .map(|r| r.trim_start_matches('#').to_string())
+}
+
+/// Text of the annotation of `el` with type `kind`.
+fn annotation(el: &impl Node, kind: &str) -> Option<String> {
+    child_v(el, "annotation")
+        .into_iter()
+        .find(|a| a.attribute("type").as_deref() == Some(kind))
+        .map(|a| a.text().trim().to_string())
+}
+
+fn format_channel_v(el: &impl Node) -> Vec<Channel> {
+    let mut channel_v: Vec<Channel> = child_v(el, "channel")
+        .iter()
+        .map(Channel::from_element)
+        .collect();
+    // Intermittent channels come last and may be left off any point.
+    for group in child_v(el, "intermittentChannels") {
+        channel_v.extend(child_v(&group, "channel").iter().map(Channel::from_element));
+    }
+    channel_v
+}
+
+struct Reader {
+    format_m: HashMap<String, Vec<Channel>>,
+    brush_m: HashMap<String, Brush>,
+    context_m: HashMap<String, Context>,
+    /// Makes ids for traces and groups without a fit one.
+    new_id: Box<dyn FnMut() -> String>,
+    layer_v: Vec<Layer>,
+    edge_v: Vec<Edge>,
+}
+
+impl Reader {
+    fn new(root: &impl Node, new_id: Box<dyn FnMut() -> String>) -> Self {
+        let mut reader = Self {
+            format_m: HashMap::new(),
+            brush_m: HashMap::new(),
+            context_m: HashMap::new(),
+            new_id,
+            layer_v: Vec::new(),
+            edge_v: Vec::new(),
+        };
+        for el in descendant_v(root, "traceFormat") {
+            if let Some(id) = xml_id(&el) {
+                reader.format_m.insert(id, format_channel_v(&el));
+            }
+        }
+        for el in descendant_v(root, "brush") {
+            if let Some(id) = xml_id(&el) {
+                reader.brush_m.insert(id, Brush::from_element(&el));
+            }
+        }
+        for el in descendant_v(root, "context") {
+            if let Some(id) = xml_id(&el) {
+                let context = reader.read_context(&el);
+                reader.context_m.insert(id, context);
+            }
+        }
+        reader
+    }
+
+    fn read_context(&self, el: &impl Node) -> Context {
+        let mut context = match ref_id(el, "contextRef") {
+            Some(id) => self.context_m.get(&id).cloned().unwrap_or_default(),
+            None => Context::default(),
+        };
+        if let Some(format) = child_v(el, "traceFormat").first() {
+            context.channel_v = Some(format_channel_v(format));
+        } else if let Some(id) = ref_id(el, "traceFormatRef") {
+            context.channel_v = self.format_m.get(&id).cloned().or(context.channel_v);
+        }
+        if let Some(brush) = child_v(el, "brush").first() {
+            context.brush = Some(Brush::from_element(brush));
+        } else if let Some(id) = ref_id(el, "brushRef") {
+            context.brush = self.brush_m.get(&id).copied().or(context.brush);
+        }
+        context
+    }
+
+    /// `scope` with the context and brush that `el` refers to.
+    fn scope_of(&self, el: &impl Node, scope: &Scope) -> Scope {
+        let mut scope = scope.clone();
+        if let Some(context) = ref_id(el, "contextRef").and_then(|id| self.context_m.get(&id)) {
+            if let Some(channel_v) = &context.channel_v {
+                scope.channel_v = channel_v.clone();
+            }
+            if let Some(brush) = context.brush {
+                scope.brush = brush;
+            }
+        }
+        if let Some(brush) = ref_id(el, "brushRef").and_then(|id| self.brush_m.get(&id)) {
+            scope.brush = *brush;
+        }
+        scope
+    }
+
+    fn read_group<N: Node>(&mut self, el: &N, mut scope: Scope, layer_id: &str) {
+        for child in child_v(el, "") {
+            match child.local_name().as_str() {
+                // A context among the traces holds for the ones after it.
+                "context" => {
+                    let context = self.read_context(&child);
+                    if let Some(channel_v) = context.channel_v {
+                        scope.channel_v = channel_v;
+                    }
+                    if let Some(brush) = context.brush {
+                        scope.brush = brush;
+                    }
+                }
+                "traceGroup" => {
+                    let group_scope = self.scope_of(&child, &scope);
+                    let id = self.read_layer(&child);
+                    self.read_group(&child, group_scope, &id);
+                }
+                "trace" => {
+                    let trace_scope = self.scope_of(&child, &scope);
+                    self.read_trace(&child, &trace_scope, layer_id);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Add the layer a trace group stands for, and return its id.
+    fn read_layer(&mut self, el: &impl Node) -> String {
+        let id = own_id(el, "layer-").unwrap_or_else(&mut self.new_id);
+        if self.layer_v.iter().all(|layer| layer.id != id) {
+            let mut layer = Layer {
+                id: id.clone(),
+                name: format!("Layer {}", self.layer_v.len() + 1),
+                visible: true,
+                locked: false,
+                opacity: 1.0,
+            };
+            if let Some(name) = annotation(el, "name").filter(|name| !name.is_empty()) {
+                layer.name = name;
+            }
+            if let Some(visible) = annotation(el, "visible") {
+                layer.visible = visible != "false";
+            }
+            if let Some(locked) = annotation(el, "locked") {
+                layer.locked = locked == "true";
+            }
+            if let Some(opacity) = annotation(el, "opacity").and_then(|o| o.parse::<f32>().ok()) {
+                layer.opacity = opacity.clamp(0.0, 1.0);
+            }
+            self.layer_v.push(layer);
+        }
+        id
+    }
+
+    fn read_trace(&mut self, el: &impl Node, scope: &Scope, layer_id: &str) {
+        if el.attribute("type").as_deref() == Some("penUp") {
+            return;
+        }
+        let channel_v = &scope.channel_v;
+        let position = |name: &str| channel_v.iter().position(|c| c.name == name);
+        let (Some(ix), Some(iy)) = (position("X"), position("Y")) else {
+            return;
+        };
+        let [iw, it, itx, ity, ir, ib, i_f] =
+            ["W", "T", "OTx", "OTy", "OR", "B1", "F"].map(position);
+
+        let mut edge = Edge {
+            id: own_id(el, "edge-").unwrap_or_else(&mut self.new_id),
+            layer_id: layer_id.to_string(),
+            point_v: Vec::new(),
+            stylus_v: Vec::new(),
+        };
+        let brush_width = scope.brush.width.unwrap_or(DEFAULT_WIDTH);
+        let mut decoder_v = vec![Decoder::default(); channel_v.len()];
+        let mut start_time = None;
+        let text = el.text();
+        for point_s in text.split(',') {
+            let value_v: Vec<Option<f32>> = token_v(point_s)
+                .into_iter()
+                .zip(decoder_v.iter_mut())
+                .map(|((order, value), decoder)| decoder.next(order, value))
+                .collect();
+            let get = |i: Option<usize>| {
+                let i = i?;
+                value_v
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .map(|v| v * channel_v[i].scale)
+            };
+            let (Some(x), Some(y)) = (get(Some(ix)), get(Some(iy))) else {
+                continue;
+            };
+            let width = match (get(iw), get(i_f)) {
+                (Some(w), _) => w,
+                (None, Some(force)) => {
+                    let max = i_f.and_then(|i| channel_v[i].max).unwrap_or(1.0);
+                    brush_width * (force / max).clamp(MIN_FORCE, 1.0)
+                }
+                (None, None) => brush_width,
+            };
+            let time = get(it).map(|t| t - *start_time.get_or_insert(t));
+            edge.push(
+                Point {
+                    pos: Point3::new(x, y, -1.0),
+                    color: scope.brush.color,
+                    width,
+                },
+                Stylus {
+                    tilt_x: get(itx).unwrap_or(0.0),
+                    tilt_y: get(ity).unwrap_or(0.0),
+                    twist: get(ir).unwrap_or(0.0),
+                    barrel: get(ib).is_some_and(|b| b != 0.0),
+                    eraser: false,
+                    time: time.unwrap_or(0.0),
+                },
+            );
+        }
+        if !edge.is_empty() {
+            self.edge_v.push(edge);
+        }
+    }
+}
+
+// Public
+/// An InkML document of every edge in `doc`.
+pub fn to_inkml(doc: &Document) -> String {
+    let mut ink = format!("<ink xmlns=\"{INKML_NS}\">\n");
+    for (kind, text) in [
+        ("generator", &doc.meta.generator),
+        ("created", &doc.meta.created),
+    ] {
+        if !text.is_empty() {
+            let _ = writeln!(
+                ink,
+                r#"<annotation type="{kind}">{}</annotation>"#,
+                escape(text)
+            );
+        }
+    }
+
+    // One brush for every look an edge has, by color, transparency and width.
+    let mut brush_v: Vec<String> = Vec::new();
+    let mut brush_i_v: Vec<usize> = Vec::with_capacity(doc.edge_v.len());
+    for edge in &doc.edge_v {
+        let color = edge.point_v.first().map_or([0.0; 4], |pt| pt.color);
+        let width = edge.point_v.iter().fold(0.0f32, |w, pt| w.max(pt.width));
+        let brush = format!(
+            "<brushProperty name=\"color\" value=\"{}\"/>\n\
+             <brushProperty name=\"width\" value=\"{width:.2}\" units=\"px\"/>\n\
+             <brushProperty name=\"transparency\" value=\"{:.2}\"/>\n",
+            rgb(color),
+            1.0 - color[3]
+        );
+        let i = match brush_v.iter().position(|b| *b == brush) {
+            Some(i) => i,
+            None => {
+                brush_v.push(brush);
+                brush_v.len() - 1
+            }
+        };
+        brush_i_v.push(i);
+    }
+
+    ink.push_str("<definitions>\n<context xml:id=\"ctx\">\n<traceFormat>\n");
+    for (name, kind, units) in [
+        ("X", "decimal", "px"),
+        ("Y", "decimal", "px"),
+        ("W", "decimal", "px"),
+        ("T", "integer", "ms"),
+        ("OTx", "decimal", "deg"),
+        ("OTy", "decimal", "deg"),
+        ("OR", "decimal", "deg"),
+    ] {
+        let _ = writeln!(
+            ink,
+            r#"<channel name="{name}" type="{kind}" units="{units}"/>"#
+        );
+    }
+    ink.push_str("<channel name=\"B1\" type=\"boolean\"/>\n</traceFormat>\n</context>\n");
+    for (i, brush) in brush_v.iter().enumerate() {
+        let _ = write!(ink, "<brush xml:id=\"brush-{i}\">\n{brush}</brush>\n");
+    }
+    ink.push_str("</definitions>\n");
+
+    let write_trace = |ink: &mut String, i: usize, edge: &Edge| {
+        if edge.is_empty() {
+            return;
+        }
+        let point_v: Vec<String> = edge
+            .point_v
+            .iter()
+            .zip(&edge.stylus_v)
+            .map(|(pt, stylus)| {
+                format!(
+                    "{:.2} {:.2} {:.2} {:.0} {} {} {} {}",
+                    pt.pos.x,
+                    pt.pos.y,
+                    pt.width,
+                    stylus.time,
+                    stylus.tilt_x,
+                    stylus.tilt_y,
+                    stylus.twist,
+                    if stylus.barrel { "T" } else { "F" }
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            ink,
+            r##"<trace xml:id="edge-{}" contextRef="#ctx" brushRef="#brush-{}">{}</trace>"##,
+            escape(&edge.id),
+            brush_i_v[i],
+            point_v.join(", ")
+        );
+    };
+
+    // Edges on layers that aren't listed go below the rest, outside any group.
+    for (i, edge) in doc.edge_v.iter().enumerate() {
+        if doc.layer_v.iter().all(|layer| layer.id != edge.layer_id) {
+            write_trace(&mut ink, i, edge);
+        }
+    }
+    for layer in &doc.layer_v {
+        let _ = writeln!(ink, r#"<traceGroup xml:id="layer-{}">"#, escape(&layer.id));
+        for (kind, text) in [
+            ("name", escape(&layer.name)),
+            ("visible", layer.visible.to_string()),
+            ("locked", layer.locked.to_string()),
+            ("opacity", layer.opacity.to_string()),
+        ] {
+            let _ = writeln!(ink, r#"<annotation type="{kind}">{text}</annotation>"#);
+        }
+        for (i, edge) in doc.edge_v.iter().enumerate() {
+            if edge.layer_id == layer.id {
+                write_trace(&mut ink, i, edge);
+            }
+        }
+        ink.push_str("</traceGroup>\n");
+    }
+    ink.push_str("</ink>\n");
+    ink
+}
+
+/// Read the traces of an InkML document.
+///
+/// Traces outside any trace group go on the default layer, and every trace
+/// group becomes a layer.
+pub fn from_inkml(s: &str) -> err::Result<Document> {
+    let parser = DomParser::new().map_err(util::map_js_error)?;
+    let xml = parser
+        .parse_from_string(s, SupportedType::ApplicationXml)
+        .map_err(util::map_js_error)?;
+    if xml.get_elements_by_tag_name("parsererror").length() > 0 {
+        return Err(err::Error::Other(format!(
+            "when read InkML:\n\tnot well-formed XML"
+        )));
+    }
+    let root = xml
+        .document_element()
+        .filter(|root| root.local_name() == "ink")
+        .ok_or(err::Error::Other(format!(
+            "when read InkML:\n\tno <ink> element"
+        )))?;
+
+    Ok(read_document(&root, Box::new(element::new_id)))
+}
+
+/// The document under `root`, an `<ink>` element.
+fn read_document(root: &impl Node, new_id: Box<dyn FnMut() -> String>) -> Document {
+    let mut reader = Reader::new(root, new_id);
+    let default_v = match descendant_v(root, "traceFormat").first() {
+        Some(format) => format_channel_v(format),
+        None => Channel::xy(),
+    };
+    let scope = Scope {
+        channel_v: default_v,
+        brush: Brush::default(),
+    };
+    reader.read_group(root, scope, DEFAULT_LAYER_ID);
+
+    Document {
+        meta: Meta {
+            generator: annotation(root, "generator").unwrap_or_default(),
+            created: annotation(root, "created").unwrap_or_default(),
+        },
+        layer_v: reader.layer_v,
+        edge_v: reader.edge_v,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::Point3;
+    use painting::point::Point;
+
+    use crate::{
+        element::{Edge, Layer, Stylus, DEFAULT_LAYER_ID},
+        export::Document,
+    };
+
+    use super::{read_document, to_inkml, Node};
+
+    const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+    impl Node for roxmltree::Node<'_, '_> {
+        fn local_name(&self) -> String {
+            self.tag_name().name().to_string()
+        }
+
+        fn attribute(&self, name: &str) -> Option<String> {
+            let value = match name.strip_prefix("xml:") {
+                Some(name) => roxmltree::Node::attribute(self, (XML_NS, name)),
+                None => roxmltree::Node::attribute(self, name),
+            };
+            value.map(String::from)
+        }
+
+        fn element_v(&self) -> Vec<Self> {
+            self.children().filter(|child| child.is_element()).collect()
+        }
+
+        fn text(&self) -> String {
+            self.descendants()
+                .filter(|node| node.is_text())
+                .filter_map(|node| node.text())
+                .collect()
+        }
+    }
+
+    fn read(s: &str) -> Document {
+        let xml = roxmltree::Document::parse(s).unwrap();
+        let mut id_n = 0;
+        let new_id = move || {
+            id_n += 1;
+            format!("{id_n:016x}")
+        };
+        read_document(&xml.root_element(), Box::new(new_id))
+    }
+
+    fn ink(trace_format: &str, trace_v: &[&str]) -> String {
+        format!(
+            "<ink xmlns=\"http://www.w3.org/2003/InkML\">\
+             <traceFormat>{trace_format}</traceFormat>{}</ink>",
+            trace_v.concat()
+        )
+    }
+
+    fn xy_v(edge: &Edge) -> Vec<(f32, f32)> {
+        edge.point_v.iter().map(|pt| (pt.pos.x, pt.pos.y)).collect()
+    }
+
+    fn near(a: f32, b: f32, tolerance: f32) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn edge_round_trip() {
+        let layer = Layer {
+            id: "0123456789abcdef".to_string(),
+            name: "Notes".to_string(),
+            visible: true,
+            locked: false,
+            opacity: 0.5,
+        };
+        let mut edge = Edge {
+            id: "fedcba9876543210".to_string(),
+            layer_id: layer.id.clone(),
+            point_v: Vec::new(),
+            stylus_v: Vec::new(),
+        };
+        for i in 0..5 {
+            let i = i as f32;
+            edge.push(
+                Point {
+                    pos: Point3::new(10.0 + i * 3.25, -4.5 + i * i, -1.0),
+                    color: [0.2, 0.4, 0.6, 0.8],
+                    width: 2.0 + i,
+                },
+                Stylus {
+                    tilt_x: 10.0,
+                    tilt_y: -5.0,
+                    twist: 90.0,
+                    barrel: i == 2.0,
+                    eraser: false,
+                    time: i * 16.0,
+                },
+            );
+        }
+        let doc = Document {
+            layer_v: vec![layer.clone()],
+            edge_v: vec![edge.clone()],
+            ..Document::default()
+        };
+
+        let read_doc = read(&to_inkml(&doc));
+        assert_eq!(read_doc.layer_v, vec![layer]);
+        assert_eq!(read_doc.edge_v.len(), 1);
+        let read_edge = &read_doc.edge_v[0];
+        assert_eq!(read_edge.id, edge.id);
+        assert_eq!(read_edge.layer_id, edge.layer_id);
+        assert_eq!(read_edge.point_v.len(), edge.point_v.len());
+        for (a, b) in edge.point_v.iter().zip(&read_edge.point_v) {
+            assert!(near(a.pos.x, b.pos.x, 0.01) && near(a.pos.y, b.pos.y, 0.01));
+            assert!(near(a.width, b.width, 0.01));
+            for (ca, cb) in a.color.iter().zip(b.color) {
+                assert!(near(*ca, cb, 0.01), "{:?} != {:?}", a.color, b.color);
+            }
+        }
+        assert_eq!(read_edge.stylus_v, edge.stylus_v);
+    }
+
+    #[test]
+    fn unlisted_layer_goes_to_default() {
+        let mut edge = Edge {
+            id: "fedcba9876543210".to_string(),
+            layer_id: "0123456789abcdef".to_string(),
+            point_v: Vec::new(),
+            stylus_v: Vec::new(),
+        };
+        edge.push(
+            Point {
+                pos: Point3::new(1.0, 2.0, -1.0),
+                color: [0.0, 0.0, 0.0, 1.0],
+                width: 2.0,
+            },
+            Stylus::default(),
+        );
+        let doc = Document {
+            edge_v: vec![edge],
+            ..Document::default()
+        };
+        let read_doc = read(&to_inkml(&doc));
+        assert!(read_doc.layer_v.is_empty());
+        assert_eq!(read_doc.edge_v[0].layer_id, DEFAULT_LAYER_ID);
+    }
+
+    #[test]
+    fn difference_coded_traces() {
+        let s = ink(
+            r#"<channel name="X"/><channel name="Y"/>"#,
+            &[
+                // Plain, then first differences, then second, a repeat and plain again.
+                r#"<trace>10 20, '1 '2, "1 "0, * *, !0 !0</trace>"#,
+                // Numbers may run together.
+                r#"<trace>5-5,'-1'-1</trace>"#,
+            ],
+        );
+        let doc = read(&s);
+        assert_eq!(
+            xy_v(&doc.edge_v[0]),
+            vec![
+                (10.0, 20.0),
+                (11.0, 22.0),
+                (13.0, 24.0),
+                (15.0, 26.0),
+                (0.0, 0.0)
+            ]
+        );
+        assert_eq!(xy_v(&doc.edge_v[1]), vec![(5.0, -5.0), (4.0, -6.0)]);
+    }
+
+    #[test]
+    fn pen_up_traces_skipped() {
+        let s = ink(
+            r#"<channel name="X"/><channel name="Y"/>"#,
+            &[
+                r#"<trace type="penUp">0 0, 9 9</trace>"#,
+                r#"<trace type="penDown">1 1, 2 2</trace>"#,
+                r#"<trace>3 3</trace>"#,
+            ],
+        );
+        let doc = read(&s);
+        assert_eq!(doc.edge_v.len(), 2);
+        assert_eq!(xy_v(&doc.edge_v[0]), vec![(1.0, 1.0), (2.0, 2.0)]);
+    }
+
+    #[test]
+    fn unsupported_channels_dropped() {
+        let s = ink(
+            r#"<channel name="X" units="mm"/><channel name="Z"/><channel name="Y" units="mm"/>
+               <channel name="S"/><channel name="F" max="1024"/><channel name="OA"/>"#,
+            &[r#"<trace>25.4 7 -25.4 1 1024 45, 0 8 0 0 512 45</trace>"#],
+        );
+        let doc = read(&s);
+        let edge = &doc.edge_v[0];
+        assert_eq!(xy_v(edge), vec![(96.0, -96.0), (0.0, 0.0)]);
+        for pt in &edge.point_v {
+            assert_eq!(pt.pos.z, -1.0);
+        }
+        // F thins the default 2px brush.
+        assert!(near(edge.point_v[0].width, 2.0, 1e-4));
+        assert!(near(edge.point_v[1].width, 1.0, 1e-4));
+        assert_eq!(edge.stylus_v, vec![Stylus::default(); 2]);
+    }
+
+    #[test]
+    fn unsafe_ids_replaced() {
+        let s = ink(
+            r#"<channel name="X"/><channel name="Y"/>"#,
+            &[
+                r#"<traceGroup xml:id="layer-x _&#10;huiwen->canvas->layer = = _ _">"#,
+                r#"<trace xml:id="edge-0 _ huiwen->canvas->edge = = _ _">1 1</trace>"#,
+                r#"<trace xml:id="edge-00000000000000aa">2 2</trace>"#,
+                "</traceGroup>",
+            ],
+        );
+        let doc = read(&s);
+        assert_eq!(doc.layer_v.len(), 1);
+        assert_eq!(doc.layer_v[0].id, "0000000000000001");
+        assert_eq!(doc.edge_v[0].id, "0000000000000002");
+        assert_eq!(doc.edge_v[0].layer_id, "0000000000000001");
+        assert_eq!(doc.edge_v[1].id, "00000000000000aa");
+    }
+}
@@ -0,0 +1,113 @@
+mod document;
+mod inkml;
+mod outline;
+mod pdf;
+#[cfg(target_arch = "wasm32")]
+mod png;
+mod raster;
+mod svg;
+
+use cgmath::Vector2;
+
+use crate::element::{Edge, Layer, Rect, Viewport};
+
+/// Longest side of an exported image, in pixels.
+const MAX_SIDE: f32 = 4096.0;
+/// World units left around exported content.
+const MARGIN: f32 = 16.0;
+
+/// `s` made safe for XML text and attribute values.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `#rrggbb` for the color part of `color`.
+fn rgb(color: [f32; 4]) -> String {
+    let [r, g, b] =
+        [color[0], color[1], color[2]].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Shrink `frame` until it fits in `MAX_SIDE`.
+fn cap(frame: Viewport) -> Viewport {
+    let side = frame.size.x.max(frame.size.y);
+    if side <= MAX_SIDE {
+        return frame;
+    }
+    let k = MAX_SIDE / side;
+    Viewport {
+        zoom: frame.zoom * k,
+        size: frame.size * k,
+        ..frame
+    }
+}
+
+// Public
+pub use document::*;
+pub use inkml::*;
+pub use outline::*;
+pub use pdf::*;
+#[cfg(target_arch = "wasm32")]
+pub use png::*;
+pub use raster::*;
+pub use svg::*;
+
+/// Id of the layer that holds edges whose own layer is gone.
+///
+/// `new_id` only makes hex digits, so no real layer has it.
+pub const OTHER_LAYER_ID: &str = "other";
+
+/// Visible layers bottom first, each with its edges in drawing order.
+///
+/// Edges on layers that aren't listed go in a layer of their own below the rest.
+pub fn layer_group_v<'a, I>(edge_v: I, layer_v: &[Layer]) -> Vec<(Layer, Vec<Edge>)>
+where
+    I: IntoIterator<Item = &'a Edge>,
+{
+    let mut group_v: Vec<(Layer, Vec<Edge>)> = layer_v
+        .iter()
+        .map(|layer| (layer.clone(), Vec::new()))
+        .collect();
+    let mut other_v = Vec::new();
+    for edge in edge_v {
+        match layer_v.iter().position(|layer| layer.id == edge.layer_id) {
+            Some(i) => group_v[i].1.push(edge.clone()),
+            None => other_v.push(edge.clone()),
+        }
+    }
+    if !other_v.is_empty() {
+        let layer = Layer {
+            id: OTHER_LAYER_ID.to_string(),
+            name: "Other".to_string(),
+            visible: true,
+            locked: false,
+            opacity: 1.0,
+        };
+        group_v.insert(0, (layer, other_v));
+    }
+    group_v.retain(|(layer, edge_v)| layer.visible && !edge_v.is_empty());
+    group_v
+}
+
+/// Camera for an image of `rect` at `scale` pixels per world unit, unrotated.
+pub fn frame_rect(rect: &Rect, scale: f32) -> Viewport {
+    let rect = rect.inflate(MARGIN);
+    cap(Viewport {
+        center: rect.center(),
+        zoom: scale,
+        rotation: 0.0,
+        size: Vector2::new(rect.width() * scale, rect.height() * scale).map(|v| v.ceil()),
+    })
+}
+
+/// Camera for an image of what `viewport` shows, `scale` times as many pixels.
+pub fn frame_view(viewport: &Viewport, scale: f32) -> Viewport {
+    cap(Viewport {
+        zoom: viewport.zoom * scale,
+        size: (viewport.size * scale).map(|v| v.ceil()),
+        ..*viewport
+    })
+}
@@ -0,0 +1,110 @@
+use cgmath::{InnerSpace, Point2, Vector2};
+use painting::point::Point;
+
+/// Segments of a half circle closing either end of a stroke.
+const CAP_SEGMENT_N: usize = 8;
+/// Longest a corner may reach out, in half widths.
+const MITER_LIMIT: f32 = 2.0;
+/// Half width below which a stroke is drawn as a hairline.
+const MIN_RADIUS: f32 = 0.25;
+
+// Public
+/// Closed outline around a stroke, following its width point by point.
+///
+/// Positions and widths stay in the units of `point_v`. Each end gets a round
+/// cap, and a single point becomes a dot.
+pub fn outline(point_v: &[Point]) -> Vec<Point2<f32>> {
+    let mut center_v: Vec<(Point2<f32>, f32)> = Vec::with_capacity(point_v.len());
+    for pt in point_v {
+        let pos = Point2::new(pt.pos.x, pt.pos.y);
+        let radius = (pt.width / 2.0).max(MIN_RADIUS);
+        match center_v.last_mut() {
+            // Repeated positions have no direction to offset along.
+            Some((last, last_radius)) if (pos - *last).magnitude2() < 1e-6 => {
+                *last_radius = last_radius.max(radius);
+            }
+            _ => center_v.push((pos, radius)),
+        }
+    }
+    match center_v.len() {
+        0 => Vec::new(),
+        1 => {
+            let (pos, radius) = center_v[0];
+            arc(pos, radius, Vector2::new(1.0, 0.0), 2 * CAP_SEGMENT_N)
+        }
+        n => {
+            // Normal of each segment, then per point the miter between them.
+            let segment_v: Vec<Vector2<f32>> = center_v
+                .windows(2)
+                .map(|pair| {
+                    let t = (pair[1].0 - pair[0].0).normalize();
+                    Vector2::new(-t.y, t.x)
+                })
+                .collect();
+            let normal_v: Vec<Vector2<f32>> = (0..n)
+                .map(|i| {
+                    let before = segment_v[i.saturating_sub(1)];
+                    let after = segment_v[i.min(n - 2)];
+                    let sum = before + after;
+                    if sum.magnitude2() < 1e-6 {
+                        return before;
+                    }
+                    let miter = sum.normalize();
+                    miter / miter.dot(before).max(1.0 / MITER_LIMIT)
+                })
+                .collect();
+
+            let mut polygon = Vec::with_capacity(2 * n + 2 * CAP_SEGMENT_N);
+            for ((pos, radius), normal) in center_v.iter().zip(&normal_v) {
+                polygon.push(pos + normal * *radius);
+            }
+            let (pos, radius) = center_v[n - 1];
+            polygon.extend(
+                arc(pos, radius, normal_v[n - 1], CAP_SEGMENT_N)
+                    .into_iter()
+                    .skip(1),
+            );
+            for ((pos, radius), normal) in center_v.iter().zip(&normal_v).rev().skip(1) {
+                polygon.push(pos - normal * *radius);
+            }
+            let (pos, radius) = center_v[0];
+            polygon.extend(
+                arc(pos, radius, -normal_v[0], CAP_SEGMENT_N)
+                    .into_iter()
+                    .skip(1),
+            );
+            polygon.pop();
+            polygon
+        }
+    }
+}
+
+/// `point_v` cut where its color changes, each run with its color.
+///
+/// A run ends on the first point of the next one, so the pieces join.
+pub fn color_run_v(point_v: &[Point]) -> Vec<(&[Point], [f32; 4])> {
+    let mut run_v = Vec::new();
+    let mut start = 0;
+    for i in 1..=point_v.len() {
+        if i == point_v.len() || point_v[i].color != point_v[start].color {
+            run_v.push((
+                &point_v[start..(i + 1).min(point_v.len())],
+                point_v[start].color,
+            ));
+            start = i;
+        }
+    }
+    run_v
+}
+
+/// Points on the circle around `center`, starting in direction `from` and
+/// turning against the stroke normal by `segment_n` cap segments.
+fn arc(center: Point2<f32>, radius: f32, from: Vector2<f32>, segment_n: usize) -> Vec<Point2<f32>> {
+    let start = from.y.atan2(from.x);
+    (0..=segment_n)
+        .map(|i| {
+            let angle = start - std::f32::consts::PI * i as f32 / CAP_SEGMENT_N as f32;
+            center + Vector2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
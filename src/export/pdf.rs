@@ -0,0 +1,400 @@
+use std::fmt::Write;
+
+use crate::element::{Edge, Layer, PageSize, Rect};
+
+use super::{color_run_v, outline};
+
+/// Blank border around the content of a page, in points.
+const PAGE_MARGIN: f32 = 36.0;
+
+/// Width and height of a page, in points.
+fn page_pt(size: PageSize, landscape: bool) -> (f32, f32) {
+    let (w, h) = match size {
+        PageSize::A4 => (595.28, 841.89),
+        PageSize::A3 => (841.89, 1190.55),
+        PageSize::Letter => (612.0, 792.0),
+    };
+    if landscape {
+        (h, w)
+    } else {
+        (w, h)
+    }
+}
+
+/// `s` as a PDF string, in UTF-16 if it isn't plain ASCII.
+fn pdf_text(s: &str) -> String {
+    if s.is_ascii() {
+        let mut out = String::from("(");
+        for c in s.chars() {
+            if matches!(c, '(' | ')' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+        out.push(')');
+        out
+    } else {
+        let mut out = String::from("<FEFF");
+        for unit in s.encode_utf16() {
+            let _ = write!(out, "{unit:04X}");
+        }
+        out.push('>');
+        out
+    }
+}
+
+/// Index of `item` in `item_v`, pushing it if it is new.
+fn intern(item_v: &mut Vec<String>, item: String) -> usize {
+    match item_v.iter().position(|it| *it == item) {
+        Some(i) => i,
+        None => {
+            item_v.push(item);
+            item_v.len() - 1
+        }
+    }
+}
+
+// Public
+/// What goes on one page: layer groups, and the part of the board to fit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PdfPage {
+    pub group_v: Vec<(Layer, Vec<Edge>)>,
+    pub rect: Rect,
+}
+
+/// A PDF document with one page for each of `page_v`.
+///
+/// Each page fits its rectangle within the margins, unrotated, and draws every
+/// edge as a filled path around its outline. Layers become optional content,
+/// so viewers can list and hide them; layer opacity multiplies into the
+/// opacity of each of its paths.
+pub fn to_pdf(page_v: &[PdfPage], size: PageSize, landscape: bool) -> Vec<u8> {
+    let (page_w, page_h) = page_pt(size, landscape);
+
+    // Layers and opacities, shared by all pages.
+    let mut layer_v: Vec<String> = Vec::new();
+    let mut layer_name_v: Vec<String> = Vec::new();
+    let mut alpha_v: Vec<String> = Vec::new();
+    let mut content_v: Vec<String> = Vec::with_capacity(page_v.len());
+    for page in page_v {
+        let rect = &page.rect;
+        let k = ((page_w - 2.0 * PAGE_MARGIN) / rect.width().max(1.0))
+            .min((page_h - 2.0 * PAGE_MARGIN) / rect.height().max(1.0));
+        // Center the content, and turn the y axis down like the board's.
+        let left = (page_w - rect.width() * k) / 2.0;
+        let top = (page_h - rect.height() * k) / 2.0;
+        let mut content = String::from("q\n");
+        let _ = writeln!(
+            content,
+            "{k:.4} 0 0 {:.4} {:.2} {:.2} cm",
+            -k,
+            left - rect.min.x * k,
+            page_h - top + rect.min.y * k
+        );
+        let _ = writeln!(
+            content,
+            "{:.2} {:.2} {:.2} {:.2} re W n",
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height()
+        );
+        for (layer, edge_v) in &page.group_v {
+            let i = intern(&mut layer_v, layer.id.clone());
+            if i == layer_name_v.len() {
+                layer_name_v.push(layer.name.clone());
+            }
+            let _ = writeln!(content, "/OC /L{i} BDC");
+            for (run, color) in edge_v.iter().flat_map(|edge| color_run_v(&edge.point_v)) {
+                let polygon = outline(run);
+                if polygon.is_empty() {
+                    continue;
+                }
+                let alpha = (color[3] * layer.opacity).clamp(0.0, 1.0);
+                let j = intern(&mut alpha_v, format!("{alpha:.3}"));
+                let _ = writeln!(
+                    content,
+                    "{:.3} {:.3} {:.3} rg /GS{j} gs",
+                    color[0].clamp(0.0, 1.0),
+                    color[1].clamp(0.0, 1.0),
+                    color[2].clamp(0.0, 1.0)
+                );
+                for (n, pt) in polygon.iter().enumerate() {
+                    let op = if n == 0 { "m" } else { "l" };
+                    let _ = writeln!(content, "{:.2} {:.2} {op}", pt.x, pt.y);
+                }
+                content.push_str("h f\n");
+            }
+            content.push_str("EMC\n");
+        }
+        content.push_str("Q\n");
+        content_v.push(content);
+    }
+
+    // Objects 1 to 3 are the catalog, the page tree and the info, then come
+    // the layers, the opacities, and a page and its content for every page.
+    let layer_obj = |i: usize| 4 + i;
+    let alpha_obj = |j: usize| 4 + layer_v.len() + j;
+    let page_obj = |p: usize| 4 + layer_v.len() + alpha_v.len() + 2 * p;
+
+    let ocg_v: Vec<String> = (0..layer_v.len())
+        .map(|i| format!("{} 0 R", layer_obj(i)))
+        .collect();
+    let mut catalog = String::from("<< /Type /Catalog /Pages 2 0 R");
+    if !ocg_v.is_empty() {
+        let _ = write!(
+            catalog,
+            " /OCProperties << /OCGs [{0}] /D << /Order [{0}] >> >>",
+            ocg_v.join(" ")
+        );
+    }
+    catalog.push_str(" >>");
+
+    let kid_v: Vec<String> = (0..page_v.len())
+        .map(|p| format!("{} 0 R", page_obj(p)))
+        .collect();
+    let mut obj_v = vec![
+        catalog,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kid_v.join(" "),
+            page_v.len()
+        ),
+        format!("<< /Producer {} >>", pdf_text("huiwen")),
+    ];
+    for name in &layer_name_v {
+        obj_v.push(format!("<< /Type /OCG /Name {} >>", pdf_text(name)));
+    }
+    for alpha in &alpha_v {
+        obj_v.push(format!("<< /Type /ExtGState /ca {alpha} >>"));
+    }
+
+    let mut resource = String::from("<< /ExtGState <<");
+    for j in 0..alpha_v.len() {
+        let _ = write!(resource, " /GS{j} {} 0 R", alpha_obj(j));
+    }
+    resource.push_str(" >> /Properties <<");
+    for i in 0..layer_v.len() {
+        let _ = write!(resource, " /L{i} {} 0 R", layer_obj(i));
+    }
+    resource.push_str(" >> >>");
+    for (p, content) in content_v.iter().enumerate() {
+        obj_v.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {page_w:.2} {page_h:.2}] /Resources {resource} /Contents {} 0 R >>",
+            page_obj(p) + 1
+        ));
+        obj_v.push(format!(
+            "<< /Length {} >>\nstream\n{content}endstream",
+            content.len()
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.5\n");
+    let mut offset_v = Vec::with_capacity(obj_v.len());
+    for (i, obj) in obj_v.iter().enumerate() {
+        offset_v.push(pdf.len());
+        let _ = write!(pdf, "{} 0 obj\n{obj}\nendobj\n", i + 1);
+    }
+    let xref = pdf.len();
+    let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", obj_v.len() + 1);
+    for offset in offset_v {
+        let _ = writeln!(pdf, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        obj_v.len() + 1
+    );
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{Point2, Point3};
+    use painting::point::Point;
+
+    use crate::{
+        element::{Edge, Layer, PageSize, Rect, Stylus},
+        export::layer_group_v,
+    };
+
+    use super::{to_pdf, PdfPage};
+
+    fn edge(id: &str, layer_id: &str, point_v: &[((f32, f32), [f32; 4])]) -> Edge {
+        Edge {
+            id: id.to_string(),
+            layer_id: layer_id.to_string(),
+            point_v: point_v
+                .iter()
+                .map(|&((x, y), color)| Point {
+                    pos: Point3::new(x, y, 0.0),
+                    color,
+                    width: 5.4,
+                })
+                .collect(),
+            stylus_v: vec![Stylus::default(); point_v.len()],
+        }
+    }
+
+    fn layer(id: &str, name: &str) -> Layer {
+        Layer {
+            id: id.to_string(),
+            name: name.to_string(),
+            visible: true,
+            locked: false,
+            opacity: 1.0,
+        }
+    }
+
+    fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Rect {
+        Rect::from_corners(Point2::new(x0, y0), Point2::new(x1, y1))
+    }
+
+    /// The objects of `pdf` by number, after checking that the cross
+    /// reference table finds every one of them.
+    fn object_v(pdf: &[u8]) -> Vec<String> {
+        let pdf = std::str::from_utf8(pdf).unwrap();
+        assert!(pdf.starts_with("%PDF-1.5\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        let tail = &pdf[pdf.rfind("startxref\n").unwrap() + "startxref\n".len()..];
+        let xref: usize = tail.lines().next().unwrap().parse().unwrap();
+        let mut line_v = pdf[xref..].lines();
+        assert_eq!(line_v.next(), Some("xref"));
+        let (first, n) = line_v.next().unwrap().split_once(' ').unwrap();
+        assert_eq!(first, "0");
+        let n: usize = n.parse().unwrap();
+        assert_eq!(line_v.next(), Some("0000000000 65535 f "));
+
+        let mut obj_v = Vec::with_capacity(n - 1);
+        for i in 1..n {
+            let entry = line_v.next().unwrap();
+            assert!(entry.ends_with(" 00000 n "), "{entry:?}");
+            let offset: usize = entry[..10].parse().unwrap();
+            let obj = &pdf[offset..];
+            let head = format!("{i} 0 obj\n");
+            assert!(obj.starts_with(&head), "object {i} is not at {offset}");
+            obj_v.push(obj[head.len()..obj.find("\nendobj\n").unwrap()].to_string());
+        }
+        assert_eq!(line_v.next(), Some("trailer"));
+        assert!(line_v
+            .next()
+            .unwrap()
+            .starts_with(&format!("<< /Size {n} /Root 1 0 R")));
+        obj_v
+    }
+
+    fn page_n(obj_v: &[String]) -> usize {
+        obj_v
+            .iter()
+            .filter(|obj| obj.starts_with("<< /Type /Page /"))
+            .count()
+    }
+
+    fn ocg_name_v(obj_v: &[String]) -> Vec<String> {
+        obj_v
+            .iter()
+            .filter_map(|obj| obj.strip_prefix("<< /Type /OCG /Name "))
+            .map(|rest| rest.trim_end_matches(" >>").to_string())
+            .collect()
+    }
+
+    fn stream_v(obj_v: &[String]) -> Vec<&str> {
+        obj_v
+            .iter()
+            .filter_map(|obj| {
+                let length: usize = obj
+                    .strip_prefix("<< /Length ")?
+                    .split_once(' ')?
+                    .0
+                    .parse()
+                    .ok()?;
+                let body = &obj[obj.find("stream\n")? + "stream\n".len()..];
+                assert_eq!(&body[length..], "endstream");
+                Some(&body[..length])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn structure() {
+        let layer_v = [
+            layer("default", "Layer 1"),
+            layer("0123456789abcdef", "Ink (2)"),
+        ];
+        let black = [0.0, 0.0, 0.0, 1.0];
+        let edge_v = [
+            edge(
+                "e1",
+                "default",
+                &[((0.0, 0.0), black), ((100.0, 50.0), black)],
+            ),
+            edge("e2", "0123456789abcdef", &[((10.0, 10.0), black)]),
+            edge("e3", "gone", &[((20.0, 20.0), black)]),
+        ];
+        let group_v = layer_group_v(&edge_v, &layer_v);
+        let page_v: Vec<PdfPage> = [rect(0.0, 0.0, 100.0, 50.0), rect(0.0, 0.0, 30.0, 30.0)]
+            .into_iter()
+            .map(|rect| PdfPage {
+                group_v: group_v.clone(),
+                rect,
+            })
+            .collect();
+
+        let obj_v = object_v(&to_pdf(&page_v, PageSize::A4, false));
+        assert!(obj_v[1].contains("/Count 2"));
+        assert_eq!(page_n(&obj_v), 2);
+        // The group of edges on missing layers is a layer of its own.
+        assert_eq!(
+            ocg_name_v(&obj_v),
+            ["(Other)", "(Layer 1)", "(Ink \\(2\\))"]
+        );
+        assert_eq!(stream_v(&obj_v).len(), 2);
+        assert!(obj_v
+            .iter()
+            .filter(|obj| obj.starts_with("<< /Type /Page /"))
+            .all(|obj| obj.contains("/MediaBox [0 0 595.28 841.89]")));
+    }
+
+    #[test]
+    fn landscape_pages() {
+        let page_v = [PdfPage {
+            group_v: Vec::new(),
+            rect: rect(0.0, 0.0, 10.0, 10.0),
+        }];
+        let obj_v = object_v(&to_pdf(&page_v, PageSize::Letter, true));
+        assert_eq!(page_n(&obj_v), 1);
+        assert!(ocg_name_v(&obj_v).is_empty());
+        assert!(obj_v
+            .iter()
+            .any(|obj| obj.contains("/MediaBox [0 0 792.00 612.00]")));
+    }
+
+    #[test]
+    fn color_changes_along_an_edge() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let blue = [0.0, 0.0, 1.0, 0.5];
+        let layer_v = [layer("default", "Layer 1")];
+        let edge_v = [edge(
+            "e1",
+            "default",
+            &[
+                ((0.0, 0.0), red),
+                ((10.0, 0.0), red),
+                ((20.0, 0.0), blue),
+                ((30.0, 0.0), blue),
+            ],
+        )];
+        let page_v = [PdfPage {
+            group_v: layer_group_v(&edge_v, &layer_v),
+            rect: rect(0.0, 0.0, 30.0, 10.0),
+        }];
+        let obj_v = object_v(&to_pdf(&page_v, PageSize::A4, false));
+        let content = stream_v(&obj_v)[0];
+        assert!(content.contains("1.000 0.000 0.000 rg /GS0 gs"));
+        assert!(content.contains("0.000 0.000 1.000 rg /GS1 gs"));
+        assert_eq!(content.matches("h f").count(), 2);
+        assert!(obj_v
+            .iter()
+            .any(|obj| obj == "<< /Type /ExtGState /ca 0.500 >>"));
+    }
+}
@@ -0,0 +1,140 @@
+use cgmath::Point2;
+use painting::{point::Pen, AsCanvas};
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData};
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    element::{Edge, Layer, Viewport},
+    err, util,
+};
+
+use super::{raster::frame_size, rasterize, Raster};
+
+/// Largest side the GPU is asked to draw; bigger images go to the rasterizer.
+const MAX_GPU_SIDE: u32 = 4096;
+
+fn create_canvas(size: PhysicalSize<u32>) -> err::Result<HtmlCanvasElement> {
+    let html_canvas = util::get_document()
+        .ok_or(err::Error::Other("failed to get document".to_string()))?
+        .create_element("canvas")
+        .map_err(util::map_js_error)?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(|_| err::Error::Other("failed to create canvas".to_string()))?;
+    html_canvas.set_width(size.width);
+    html_canvas.set_height(size.height);
+    Ok(html_canvas)
+}
+
+/// Draw through `painting` on a canvas of its own, as `RawCanvas` does on screen.
+///
+/// `painting` clears to its own opaque background, so `background` goes
+/// over it first as a line wide enough to cover the whole canvas.
+async fn render_gpu(
+    group_v: &[(Layer, Vec<Edge>)],
+    frame: &Viewport,
+    background: [f32; 4],
+) -> err::Result<String> {
+    let size = frame_size(frame);
+    if size.width > MAX_GPU_SIDE || size.height > MAX_GPU_SIDE {
+        return Err(err::Error::Other(format!(
+            "{}x{} is too big for the GPU",
+            size.width, size.height
+        )));
+    }
+    let html_canvas = create_canvas(size)?;
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        dx12_shader_compiler: Default::default(),
+    });
+    let surface = instance
+        .create_surface_from_canvas(&html_canvas)
+        .map_err(|e| err::Error::Other(e.to_string()))?;
+    let mut canvas = painting::Canvas::create(&instance, surface, size)
+        .await
+        .map_err(|e| err::Error::Other(e.to_string()))?;
+    canvas.set_aspect(size.width as f32 / size.height as f32);
+
+    let pen = Pen::default();
+    let (w, h) = (size.width as f32, size.height as f32);
+    for (i, x) in [-h, w + h].into_iter().enumerate() {
+        let mut pt = pen.px2point(x, h / 2.0, 1.0, size);
+        pt.pos.z = -1.0;
+        pt.color = background;
+        // Twice the height, in clip space.
+        pt.width = 4.0;
+        if i == 0 {
+            canvas.start_line(pt);
+        } else {
+            canvas.push_point(pt);
+        }
+    }
+    canvas.end_line();
+
+    // `painting` measures widths in clip space, so one unit is half the surface.
+    let width_k = frame.zoom * 2.0 / size.height as f32;
+    for (layer, edge_v) in group_v {
+        for edge in edge_v {
+            for (i, pt) in edge.point_v.iter().enumerate() {
+                let screen = frame.world_to_screen(Point2::new(pt.pos.x, pt.pos.y));
+                let mut device = pt.clone();
+                device.pos = pen.px2point(screen.x, screen.y, 1.0, size).pos;
+                device.pos.z = pt.pos.z;
+                device.width = pt.width * width_k;
+                device.color[3] *= layer.opacity;
+                if i == 0 {
+                    canvas.start_line(device);
+                } else {
+                    canvas.push_point(device);
+                }
+            }
+            canvas.end_line();
+        }
+    }
+    canvas
+        .render()
+        .map_err(|e| err::Error::Other(e.to_string()))?;
+    // Read back in the same task, before the browser drops the drawing buffer.
+    html_canvas
+        .to_data_url_with_type("image/png")
+        .map_err(util::map_js_error)
+}
+
+fn encode(raster: Raster) -> err::Result<String> {
+    let size = PhysicalSize::new(raster.width(), raster.height());
+    let html_canvas = create_canvas(size)?;
+    let context = html_canvas
+        .get_context("2d")
+        .map_err(util::map_js_error)?
+        .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or(err::Error::Other("failed to get 2d context".to_string()))?;
+    let rgba = raster.into_rgba();
+    let image =
+        ImageData::new_with_u8_clamped_array_and_sh(Clamped(&rgba), size.width, size.height)
+            .map_err(util::map_js_error)?;
+    context
+        .put_image_data(&image, 0.0, 0.0)
+        .map_err(util::map_js_error)?;
+    html_canvas
+        .to_data_url_with_type("image/png")
+        .map_err(util::map_js_error)
+}
+
+// Public
+/// A PNG data URL of the layer groups as `frame` sees them.
+///
+/// Drawn on the GPU when there is an opaque background and WebGL works, and
+/// by the software rasterizer otherwise.
+pub async fn to_png_url(
+    group_v: Vec<(Layer, Vec<Edge>)>,
+    frame: Viewport,
+    background: Option<[f32; 4]>,
+) -> err::Result<String> {
+    if let Some(color) = background.filter(|color| color[3] >= 1.0) {
+        match render_gpu(&group_v, &frame, color).await {
+            Ok(url) => return Ok(url),
+            Err(e) => log::warn!("when render png on gpu:\n\t{e}"),
+        }
+    }
+    encode(rasterize(&group_v, &frame, background))
+}
@@ -0,0 +1,256 @@
+use cgmath::Point2;
+use winit::dpi::PhysicalSize;
+
+use crate::element::{Edge, Layer, Viewport};
+
+use super::{color_run_v, outline};
+
+/// Rows sampled per pixel row for anti-aliasing.
+const SUBSAMPLE_N: usize = 4;
+
+fn to_byte(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Pixel size of an image of what `frame` sees.
+pub(super) fn frame_size(frame: &Viewport) -> PhysicalSize<u32> {
+    PhysicalSize::new(frame.size.x.max(1.0) as u32, frame.size.y.max(1.0) as u32)
+}
+
+// Public
+/// Software rasterizer filling polygons into an RGBA image.
+///
+/// Keeps premultiplied RGBA bytes so coverage and alpha compose with
+/// source-over like the GPU does, at four bytes a pixel.
+pub struct Raster {
+    width: usize,
+    height: usize,
+    byte_v: Vec<u8>,
+}
+
+impl Raster {
+    /// A `width` by `height` image, transparent if there is no `background`.
+    pub fn new(width: u32, height: u32, background: Option<[f32; 4]>) -> Self {
+        let fill = match background {
+            Some([r, g, b, a]) => [r * a, g * a, b * a, a].map(to_byte),
+            None => [0; 4],
+        };
+        Self {
+            width: width as usize,
+            height: height as usize,
+            byte_v: fill.repeat(width as usize * height as usize),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height as u32
+    }
+
+    /// Fill `polygon`, given in pixels, with the nonzero rule.
+    pub fn fill(&mut self, polygon: &[Point2<f32>], color: [f32; 4]) {
+        if polygon.len() < 3 || color[3] <= 0.0 {
+            return;
+        }
+        let (top, bottom) = polygon
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(top, bottom), pt| {
+                (top.min(pt.y), bottom.max(pt.y))
+            });
+        let first_row = top.floor().max(0.0) as usize;
+        let last_row = (bottom.ceil().max(0.0) as usize).min(self.height);
+
+        let mut coverage_v = vec![0.0f32; self.width + 1];
+        let mut crossing_v: Vec<(f32, i32)> = Vec::new();
+        for row in first_row..last_row {
+            coverage_v.iter_mut().for_each(|c| *c = 0.0);
+            for sub in 0..SUBSAMPLE_N {
+                let y = row as f32 + (sub as f32 + 0.5) / SUBSAMPLE_N as f32;
+                crossing_v.clear();
+                for (i, a) in polygon.iter().enumerate() {
+                    let b = &polygon[(i + 1) % polygon.len()];
+                    if (a.y <= y) == (b.y <= y) {
+                        continue;
+                    }
+                    let x = a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x);
+                    crossing_v.push((x, if b.y > a.y { 1 } else { -1 }));
+                }
+                crossing_v.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                for pair in crossing_v.windows(2) {
+                    winding += pair[0].1;
+                    if winding != 0 {
+                        self.cover(&mut coverage_v, pair[0].0, pair[1].0);
+                    }
+                }
+            }
+            self.blend_row(row, &coverage_v, color);
+        }
+    }
+
+    /// Straight RGBA bytes, row by row from the top.
+    pub fn into_rgba(mut self) -> Vec<u8> {
+        for pixel in self.byte_v.chunks_exact_mut(4) {
+            let a = pixel[3];
+            for c in &mut pixel[..3] {
+                *c = match a {
+                    0 => 0,
+                    _ => ((*c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8,
+                };
+            }
+        }
+        self.byte_v
+    }
+
+    /// Add the part of each pixel that `[x0, x1)` covers on one sample row.
+    fn cover(&self, coverage_v: &mut [f32], x0: f32, x1: f32) {
+        let x0 = x0.clamp(0.0, self.width as f32);
+        let x1 = x1.clamp(0.0, self.width as f32);
+        if x1 <= x0 {
+            return;
+        }
+        let (i0, i1) = (x0 as usize, x1 as usize);
+        if i0 == i1 {
+            coverage_v[i0] += x1 - x0;
+            return;
+        }
+        coverage_v[i0] += (i0 + 1) as f32 - x0;
+        for c in &mut coverage_v[i0 + 1..i1] {
+            *c += 1.0;
+        }
+        coverage_v[i1] += x1 - i1 as f32;
+    }
+
+    fn blend_row(&mut self, row: usize, coverage_v: &[f32], [r, g, b, a]: [f32; 4]) {
+        let row_v = &mut self.byte_v[row * self.width * 4..(row + 1) * self.width * 4];
+        for (pixel, coverage) in row_v.chunks_exact_mut(4).zip(coverage_v) {
+            let alpha = a * (coverage / SUBSAMPLE_N as f32).min(1.0);
+            if alpha <= 0.0 {
+                continue;
+            }
+            let keep = 1.0 - alpha;
+            for (c, source) in pixel.iter_mut().zip([r, g, b, 1.0]) {
+                *c = to_byte(source * alpha + *c as f32 / 255.0 * keep);
+            }
+        }
+    }
+}
+
+/// Draw the layer groups as `frame` sees them with the software rasterizer.
+pub fn rasterize(
+    group_v: &[(Layer, Vec<Edge>)],
+    frame: &Viewport,
+    background: Option<[f32; 4]>,
+) -> Raster {
+    let size = frame_size(frame);
+    let mut raster = Raster::new(size.width, size.height, background);
+    let visible = frame.world_rect();
+    for (layer, edge_v) in group_v {
+        for edge in edge_v {
+            let reach = edge.point_v.iter().map(|pt| pt.width).fold(0.0, f32::max);
+            match edge.bounds() {
+                Some(rect) if rect.inflate(reach).intersects(&visible) => (),
+                _ => continue,
+            }
+            for (run, mut color) in color_run_v(&edge.point_v) {
+                let polygon: Vec<Point2<f32>> = outline(run)
+                    .into_iter()
+                    .map(|pt| frame.world_to_screen(pt))
+                    .collect();
+                color[3] *= layer.opacity;
+                raster.fill(&polygon, color);
+            }
+        }
+    }
+    raster
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{Point3, Vector2};
+    use painting::point::Point;
+
+    use super::*;
+    use crate::element::Stylus;
+
+    const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+    fn pixel(rgba: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * width + x) * 4) as usize;
+        rgba[i..i + 4].try_into().unwrap()
+    }
+
+    fn square(x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<Point2<f32>> {
+        vec![
+            Point2::new(x0, y0),
+            Point2::new(x1, y0),
+            Point2::new(x1, y1),
+            Point2::new(x0, y1),
+        ]
+    }
+
+    #[test]
+    fn fills_whole_pixels() {
+        let mut raster = Raster::new(4, 4, Some(WHITE));
+        raster.fill(&square(1.0, 1.0, 3.0, 3.0), [1.0, 0.0, 0.0, 1.0]);
+        let rgba = raster.into_rgba();
+        assert_eq!(pixel(&rgba, 4, 0, 0), [255, 255, 255, 255]);
+        assert_eq!(pixel(&rgba, 4, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&rgba, 4, 2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&rgba, 4, 3, 3), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn covers_part_of_a_pixel() {
+        let mut raster = Raster::new(2, 1, None);
+        raster.fill(&square(0.0, 0.0, 0.5, 1.0), [0.0, 0.0, 1.0, 1.0]);
+        let rgba = raster.into_rgba();
+        assert_eq!(pixel(&rgba, 2, 0, 0), [0, 0, 255, 128]);
+        assert_eq!(pixel(&rgba, 2, 1, 0), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn blends_over_the_background() {
+        let mut raster = Raster::new(1, 1, Some(WHITE));
+        raster.fill(&square(0.0, 0.0, 1.0, 1.0), [0.0, 0.0, 0.0, 0.5]);
+        assert_eq!(raster.into_rgba(), vec![128, 128, 128, 255]);
+    }
+
+    #[test]
+    fn draws_edges_through_the_frame() {
+        let layer = Layer {
+            opacity: 0.5,
+            ..Layer::default()
+        };
+        let edge = Edge {
+            point_v: [(-10.0, 0.0), (10.0, 0.0)]
+                .into_iter()
+                .map(|(x, y)| Point {
+                    pos: Point3::new(x, y, 0.0),
+                    color: [0.0, 0.0, 0.0, 1.0],
+                    width: 4.0,
+                })
+                .collect(),
+            stylus_v: vec![Stylus::default(); 2],
+            ..Edge::default()
+        };
+        let frame = Viewport {
+            center: Point2::new(0.0, 0.0),
+            zoom: 2.0,
+            rotation: 0.0,
+            size: Vector2::new(16.0, 16.0),
+        };
+        let raster = rasterize(&[(layer, vec![edge])], &frame, Some(WHITE));
+        assert_eq!((raster.width(), raster.height()), (16, 16));
+        let rgba = raster.into_rgba();
+        // 4 wide at twice the zoom covers rows 4 to 12 across the frame.
+        assert_eq!(pixel(&rgba, 16, 0, 8), [128, 128, 128, 255]);
+        assert_eq!(pixel(&rgba, 16, 15, 5), [128, 128, 128, 255]);
+        assert_eq!(pixel(&rgba, 16, 8, 3), [255, 255, 255, 255]);
+        assert_eq!(pixel(&rgba, 16, 8, 12), [255, 255, 255, 255]);
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt::Write;
+
+use crate::element::{Edge, Layer, Viewport};
+
+use super::{color_run_v, escape, outline, rgb};
+
+// Public
+/// An SVG document of the layer groups as `frame` sees them.
+///
+/// Every edge becomes a filled path around its outline, so width changes
+/// survive, and every layer a `<g>` carrying its name and opacity.
+pub fn to_svg(
+    group_v: &[(Layer, Vec<Edge>)],
+    frame: &Viewport,
+    background: Option<[f32; 4]>,
+) -> String {
+    let (width, height) = (frame.size.x.round(), frame.size.y.round());
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    svg.push('\n');
+    if let Some(color) = background {
+        let _ = writeln!(
+            svg,
+            r#"<rect width="100%" height="100%" fill="{}" fill-opacity="{}"/>"#,
+            rgb(color),
+            color[3]
+        );
+    }
+    for (layer, edge_v) in group_v {
+        let _ = writeln!(
+            svg,
+            r#"<g id="layer-{}" data-name="{}" opacity="{}">"#,
+            escape(&layer.id),
+            escape(&layer.name),
+            layer.opacity
+        );
+        for edge in edge_v {
+            // An edge that changes color takes a path for every color.
+            for (n, (run, color)) in color_run_v(&edge.point_v).into_iter().enumerate() {
+                let polygon = outline(run);
+                if polygon.is_empty() {
+                    continue;
+                }
+                let mut d = String::new();
+                for (i, pt) in polygon.iter().enumerate() {
+                    let pt = frame.world_to_screen(*pt);
+                    let _ = write!(
+                        d,
+                        "{}{:.2} {:.2} ",
+                        if i == 0 { "M" } else { "L" },
+                        pt.x,
+                        pt.y
+                    );
+                }
+                d.push('Z');
+                let id = match n {
+                    0 => escape(&edge.id),
+                    _ => format!("{}-{n}", escape(&edge.id)),
+                };
+                let _ = writeln!(
+                    svg,
+                    r#"<path id="edge-{id}" d="{d}" fill="{}" fill-opacity="{}"/>"#,
+                    rgb(color),
+                    color[3]
+                );
+            }
+        }
+        svg.push_str("</g>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod test {
+    use cgmath::{Point2, Point3, Vector2};
+    use painting::point::Point;
+
+    use crate::{
+        element::{Edge, Layer, Stylus, Viewport},
+        export::{layer_group_v, to_svg},
+    };
+
+    fn edge(id: &str, layer_id: &str, pos_v: &[(f32, f32)], color: [f32; 4]) -> Edge {
+        Edge {
+            id: id.to_string(),
+            layer_id: layer_id.to_string(),
+            point_v: pos_v
+                .iter()
+                .map(|&(x, y)| Point {
+                    pos: Point3::new(x, y, 0.0),
+                    color,
+                    width: 5.4,
+                })
+                .collect(),
+            stylus_v: vec![Stylus::default(); pos_v.len()],
+        }
+    }
+
+    fn layer(id: &str, name: &str, opacity: f32) -> Layer {
+        Layer {
+            id: id.to_string(),
+            name: name.to_string(),
+            visible: true,
+            locked: false,
+            opacity,
+        }
+    }
+
+    fn frame() -> Viewport {
+        Viewport {
+            center: Point2::new(50.0, 50.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            size: Vector2::new(120.0, 100.0),
+        }
+    }
+
+    /// Compare `svg` with `testdata/<name>`, or write it there if
+    /// `UPDATE_GOLDEN` is set.
+    fn check_golden(name: &str, svg: &str) {
+        let path = format!("{}/src/export/testdata/{name}", env!("CARGO_MANIFEST_DIR"));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, svg).unwrap();
+            return;
+        }
+        let golden = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(svg, golden, "{name} differs from its golden file");
+    }
+
+    #[test]
+    fn layers_golden() {
+        let layer_v = [
+            layer("default", "Layer 1", 1.0),
+            layer("0123456789abcdef", "Ink & <notes>", 0.5),
+        ];
+        let edge_v = [
+            edge(
+                "e1",
+                "default",
+                &[(10.0, 10.0), (90.0, 20.0)],
+                [0.0, 0.0, 0.0, 1.0],
+            ),
+            edge(
+                "e2",
+                "0123456789abcdef",
+                &[(20.0, 80.0), (50.0, 50.0), (80.0, 80.0)],
+                [1.0, 0.0, 0.0, 0.8],
+            ),
+            edge("e3", "gone", &[(50.0, 50.0)], [0.0, 0.0, 1.0, 1.0]),
+        ];
+        let group_v = layer_group_v(&edge_v, &layer_v);
+        let svg = to_svg(&group_v, &frame(), Some([1.0, 1.0, 1.0, 1.0]));
+        check_golden("layers.svg", &svg);
+    }
+
+    #[test]
+    fn transparent_golden() {
+        let layer_v = [layer("default", "Layer 1", 1.0)];
+        let edge_v = [edge(
+            "e1",
+            "default",
+            &[(0.0, 0.0), (100.0, 100.0)],
+            [0.2, 0.4, 0.6, 1.0],
+        )];
+        let group_v = layer_group_v(&edge_v, &layer_v);
+        let svg = to_svg(&group_v, &frame(), None);
+        check_golden("transparent.svg", &svg);
+    }
+
+    #[test]
+    fn other_layer_is_its_own() {
+        let layer_v = [layer("default", "Layer 1", 1.0)];
+        let edge_v = [
+            edge("e1", "default", &[(0.0, 0.0)], [0.0; 4]),
+            edge("e2", "gone", &[(0.0, 0.0)], [0.0; 4]),
+        ];
+        let group_v = layer_group_v(&edge_v, &layer_v);
+        let svg = to_svg(&group_v, &frame(), None);
+        assert_eq!(svg.matches(r#"id="layer-default""#).count(), 1);
+        assert_eq!(svg.matches(r#"id="layer-other""#).count(), 1);
+    }
+}
@@ -1,9 +1,18 @@
-mod service;
+// Off the browser only the parts that don't need one are built, for tests;
+// what the app alone uses goes unused there.
+#![cfg_attr(not(target_arch = "wasm32"), allow(dead_code, unused_imports))]
+
+mod export;
 mod router;
+mod service;
 
 // Public
-pub mod err;
+#[cfg(target_arch = "wasm32")]
 pub mod app;
-pub mod util;
 pub mod component;
 pub mod element;
+pub mod err;
+pub mod metrics;
+#[cfg(target_arch = "wasm32")]
+pub mod render_worker;
+pub mod util;
@@ -1,6 +1,13 @@
-use huiwen::app;
+#[cfg(target_arch = "wasm32")]
+use huiwen::{app, metrics};
 
+#[cfg(target_arch = "wasm32")]
 fn main() {
     let _ = console_log::init_with_level(log::Level::Info);
+    metrics::expose();
     yew::Renderer::<app::Main>::new().render();
 }
+
+/// The app runs in the browser only.
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {}
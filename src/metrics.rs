@@ -0,0 +1,149 @@
+//! How the board performs, for the performance HUD and for benchmarks.
+//!
+//! The canvas and the service write here as they work; `snapshot` reads it
+//! back. `expose` also lets a script driving the page read it as
+//! `window.huiwenMetrics()`, which returns a plain object.
+
+use std::{cell::RefCell, collections::VecDeque};
+
+use serde::Serialize;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+#[cfg(target_arch = "wasm32")]
+use web_sys::Performance;
+
+/// Milliseconds over which frames are counted for `fps`.
+const FPS_WINDOW: f64 = 1000.0;
+/// Name of the function `expose` puts on `window`.
+const API_NAME: &str = "huiwenMetrics";
+
+thread_local! {
+    static METRICS: RefCell<Metrics> = RefCell::new(Metrics::default());
+    /// When the frames of the last `FPS_WINDOW` ended.
+    static FRAME_END_V: RefCell<VecDeque<f64>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// The clock of the page or of the worker, whichever this runs in.
+#[cfg(target_arch = "wasm32")]
+fn performance() -> Option<Performance> {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance"))
+        .ok()?
+        .dyn_into()
+        .ok()
+}
+
+/// Drop from `end_v` the frames that ended before the window reaching back
+/// from `now`, and count those left.
+fn frame_n_at(end_v: &mut VecDeque<f64>, now: f64) -> usize {
+    while end_v.front().is_some_and(|t| *t < now - FPS_WINDOW) {
+        end_v.pop_front();
+    }
+    end_v.len()
+}
+
+// Public
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Metrics {
+    /// Frames rendered over the last second, read when the snapshot is taken.
+    pub fps: f32,
+    /// Milliseconds the last frame took, redrawing included.
+    pub frame_ms: f32,
+    /// Edges on the GPU.
+    pub edge_n: usize,
+    /// Points on the GPU, the paper and guides included.
+    pub point_n: usize,
+    /// Bytes of points handed to `painting` in the last frame.
+    pub upload_bytes: usize,
+    /// Milliseconds the last `pull_edge_v` took, and the part of it spent
+    /// decoding the answer.
+    pub pull_ms: f32,
+    pub decode_ms: f32,
+    /// Commits and erasures not yet seen in a pull.
+    pub sync_n: usize,
+}
+
+/// Milliseconds since the page loaded, or the worker started when called
+/// in one.
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> f64 {
+    performance().map_or_else(js_sys::Date::now, |performance| performance.now())
+}
+
+/// Milliseconds since the epoch, off the browser.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+}
+
+/// When `now` counts from, in milliseconds since the epoch.
+#[cfg(target_arch = "wasm32")]
+pub fn time_origin() -> f64 {
+    performance().map_or(0.0, |performance| performance.time_origin())
+}
+
+/// When `now` counts from: the epoch, off the browser.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn time_origin() -> f64 {
+    0.0
+}
+
+/// The numbers as of now. `fps` is counted when read, so it falls to zero
+/// once nothing has been drawn for `FPS_WINDOW`.
+pub fn snapshot() -> Metrics {
+    let frame_n = FRAME_END_V.with(|end_v| frame_n_at(&mut end_v.borrow_mut(), now()));
+    METRICS.with(|metrics| {
+        let mut metrics = metrics.borrow_mut();
+        metrics.fps = frame_n as f32;
+        *metrics
+    })
+}
+
+pub fn update(f: impl FnOnce(&mut Metrics)) {
+    METRICS.with(|metrics| f(&mut metrics.borrow_mut()));
+}
+
+/// Count a frame that started at `start` and just ended.
+pub fn record_frame(start: f64) {
+    let end = now();
+    let frame_n = FRAME_END_V.with(|end_v| {
+        let mut end_v = end_v.borrow_mut();
+        end_v.push_back(end);
+        frame_n_at(&mut end_v, end)
+    });
+    update(|metrics| {
+        metrics.fps = frame_n as f32;
+        metrics.frame_ms = (end - start) as f32;
+    });
+}
+
+/// Put `window.huiwenMetrics()` on the page.
+pub fn expose() {
+    let window = match web_sys::window() {
+        Some(window) => window,
+        None => return,
+    };
+    let api = Closure::<dyn Fn() -> JsValue>::new(|| {
+        serde_json::to_string(&snapshot())
+            .ok()
+            .and_then(|s| js_sys::JSON::parse(&s).ok())
+            .unwrap_or(JsValue::NULL)
+    });
+    let _ = js_sys::Reflect::set(&window, &JsValue::from_str(API_NAME), api.as_ref());
+    // Lives as long as the page.
+    api.forget();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_leave_the_window() {
+        let mut end_v: VecDeque<f64> = [0.0, 400.0, 900.0].into();
+        assert_eq!(frame_n_at(&mut end_v, 1000.0), 3);
+        assert_eq!(frame_n_at(&mut end_v, 1200.0), 2);
+        assert_eq!(frame_n_at(&mut end_v, 1950.0), 0);
+        assert!(end_v.is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+//! Drawing in a worker, off the main thread.
+//!
+//! The page hands the worker an `OffscreenCanvas` and the `time_origin` of
+//! its clock. The worker answers once, with `true` when `painting` is ready
+//! on the canvas or with a string telling why it isn't. From then on the page
+//! sends `ToWorker` messages and the worker runs the `Scene` on them,
+//! answering with what the scene tells, all as JSON.
+
+use std::{cell::RefCell, rc::Rc};
+
+use cgmath::Vector2;
+use js_sys::Reflect;
+use painting::AsCanvas;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent, OffscreenCanvas};
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    element::{CountedCanvas, Delta, FrameScheduler, InputEvent, Scene},
+    metrics,
+};
+
+/// `painting` on the GPU behind `offscreen`.
+async fn create(offscreen: &OffscreenCanvas) -> Result<painting::Canvas, String> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        dx12_shader_compiler: Default::default(),
+    });
+    let surface = instance
+        .create_surface_from_offscreen_canvas(offscreen)
+        .map_err(|e| format!("failed to create surface: {e}"))?;
+    let size = PhysicalSize::new(offscreen.width(), offscreen.height());
+    painting::Canvas::create(&instance, surface, size)
+        .await
+        .map_err(|e| format!("failed to set up the GPU: {e}"))
+}
+
+/// The scene and what it draws on.
+struct Host {
+    scope: DedicatedWorkerGlobalScope,
+    offscreen: OffscreenCanvas,
+    canvas: CountedCanvas,
+    scene: Scene,
+    frames: FrameScheduler,
+    /// Milliseconds to add to the worker's clock to read the page's.
+    shift: f64,
+}
+
+impl Host {
+    fn handle(&mut self, msg: ToWorker) {
+        match msg {
+            ToWorker::Resize {
+                width,
+                height,
+                client,
+            } => {
+                let size = PhysicalSize::new(width, height);
+                if size != *self.canvas.get_size() {
+                    self.offscreen.set_width(width);
+                    self.offscreen.set_height(height);
+                    self.canvas.resize(size);
+                }
+                self.canvas
+                    .set_aspect(client.x.max(1.0) / client.y.max(1.0));
+                self.scene.apply(&mut self.canvas, Delta::Size(client));
+            }
+            ToWorker::Input(input) => self.scene.input(&mut self.canvas, input),
+            ToWorker::Delta(delta) => self.scene.apply(&mut self.canvas, delta),
+        }
+        self.flush();
+    }
+
+    fn frame(&mut self) {
+        self.scene
+            .frame(&mut self.canvas, metrics::now() + self.shift);
+        self.flush();
+    }
+
+    /// Tell the page what the scene told, and ask for a frame if there is
+    /// something to render.
+    fn flush(&mut self) {
+        let out_v = self.scene.take_out_v();
+        if !out_v.is_empty() {
+            match serde_json::to_string(&out_v) {
+                Ok(s) => {
+                    let _ = self.scope.post_message(&JsValue::from_str(&s));
+                }
+                Err(e) => log::error!("failed to encode a message for the page: {e}"),
+            }
+        }
+        if self.scene.wants_frame() {
+            self.frames.request();
+        }
+    }
+}
+
+// Public
+/// What the page sends the worker once it is ready.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ToWorker {
+    /// The canvas was laid out at `client` CSS pixels, to be drawn at
+    /// `width` by `height` device pixels.
+    Resize {
+        width: u32,
+        height: u32,
+        client: Vector2<f32>,
+    },
+    Input(InputEvent),
+    Delta(Delta),
+}
+
+/// Entry of the worker: wait for the canvas, then run the scene on what comes.
+pub fn run() {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let state: Rc<RefCell<Option<Host>>> = Rc::default();
+    let reply = scope.clone();
+    let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |e: MessageEvent| {
+        let data = e.data();
+        if let Some(s) = data.as_string() {
+            match serde_json::from_str::<ToWorker>(&s) {
+                Ok(msg) => {
+                    if let Some(host) = state.borrow_mut().as_mut() {
+                        host.handle(msg);
+                    }
+                }
+                Err(e) => log::error!("unexpected message: {e}"),
+            }
+            return;
+        }
+        let offscreen = match Reflect::get(&data, &JsValue::from_str("canvas"))
+            .ok()
+            .and_then(|canvas| canvas.dyn_into::<OffscreenCanvas>().ok())
+        {
+            Some(offscreen) => offscreen,
+            None => {
+                log::error!("unexpected message: {data:?}");
+                return;
+            }
+        };
+        let origin = Reflect::get(&data, &JsValue::from_str("origin"))
+            .ok()
+            .and_then(|origin| origin.as_f64())
+            .unwrap_or_else(metrics::time_origin);
+        let state = state.clone();
+        let reply = reply.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let answer = match create(&offscreen).await {
+                Ok(canvas) => {
+                    let weak = Rc::downgrade(&state);
+                    let frames = FrameScheduler::new(move || {
+                        if let Some(state) = weak.upgrade() {
+                            if let Some(host) = state.borrow_mut().as_mut() {
+                                host.frame();
+                            }
+                        }
+                    });
+                    *state.borrow_mut() = Some(Host {
+                        scope: reply.clone(),
+                        offscreen,
+                        canvas: CountedCanvas::new(Box::new(canvas)),
+                        scene: Scene::new(),
+                        frames,
+                        shift: metrics::time_origin() - origin,
+                    });
+                    JsValue::TRUE
+                }
+                Err(e) => JsValue::from_str(&e),
+            };
+            let _ = reply.post_message(&answer);
+        });
+    });
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // The worker lives as long as its page wants it.
+    on_message.forget();
+}
@@ -1,7 +1,65 @@
+use cgmath::Point2;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::*;
+use crate::{element::Viewport, *};
+
+/// Query keys holding the view, the angle being in degrees.
+const VIEW_KEY_V: [&str; 4] = ["x", "y", "zoom", "angle"];
+/// Query key holding the id of the board.
+const BOARD_KEY: &str = "board";
+
+fn query_pair_v(search: &str) -> Vec<(String, String)> {
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((k, v)) => (k.to_string(), v.to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// The view kept in `search`, None if it has none.
+fn parse_view(search: &str) -> Option<Viewport> {
+    let mut viewport = Viewport::default();
+    let mut found = false;
+    for (k, v) in query_pair_v(search) {
+        let v = match v.parse::<f32>() {
+            Ok(v) if v.is_finite() => v,
+            _ => continue,
+        };
+        match k.as_str() {
+            "x" => viewport.center.x = v,
+            "y" => viewport.center.y = v,
+            "zoom" if v > 0.0 => viewport.zoom = v,
+            "angle" => viewport.rotation = v.to_radians(),
+            _ => continue,
+        }
+        found = true;
+    }
+    if found {
+        Some(viewport)
+    } else {
+        None
+    }
+}
+
+/// Query pairs keeping `board` and `viewport`. Zoom keeps every digit, as
+/// far out it is too small for a fixed number of decimals.
+fn place_pair_v(board: Option<&str>, viewport: &Viewport) -> Vec<(String, String)> {
+    let Point2 { x, y } = viewport.center;
+    let mut pair_v = Vec::new();
+    if let Some(board) = board {
+        pair_v.push((BOARD_KEY.to_string(), board.to_string()));
+    }
+    pair_v.push(("x".to_string(), format!("{}", x.round())));
+    pair_v.push(("y".to_string(), format!("{}", y.round())));
+    pair_v.push(("zoom".to_string(), format!("{}", viewport.zoom)));
+    pair_v.push(("angle".to_string(), format!("{}", viewport.degrees())));
+    pair_v
+}
 
 #[derive(Clone, Routable, PartialEq)]
 enum Route {
@@ -19,8 +77,74 @@ pub struct Props {
 }
 
 // Public
+/// The view in the URL of this page, if any.
+pub fn read_view() -> Option<Viewport> {
+    let search = util::get_location()?.search().ok()?;
+    parse_view(&search)
+}
+
+/// The board in the URL of this page, None for the default one or one
+/// whose id can't be.
+pub fn read_board() -> Option<String> {
+    let board = query_value(BOARD_KEY)?;
+    if service::is_board_id(&board) {
+        Some(board)
+    } else {
+        log::warn!("ignoring board id {board:?}");
+        None
+    }
+}
+
+/// Value of query key `key` in the URL of this page, as in `?name=Ann`.
+pub fn query_value(key: &str) -> Option<String> {
+    let search = util::get_location()?.search().ok()?;
+    let (_, value) = query_pair_v(&search).into_iter().find(|(k, _)| k == key)?;
+    js_sys::decode_uri_component(&value).ok().map(String::from)
+}
+
+/// True if the URL of this page has query key `key`, as in `?hud`.
+pub fn has_flag(key: &str) -> bool {
+    util::get_location()
+        .and_then(|location| location.search().ok())
+        .is_some_and(|search| query_pair_v(&search).iter().any(|(k, _)| k == key))
+}
+
+/// Keep `board` and `viewport` in the URL of this page, without adding to
+/// the history. Other query keys and the fragment stay.
+pub fn replace_view(board: Option<&str>, viewport: &Viewport) -> err::Result<()> {
+    let window = web_sys::window().ok_or(err::Error::Other("window not found".to_string()))?;
+    let location = window.location();
+    let path = location.pathname().map_err(util::map_js_error)?;
+    let search = location.search().map_err(util::map_js_error)?;
+    let hash = location.hash().map_err(util::map_js_error)?;
+
+    let mut pair_v = place_pair_v(board, viewport);
+    pair_v.extend(
+        query_pair_v(&search)
+            .into_iter()
+            .filter(|(k, _)| k != BOARD_KEY && !VIEW_KEY_V.contains(&k.as_str())),
+    );
+    let query = pair_v
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<String>>()
+        .join("&");
+
+    window
+        .history()
+        .map_err(util::map_js_error)?
+        .replace_state_with_url(
+            &wasm_bindgen::JsValue::NULL,
+            "",
+            Some(&format!("{path}?{query}{hash}")),
+        )
+        .map_err(util::map_js_error)
+}
+
+#[cfg(target_arch = "wasm32")]
 pub struct Router {}
 
+#[cfg(target_arch = "wasm32")]
 impl yew::Component for Router {
     type Message = ();
     type Properties = Props;
@@ -32,7 +156,9 @@ impl yew::Component for Router {
     fn view(&self, ctx: &yew::prelude::Context<Self>) -> Html {
         let on_error = ctx.props().on_error.clone();
         let switch = Callback::from(move |route: Route| match route {
-            Route::Home => html! { <app::HomePage on_error={on_error.clone()} /> },
+            Route::Home => html! {
+                <app::HomePage on_error={on_error.clone()} board={read_board()} view={read_view()} />
+            },
             Route::NotFound => html! {
                 <div>{"404"}</div>
             },
@@ -45,3 +171,40 @@ impl yew::Component for Router {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use cgmath::Point2;
+
+    use super::{parse_view, place_pair_v, query_pair_v, Viewport, BOARD_KEY};
+
+    fn search(pair_v: &[(String, String)]) -> String {
+        let query: Vec<String> = pair_v.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        format!("?{}", query.join("&"))
+    }
+
+    #[test]
+    fn view_round_trip() {
+        for zoom in [0.0001, 0.1234567, 1.0, 8.0] {
+            let viewport = Viewport {
+                center: Point2::new(-120.0, 3400.0),
+                zoom,
+                rotation: 90f32.to_radians(),
+                ..Viewport::default()
+            };
+            let back = parse_view(&search(&place_pair_v(None, &viewport))).unwrap();
+            assert_eq!(back.center, viewport.center);
+            assert_eq!(back.zoom, zoom);
+            assert!((back.rotation - viewport.rotation).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn board_kept() {
+        let pair_v = place_pair_v(Some("team_1"), &Viewport::default());
+        let found = query_pair_v(&search(&pair_v))
+            .into_iter()
+            .find(|(k, _)| k == BOARD_KEY);
+        assert_eq!(found, Some((BOARD_KEY.to_string(), "team_1".to_string())));
+    }
+}
@@ -1,12 +1,40 @@
-use cgmath::Point3;
-use painting::point::Point;
+mod presence;
+#[cfg(target_arch = "wasm32")]
+mod remote;
+
+use cgmath::{Point2, Point3};
+use painting::point::{Pen, Point};
 use serde::{Deserialize, Serialize};
-use wasm_bindgen_futures::JsFuture;
+use winit::dpi::PhysicalSize;
+
+use crate::element::{Rect, Stylus};
+
+/// How the points of a board are stored, kept at `huiwen->canvas->coord`.
+///
+/// Boards without one keep `painting` clip space; since 1 positions and
+/// widths are world pixels.
+const COORD_VERSION: u64 = 1;
 
-use crate::{
-    err,
-    util::{self, Request},
-};
+/// Screen that boards without a coordinate version are taken to be drawn on.
+const LEGACY_CLIENT: PhysicalSize<u32> = PhysicalSize::new(1920, 1080);
+
+/// `pt` of a board without a coordinate version in world pixels, with the
+/// middle of `LEGACY_CLIENT` at the origin.
+fn legacy_to_world(pt: &Point) -> Point {
+    let (w, h) = (LEGACY_CLIENT.width as f32, LEGACY_CLIENT.height as f32);
+    // Where `px2point` puts the corners tells where screen pixels land.
+    let pen = Pen::default();
+    let origin = pen.px2point(0.0, 0.0, 1.0, LEGACY_CLIENT).pos;
+    let far = pen.px2point(w, h, 1.0, LEGACY_CLIENT).pos;
+    let k_x = w / (far.x - origin.x);
+    let k_y = h / (far.y - origin.y);
+    let mut world = pt.clone();
+    world.pos.x = (pt.pos.x - origin.x) * k_x - w / 2.0;
+    world.pos.y = (pt.pos.y - origin.y) * k_y - h / 2.0;
+    // Widths are in clip space too, so they scale like heights.
+    world.width = pt.width * k_y.abs();
+    world
+}
 
 fn p3_to_str(pt: &Point3<f32>) -> String {
     format!("{},{},{}", pt.x, pt.y, pt.z)
@@ -30,32 +58,119 @@ fn str_to_c4(s: &str) -> [f32; 4] {
     [c4[0], c4[1], c4[2], c4[3]]
 }
 
-async fn execute(script_tree: &ScriptTree) -> err::Result<json::JsonValue> {
-    let res = Request::new("/service/edge/execute1")
-        .with_body_str(&serde_json::to_string(script_tree).unwrap())?
-        .send("POST")
-        .await?;
-    let rs = JsFuture::from(res.text().map_err(util::map_js_error)?)
-        .await
-        .map_err(util::map_js_error)?
-        .as_string()
-        .ok_or(err::Error::Other(
-            "when execute:\n\treturned none".to_string(),
-        ))?;
-    match res.status() {
-        401 => {
-            return Err(err::Error::NotLogin(format!("not login")));
+fn stylus_to_str(stylus: &Stylus) -> String {
+    format!(
+        "{},{},{},{},{},{}",
+        stylus.tilt_x,
+        stylus.tilt_y,
+        stylus.twist,
+        stylus.barrel as u8,
+        stylus.eraser as u8,
+        stylus.time
+    )
+}
+
+fn rect_to_str(rect: &Rect) -> String {
+    format!(
+        "{},{},{},{}",
+        rect.min.x, rect.min.y, rect.max.x, rect.max.y
+    )
+}
+
+fn str_to_rect(s: &str) -> Option<Rect> {
+    let v: Vec<f32> = s
+        .split(',')
+        .map(|s| s.parse().ok())
+        .collect::<Option<_>>()?;
+    match v[..] {
+        [x0, y0, x1, y1] => Some(Rect::from_corners(Point2::new(x0, y0), Point2::new(x1, y1))),
+        _ => None,
+    }
+}
+
+fn str_to_stylus(s: &str) -> Stylus {
+    let v: Vec<f32> = s.split(',').map(|s| s.parse::<f32>().unwrap()).collect();
+    Stylus {
+        tilt_x: v[0],
+        tilt_y: v[1],
+        twist: v[2],
+        barrel: v[3] != 0.0,
+        eraser: v[4] != 0.0,
+        // Missing on points stored before times were.
+        time: v.get(5).copied().unwrap_or(0.0),
+    }
+}
+
+/// Id of an edge committed before ids existed, made from its points so that
+/// it stays the same from pull to pull. `seen_n` tells apart identical edges.
+fn legacy_id<'a>(pos_h_v: impl Iterator<Item = &'a str>, seen_n: usize) -> String {
+    // FNV-1a.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in pos_h_v.flat_map(|pos_h| pos_h.bytes().chain([b';'])) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash.wrapping_add(seen_n as u64))
+}
+
+/// Names may hold spaces, which the script language splits on.
+fn encode_name(name: &str) -> String {
+    String::from(js_sys::encode_uri_component(name))
+}
+
+/// `id` with anything but letters, digits and `_` percent-encoded, so that
+/// no id can end a path or a statement of a script.
+fn encode_id(id: &str) -> String {
+    let mut s = String::with_capacity(id.len());
+    for b in id.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'_' {
+            s.push(b as char);
+        } else {
+            s.push_str(&format!("%{b:02X}"));
         }
-        500 => {
-            log::warn!("when execute:\n\t{rs}");
-            return Err(err::Error::Other(rs));
+    }
+    s
+}
+
+fn decode_id(s: &str) -> String {
+    let mut byte_v = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        let hex = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (b, hex) {
+            (b'%', Some(decoded)) => {
+                byte_v.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                byte_v.push(b);
+                rest = tail;
+            }
         }
-        _ => (),
     }
-    json::parse(&rs).map_err(|_| err::Error::Other(rs))
+    String::from_utf8_lossy(&byte_v).into_owned()
+}
+
+fn decode_name(s: &str) -> String {
+    match js_sys::decode_uri_component(s) {
+        Ok(name) => String::from(name),
+        Err(_) => s.to_string(),
+    }
 }
 
 // Public
+pub use presence::*;
+#[cfg(target_arch = "wasm32")]
+pub use remote::*;
+
+/// True if `id` can name a board: letters, digits and `_`, 64 at most.
+pub fn is_board_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScriptTree {
     pub script: String,
@@ -63,106 +178,40 @@ pub struct ScriptTree {
     pub next_v: Vec<ScriptTree>,
 }
 
-pub async fn get_version() -> err::Result<String> {
-    let rs = execute(&ScriptTree {
-        script: "$->$output = = huiwen->version _".to_string(),
-        name: format!("version"),
-        next_v: vec![],
-    })
-    .await?;
-    Ok(rs["version"][0].as_str().unwrap().to_string())
-}
-
-pub async fn commit_edge(edge: Vec<Point>) -> err::Result<()> {
-    let mut script = format!("$->$edge = = ? _");
-
-    for pt in &edge {
-        script = format!(
-            r#"{script}
-$->$point = = ? _
-$->$point->pos = = {} _
-$->$point->color = = {} _
-$->$point->width = = {} _
-$->$edge->point += = $->$point _"#,
-            p3_to_str(&pt.pos),
-            c4_to_str(&pt.color),
-            pt.width
-        );
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn plain_ids_stay() {
+        for id in ["default", "0123456789abcdef", "a_B_9"] {
+            assert_eq!(encode_id(id), id);
+        }
+    }
+
+    #[test]
+    fn ids_round_trip() {
+        for id in ["x _\nhuiwen->canvas = = _ _", "50%", "%zz", "层", ""] {
+            let encoded = encode_id(id);
+            assert!(encoded
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'%'));
+            assert_eq!(decode_id(&encoded), id);
+        }
     }
-    script = format!(
-        r#"{script}
-huiwen->canvas->edge += = $->$edge _"#
-    );
-    execute(&ScriptTree {
-        script,
-        name: format!(""),
-        next_v: vec![],
-    })
-    .await?;
-    Ok(())
-}
-
-pub async fn pull_edge_v() -> err::Result<Vec<Vec<Point>>> {
-    let r_tree = execute(&ScriptTree {
-        script: format!("$->$output = = huiwen->canvas->edge _"),
-        name: format!("edge"),
-        next_v: vec![
-            ScriptTree {
-                script: format!("$->$output = = $->$input->point->width _"),
-                name: format!("width"),
-                next_v: vec![],
-            },
-            ScriptTree {
-                script: format!("$->$output = = $->$input->point->color _"),
-                name: format!("color"),
-                next_v: vec![],
-            },
-            ScriptTree {
-                script: format!("$->$output = = $->$input->point->pos _"),
-                name: format!("pos"),
-                next_v: vec![],
-            },
-        ],
-    })
-    .await?;
-
-    let mut edge_v = Vec::new();
-    let width_h_v2 = &r_tree["edge"]["width"];
-    let color_h_v2 = &r_tree["edge"]["color"];
-    let pos_h_v2 = &r_tree["edge"]["pos"];
-    for i in 0..width_h_v2.len() {
-        let mut edge = Vec::new();
-        let width_h_v = &width_h_v2[i];
-        let color_h_v = &color_h_v2[i];
-        let pos_h_v = &pos_h_v2[i];
-        for j in 0..width_h_v.len() {
-            let width_h = width_h_v[j].as_str().unwrap();
-            let color_h = color_h_v[j].as_str().unwrap();
-            let pos_h = pos_h_v[j].as_str().unwrap();
-            edge.push(Point {
-                pos: str_to_p3(pos_h),
-                color: str_to_c4(color_h),
-                width: width_h.parse().unwrap(),
-            });
+
+    #[test]
+    fn legacy_points_move_to_world_pixels() {
+        let (w, h) = (LEGACY_CLIENT.width as f32, LEGACY_CLIENT.height as f32);
+        let pen = Pen::default();
+        for (x, y) in [(0.0, 0.0), (w, h), (w / 2.0, h / 2.0), (480.0, 810.0)] {
+            let mut pt = pen.px2point(x, y, 1.0, LEGACY_CLIENT);
+            pt.width = 0.02;
+            let world = legacy_to_world(&pt);
+            assert!((world.pos.x - (x - w / 2.0)).abs() < 1e-3);
+            assert!((world.pos.y - (y - h / 2.0)).abs() < 1e-3);
+            assert!((world.width - 0.01 * h).abs() < 1e-3);
+            assert_eq!((world.pos.z, world.color), (pt.pos.z, pt.color));
         }
-        edge_v.push(edge);
     }
-    Ok(edge_v)
-}
-
-pub async fn clear() -> err::Result<()> {
-    execute(&ScriptTree {
-        script: [
-            "huiwen->canvas->edge->point->width = = _ _",
-            "huiwen->canvas->edge->point->color = = _ _",
-            "huiwen->canvas->edge->point->pos = = _ _",
-            "huiwen->canvas->edge->point = = _ _",
-            "huiwen->canvas->edge = = _ _",
-        ]
-        .join("\n"),
-        name: format!(""),
-        next_v: vec![],
-    })
-    .await?;
-    Ok(())
 }
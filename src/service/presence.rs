@@ -0,0 +1,408 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::element::Ink;
+
+#[cfg(target_arch = "wasm32")]
+use std::cell::OnceCell;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+#[cfg(target_arch = "wasm32")]
+use web_sys::{BroadcastChannel, MessageEvent};
+
+#[cfg(target_arch = "wasm32")]
+use crate::{err, util};
+
+#[cfg(target_arch = "wasm32")]
+/// Channels are named this, then the id of the board.
+const CHANNEL_PREFIX: &str = "huiwen-presence:";
+#[cfg(target_arch = "wasm32")]
+/// Stands for the default board in channel names; not a valid board id.
+const DEFAULT_BOARD: &str = "-";
+/// Milliseconds without doing anything after which a user is hidden.
+const IDLE_MS: f64 = 60_000.0;
+
+/// What goes over the link.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+enum Note {
+    /// A user just joined and wants to know who else is here.
+    Hello(Peer),
+    Here(Peer),
+    Bye {
+        id: String,
+    },
+    /// Laser or ephemeral ink of a user, live as it is drawn.
+    Ink {
+        id: String,
+        ink: Ink,
+    },
+    /// A user's ephemeral ink was wiped.
+    ClearInk {
+        id: String,
+    },
+}
+
+fn post(link: &dyn PresenceLink, note: &Note) {
+    match serde_json::to_string(note) {
+        Ok(s) => link.post(&s),
+        Err(e) => log::error!("failed to encode a presence message: {e}"),
+    }
+}
+
+// Public
+/// One user on the board, as the others see them.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Peer {
+    pub id: String,
+    pub name: String,
+    /// A CSS color.
+    pub color: String,
+    /// Name of the tool in hand.
+    pub tool: String,
+    /// Pointer over the board, in world coordinates, once it moved there.
+    pub pos: Option<(f32, f32)>,
+    /// When they last did something, in milliseconds since the epoch.
+    pub active: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PeerEvent {
+    Seen(Peer),
+    Gone(String),
+    /// Ink of the user with this id.
+    Ink(String, Ink),
+    ClearInk(String),
+}
+
+/// Carries presence notes between the users of one board.
+pub trait PresenceLink {
+    /// Send `note` to every other user on the board.
+    fn post(&self, note: &str);
+
+    /// Call `on_note` with every note the others send, from now on.
+    fn listen(&self, on_note: Box<dyn Fn(&str)>);
+}
+
+#[cfg(target_arch = "wasm32")]
+/// A `BroadcastChannel` per board, on which every tab of this browser is
+/// another user. The server has no presence of its own yet.
+pub struct BroadcastLink {
+    channel: BroadcastChannel,
+    on_message: OnceCell<Closure<dyn FnMut(MessageEvent)>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl BroadcastLink {
+    /// Meet the other tabs on `board`, None for the default one.
+    pub fn open(board: Option<&str>) -> err::Result<Self> {
+        let name = format!("{CHANNEL_PREFIX}{}", board.unwrap_or(DEFAULT_BOARD));
+        let channel = BroadcastChannel::new(&name).map_err(util::map_js_error)?;
+        Ok(Self {
+            channel,
+            on_message: OnceCell::new(),
+        })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl PresenceLink for BroadcastLink {
+    fn post(&self, note: &str) {
+        let _ = self.channel.post_message(&JsValue::from_str(note));
+    }
+
+    fn listen(&self, on_note: Box<dyn Fn(&str)>) {
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |e: MessageEvent| {
+            match e.data().as_string() {
+                Some(s) => on_note(&s),
+                None => log::warn!("unexpected presence message: {:?}", e.data()),
+            }
+        });
+        self.channel
+            .set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        // Listened to once; a second listener would replace the first.
+        let _ = self.on_message.set(on_message);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for BroadcastLink {
+    fn drop(&mut self) {
+        self.channel.set_onmessage(None);
+        self.channel.close();
+    }
+}
+
+/// Where the others on the board are and what they hold, over any link.
+pub struct Presence<L: PresenceLink> {
+    link: Rc<L>,
+    /// What is told to users who join later.
+    me: Rc<RefCell<Peer>>,
+}
+
+impl<L: PresenceLink + 'static> Presence<L> {
+    /// Join over `link` as `me`, calling `on_peer` whenever another user is
+    /// seen, leaves or inks.
+    pub fn join(link: L, me: Peer, on_peer: impl Fn(PeerEvent) + 'static) -> Self {
+        let link = Rc::new(link);
+        let me = Rc::new(RefCell::new(me));
+
+        let answer = Rc::downgrade(&link);
+        let me_c = me.clone();
+        link.listen(Box::new(move |s| {
+            let note = match serde_json::from_str::<Note>(s) {
+                Ok(note) => note,
+                Err(e) => {
+                    log::warn!("unexpected presence message: {e}");
+                    return;
+                }
+            };
+            match note {
+                Note::Hello(peer) => {
+                    if let Some(link) = answer.upgrade() {
+                        let here = me_c.borrow().clone();
+                        post(link.as_ref(), &Note::Here(here));
+                    }
+                    on_peer(PeerEvent::Seen(peer));
+                }
+                Note::Here(peer) => on_peer(PeerEvent::Seen(peer)),
+                Note::Bye { id } => on_peer(PeerEvent::Gone(id)),
+                Note::Ink { id, ink } => on_peer(PeerEvent::Ink(id, ink)),
+                Note::ClearInk { id } => on_peer(PeerEvent::ClearInk(id)),
+            }
+        }));
+        let hello = me.borrow().clone();
+        post(link.as_ref(), &Note::Hello(hello));
+
+        Self { link, me }
+    }
+}
+
+impl<L: PresenceLink> Presence<L> {
+    /// Tell the others about `me`, as it is now.
+    pub fn publish(&self, me: &Peer) {
+        *self.me.borrow_mut() = me.clone();
+        post(self.link.as_ref(), &Note::Here(me.clone()));
+    }
+
+    /// Show `ink` to the others, as it is drawn and once finished.
+    pub fn send_ink(&self, ink: &Ink) {
+        let id = self.me.borrow().id.clone();
+        post(
+            self.link.as_ref(),
+            &Note::Ink {
+                id,
+                ink: ink.clone(),
+            },
+        );
+    }
+
+    /// Wipe the ephemeral ink the others were shown.
+    pub fn clear_ink(&self) {
+        let id = self.me.borrow().id.clone();
+        post(self.link.as_ref(), &Note::ClearInk { id });
+    }
+}
+
+impl<L: PresenceLink> Drop for Presence<L> {
+    fn drop(&mut self) {
+        let id = self.me.borrow().id.clone();
+        post(self.link.as_ref(), &Note::Bye { id });
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+/// Presence among the tabs of this browser.
+pub type PresenceChannel = Presence<BroadcastLink>;
+
+#[cfg(target_arch = "wasm32")]
+/// Join the others on `board`, the default board for None, as `me`.
+pub fn join_presence(
+    board: Option<&str>,
+    me: Peer,
+    on_peer: impl Fn(PeerEvent) + 'static,
+) -> err::Result<PresenceChannel> {
+    let link = BroadcastLink::open(board)?;
+    Ok(Presence::join(link, me, on_peer))
+}
+
+/// Ids of the users of `peer_m` who did something within `IDLE_MS` of
+/// `now`, by name.
+pub fn shown_id_v(peer_m: &HashMap<String, Peer>, now: f64) -> Vec<String> {
+    let mut peer_v: Vec<&Peer> = peer_m
+        .values()
+        .filter(|peer| now - peer.active < IDLE_MS)
+        .collect();
+    peer_v.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    peer_v.into_iter().map(|peer| peer.id.clone()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    type Listener = Rc<dyn Fn(&str)>;
+
+    /// The listeners of a `LocalHub`, and the notes on their way.
+    #[derive(Default)]
+    struct Hub {
+        /// Board and listener of every link that joined, by position.
+        member_v: Vec<(String, Option<Listener>)>,
+        /// Who sent what, oldest first.
+        queue: VecDeque<(usize, String)>,
+        delivering: bool,
+    }
+
+    /// Links kept in memory, handing every note to the others on the same
+    /// board in the order sent, as a server would.
+    #[derive(Clone, Default)]
+    struct LocalHub(Rc<RefCell<Hub>>);
+
+    impl LocalHub {
+        fn join(&self, board: &str) -> LocalLink {
+            let mut hub = self.0.borrow_mut();
+            hub.member_v.push((board.to_string(), None));
+            LocalLink {
+                hub: self.clone(),
+                member: hub.member_v.len() - 1,
+            }
+        }
+
+        fn post(&self, from: usize, note: &str) {
+            {
+                let mut hub = self.0.borrow_mut();
+                hub.queue.push_back((from, note.to_string()));
+                // Notes sent while one is handed over go after it.
+                if hub.delivering {
+                    return;
+                }
+                hub.delivering = true;
+            }
+            loop {
+                let (listener_v, note) = {
+                    let mut hub = self.0.borrow_mut();
+                    let Some((from, note)) = hub.queue.pop_front() else {
+                        hub.delivering = false;
+                        return;
+                    };
+                    let board = &hub.member_v[from].0;
+                    let listener_v: Vec<Listener> = hub
+                        .member_v
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, (other, _))| *i != from && other == board)
+                        .filter_map(|(_, (_, listener))| listener.clone())
+                        .collect();
+                    (listener_v, note)
+                };
+                for listener in listener_v {
+                    listener(&note);
+                }
+            }
+        }
+    }
+
+    /// One user's way into a `LocalHub`.
+    struct LocalLink {
+        hub: LocalHub,
+        member: usize,
+    }
+
+    impl PresenceLink for LocalLink {
+        fn post(&self, note: &str) {
+            self.hub.post(self.member, note);
+        }
+
+        fn listen(&self, on_note: Box<dyn Fn(&str)>) {
+            self.hub.0.borrow_mut().member_v[self.member].1 = Some(Rc::from(on_note));
+        }
+    }
+
+    impl Drop for LocalLink {
+        fn drop(&mut self) {
+            self.hub.0.borrow_mut().member_v[self.member].1 = None;
+        }
+    }
+
+    type EventV = Rc<RefCell<Vec<PeerEvent>>>;
+
+    fn peer(id: &str, active: f64) -> Peer {
+        Peer {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            active,
+            ..Peer::default()
+        }
+    }
+
+    fn join(hub: &LocalHub, board: &str, me: Peer) -> (Presence<LocalLink>, EventV) {
+        let event_v: EventV = Rc::default();
+        let event_v_c = event_v.clone();
+        let presence = Presence::join(hub.join(board), me, move |e| event_v_c.borrow_mut().push(e));
+        (presence, event_v)
+    }
+
+    #[test]
+    fn joining_meets_those_here() {
+        let hub = LocalHub::default();
+        let (_a, a_event_v) = join(&hub, "b1", peer("a", 0.0));
+        assert!(a_event_v.borrow().is_empty());
+        let (_b, b_event_v) = join(&hub, "b1", peer("b", 0.0));
+        assert_eq!(*a_event_v.borrow(), [PeerEvent::Seen(peer("b", 0.0))]);
+        assert_eq!(*b_event_v.borrow(), [PeerEvent::Seen(peer("a", 0.0))]);
+    }
+
+    #[test]
+    fn moves_reach_the_others() {
+        let hub = LocalHub::default();
+        let (_a, a_event_v) = join(&hub, "b1", peer("a", 0.0));
+        let (b, _) = join(&hub, "b1", peer("b", 0.0));
+        a_event_v.borrow_mut().clear();
+        let moved = Peer {
+            pos: Some((10.0, -20.0)),
+            active: 5.0,
+            ..peer("b", 0.0)
+        };
+        b.publish(&moved);
+        assert_eq!(*a_event_v.borrow(), [PeerEvent::Seen(moved.clone())]);
+        // Those joining later hear of the move.
+        let (_c, c_event_v) = join(&hub, "b1", peer("c", 0.0));
+        assert!(c_event_v.borrow().contains(&PeerEvent::Seen(moved)));
+    }
+
+    #[test]
+    fn leaving_says_bye() {
+        let hub = LocalHub::default();
+        let (_a, a_event_v) = join(&hub, "b1", peer("a", 0.0));
+        let (b, _) = join(&hub, "b1", peer("b", 0.0));
+        a_event_v.borrow_mut().clear();
+        drop(b);
+        assert_eq!(*a_event_v.borrow(), [PeerEvent::Gone("b".to_string())]);
+    }
+
+    #[test]
+    fn boards_keep_apart() {
+        let hub = LocalHub::default();
+        let (_a, a_event_v) = join(&hub, "b1", peer("a", 0.0));
+        let (b, b_event_v) = join(&hub, "b2", peer("b", 0.0));
+        b.publish(&peer("b", 1.0));
+        drop(b);
+        assert!(a_event_v.borrow().is_empty());
+        assert!(b_event_v.borrow().is_empty());
+    }
+
+    #[test]
+    fn idle_users_are_hidden() {
+        let peer_m: HashMap<String, Peer> = [peer("b", 1_000.0), peer("a", 50_000.0)]
+            .into_iter()
+            .map(|peer| (peer.id.clone(), peer))
+            .collect();
+        assert_eq!(shown_id_v(&peer_m, 55_000.0), ["a", "b"]);
+        assert_eq!(shown_id_v(&peer_m, 61_000.0), ["a"]);
+        assert!(shown_id_v(&peer_m, 110_000.0).is_empty());
+    }
+}
@@ -0,0 +1,534 @@
+use painting::point::Point;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{
+    element::{Edge, Layer, PageFrame, Paper, PaperKind, DEFAULT_LAYER_ID},
+    err, metrics,
+    util::{self, Request},
+};
+
+use super::*;
+
+/// Where the default board lives.
+const DEFAULT_ROOT: &str = "huiwen->canvas";
+
+/// Where `board` lives, the default board for None.
+fn root(board: Option<&str>) -> String {
+    match board.filter(|board| is_board_id(board)) {
+        Some(board) => format!("huiwen->board_{board}"),
+        None => DEFAULT_ROOT.to_string(),
+    }
+}
+
+async fn execute(script_tree: &ScriptTree) -> err::Result<json::JsonValue> {
+    let rs = execute_text(script_tree).await?;
+    json::parse(&rs).map_err(|_| err::Error::Other(rs))
+}
+
+/// The answer to `script_tree`, before it is parsed.
+async fn execute_text(script_tree: &ScriptTree) -> err::Result<String> {
+    let res = Request::new("/service/edge/execute1")
+        .with_body_str(&serde_json::to_string(script_tree).unwrap())?
+        .send("POST")
+        .await?;
+    let rs = JsFuture::from(res.text().map_err(util::map_js_error)?)
+        .await
+        .map_err(util::map_js_error)?
+        .as_string()
+        .ok_or(err::Error::Other(
+            "when execute:\n\treturned none".to_string(),
+        ))?;
+    match res.status() {
+        401 => {
+            return Err(err::Error::NotLogin(format!("not login")));
+        }
+        500 => {
+            log::warn!("when execute:\n\t{rs}");
+            return Err(err::Error::Other(rs));
+        }
+        _ => (),
+    }
+    Ok(rs)
+}
+
+/// Paths under the root of a board that hold its edges, deepest first.
+const EDGE_PATH_V: [&str; 8] = [
+    "edge->point->stylus",
+    "edge->point->width",
+    "edge->point->color",
+    "edge->point->pos",
+    "edge->point",
+    "edge->id",
+    "edge->layer",
+    "edge",
+];
+
+/// Statements emptying every path of `path_v` under `root`.
+fn clear_script(root: &str, path_v: &[&str]) -> String {
+    path_v
+        .iter()
+        .map(|path| format!("{root}->{path} = = _ _"))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Statements adding `edge` to the board at `root`.
+fn edge_script(root: &str, edge: &Edge) -> String {
+    let mut script = format!(
+        r#"$->$edge = = ? _
+$->$edge->id = = {} _
+$->$edge->layer = = {} _"#,
+        encode_id(&edge.id),
+        encode_id(&edge.layer_id)
+    );
+
+    for (pt, stylus) in edge.point_v.iter().zip(&edge.stylus_v) {
+        script = format!(
+            r#"{script}
+$->$point = = ? _
+$->$point->pos = = {} _
+$->$point->color = = {} _
+$->$point->width = = {} _
+$->$point->stylus = = {} _
+$->$edge->point += = $->$point _"#,
+            p3_to_str(&pt.pos),
+            c4_to_str(&pt.color),
+            pt.width,
+            stylus_to_str(stylus)
+        );
+    }
+    format!(
+        r#"{script}
+{root}->edge += = $->$edge _"#
+    )
+}
+
+/// Write `edge_v`, read from `board` without a coordinate version and now in
+/// world pixels, back over its edges, so that they are converted only once.
+async fn upgrade_coord(board: Option<&str>, edge_v: &[Edge]) -> err::Result<()> {
+    let root = root(board);
+    let mut script = clear_script(&root, &EDGE_PATH_V);
+    for edge in edge_v {
+        script = format!("{script}\n{}", edge_script(&root, edge));
+    }
+    script = format!("{script}\n{root}->coord = = {COORD_VERSION} _");
+    execute(&ScriptTree {
+        script,
+        name: format!(""),
+        next_v: vec![],
+    })
+    .await?;
+    Ok(())
+}
+
+// Public
+pub async fn get_version() -> err::Result<String> {
+    let rs = execute(&ScriptTree {
+        script: "$->$output = = huiwen->version _".to_string(),
+        name: format!("version"),
+        next_v: vec![],
+    })
+    .await?;
+    Ok(rs["version"][0].as_str().unwrap().to_string())
+}
+
+/// Add `edge` to `board`, the default board for None.
+pub async fn commit_edge(board: Option<&str>, edge: Edge) -> err::Result<()> {
+    execute(&ScriptTree {
+        script: edge_script(&root(board), &edge),
+        name: format!(""),
+        next_v: vec![],
+    })
+    .await?;
+    Ok(())
+}
+
+/// Hide an edge of `board` from every later pull.
+pub async fn erase_edge(board: Option<&str>, id: &str) -> err::Result<()> {
+    execute(&ScriptTree {
+        script: format!("{}->erased += = {} _", root(board), encode_id(id)),
+        name: format!(""),
+        next_v: vec![],
+    })
+    .await?;
+    Ok(())
+}
+
+/// Edges of `board` not erased, in drawing order.
+pub async fn pull_edge_v(board: Option<&str>) -> err::Result<Vec<Edge>> {
+    let root = root(board);
+    let start = metrics::now();
+    let r_coord = execute(&ScriptTree {
+        script: format!("$->$output = = {root}->coord _"),
+        name: format!("coord"),
+        next_v: vec![],
+    })
+    .await?;
+    let coord = r_coord["coord"][0]
+        .as_str()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0);
+    let r_erased = execute(&ScriptTree {
+        script: format!("$->$output = = {root}->erased _"),
+        name: format!("erased"),
+        next_v: vec![],
+    })
+    .await?;
+    let erased_v: Vec<String> = r_erased["erased"]
+        .members()
+        .filter_map(|id| id.as_str())
+        .map(decode_id)
+        .collect();
+
+    let rs = execute_text(&ScriptTree {
+        script: format!("$->$output = = {root}->edge _"),
+        name: format!("edge"),
+        next_v: vec![
+            ScriptTree {
+                script: format!("$->$output = = $->$input->id _"),
+                name: format!("id"),
+                next_v: vec![],
+            },
+            ScriptTree {
+                script: format!("$->$output = = $->$input->layer _"),
+                name: format!("layer"),
+                next_v: vec![],
+            },
+            ScriptTree {
+                script: format!("$->$output = = $->$input->point->stylus _"),
+                name: format!("stylus"),
+                next_v: vec![],
+            },
+            ScriptTree {
+                script: format!("$->$output = = $->$input->point->width _"),
+                name: format!("width"),
+                next_v: vec![],
+            },
+            ScriptTree {
+                script: format!("$->$output = = $->$input->point->color _"),
+                name: format!("color"),
+                next_v: vec![],
+            },
+            ScriptTree {
+                script: format!("$->$output = = $->$input->point->pos _"),
+                name: format!("pos"),
+                next_v: vec![],
+            },
+        ],
+    })
+    .await?;
+
+    let decode_start = metrics::now();
+    let r_tree = json::parse(&rs).map_err(|_| err::Error::Other(rs))?;
+    let mut edge_v = Vec::new();
+    let width_h_v2 = &r_tree["edge"]["width"];
+    let color_h_v2 = &r_tree["edge"]["color"];
+    let pos_h_v2 = &r_tree["edge"]["pos"];
+    let stylus_h_v2 = &r_tree["edge"]["stylus"];
+    let id_h_v = &r_tree["edge"]["id"];
+    let layer_h_v = &r_tree["edge"]["layer"];
+    let mut legacy_id_v: Vec<String> = Vec::new();
+    for i in 0..width_h_v2.len() {
+        let id = match id_h_v[i][0].as_str() {
+            Some(id) => decode_id(id),
+            None => {
+                let pos_h_v = pos_h_v2[i].members().filter_map(|pos_h| pos_h.as_str());
+                let id = legacy_id(pos_h_v.clone(), 0);
+                let seen_n = legacy_id_v.iter().filter(|seen| **seen == id).count();
+                legacy_id_v.push(id);
+                legacy_id(pos_h_v, seen_n)
+            }
+        };
+        if erased_v.contains(&id) {
+            continue;
+        }
+        let layer_id = match layer_h_v[i][0].as_str() {
+            Some(layer_id) => decode_id(layer_id),
+            None => DEFAULT_LAYER_ID.to_string(),
+        };
+        let mut edge = Edge {
+            id,
+            layer_id,
+            point_v: Vec::new(),
+            stylus_v: Vec::new(),
+        };
+        let width_h_v = &width_h_v2[i];
+        let color_h_v = &color_h_v2[i];
+        let pos_h_v = &pos_h_v2[i];
+        let stylus_h_v = &stylus_h_v2[i];
+        for j in 0..width_h_v.len() {
+            let width_h = width_h_v[j].as_str().unwrap();
+            let color_h = color_h_v[j].as_str().unwrap();
+            let pos_h = pos_h_v[j].as_str().unwrap();
+            let stylus = match stylus_h_v[j].as_str() {
+                Some(stylus_h) => str_to_stylus(stylus_h),
+                None => Stylus::default(),
+            };
+            edge.push(
+                Point {
+                    pos: str_to_p3(pos_h),
+                    color: str_to_c4(color_h),
+                    width: width_h.parse().unwrap(),
+                },
+                stylus,
+            );
+        }
+        edge_v.push(edge);
+    }
+    if coord < COORD_VERSION {
+        for edge in &mut edge_v {
+            edge.point_v = edge.point_v.iter().map(legacy_to_world).collect();
+        }
+        if let Err(e) = upgrade_coord(board, &edge_v).await {
+            log::warn!("when upgrade coordinates:\n\t{e}");
+        }
+    }
+    let end = metrics::now();
+    let (pull_ms, decode_ms) = (end - start, end - decode_start);
+    metrics::update(|m| {
+        m.pull_ms = pull_ms as f32;
+        m.decode_ms = decode_ms as f32;
+    });
+    Ok(edge_v)
+}
+
+/// Layers of `board` bottom first, empty if it has none yet.
+pub async fn pull_layer_v(board: Option<&str>) -> err::Result<Vec<Layer>> {
+    let root = root(board);
+    let field_v = ["id", "name", "visible", "locked", "opacity"];
+    let r_tree = execute(&ScriptTree {
+        script: format!("$->$output = = {root}->layer _"),
+        name: format!("layer"),
+        next_v: field_v
+            .iter()
+            .map(|field| ScriptTree {
+                script: format!("$->$output = = $->$input->{field} _"),
+                name: field.to_string(),
+                next_v: vec![],
+            })
+            .collect(),
+    })
+    .await?;
+
+    let r_layer = &r_tree["layer"];
+    let mut layer_v = Vec::new();
+    for i in 0..r_layer["id"].len() {
+        let id = match r_layer["id"][i][0].as_str() {
+            Some(id) => decode_id(id),
+            None => continue,
+        };
+        let flag = |field: &str| r_layer[field][i][0].as_str() == Some("1");
+        layer_v.push(Layer {
+            id,
+            name: decode_name(r_layer["name"][i][0].as_str().unwrap_or_default()),
+            visible: flag("visible"),
+            locked: flag("locked"),
+            opacity: r_layer["opacity"][i][0]
+                .as_str()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1.0),
+        });
+    }
+    Ok(layer_v)
+}
+
+/// Replace the layers of `board` with `layer_v`, bottom first.
+pub async fn save_layer_v(board: Option<&str>, layer_v: Vec<Layer>) -> err::Result<()> {
+    let root = root(board);
+    let mut script = clear_script(
+        &root,
+        &[
+            "layer->id",
+            "layer->name",
+            "layer->visible",
+            "layer->locked",
+            "layer->opacity",
+            "layer",
+        ],
+    );
+    for layer in &layer_v {
+        script = format!(
+            r#"{script}
+$->$layer = = ? _
+$->$layer->id = = {} _
+$->$layer->name = = {} _
+$->$layer->visible = = {} _
+$->$layer->locked = = {} _
+$->$layer->opacity = = {} _
+{root}->layer += = $->$layer _"#,
+            encode_id(&layer.id),
+            encode_name(&layer.name),
+            layer.visible as u8,
+            layer.locked as u8,
+            layer.opacity
+        );
+    }
+    execute(&ScriptTree {
+        script,
+        name: format!(""),
+        next_v: vec![],
+    })
+    .await?;
+    Ok(())
+}
+
+/// Layers and edges of board `board`.
+pub async fn pull_board(board: &str) -> err::Result<(Vec<Layer>, Vec<Edge>)> {
+    if !is_board_id(board) {
+        return Err(err::Error::Other(format!("{board:?} is not a board")));
+    }
+    let layer_v = pull_layer_v(Some(board)).await?;
+    let edge_v = pull_edge_v(Some(board)).await?;
+    Ok((layer_v, edge_v))
+}
+
+/// Page frames of `board`, in the order they were added.
+pub async fn pull_page_frame_v(board: Option<&str>) -> err::Result<Vec<PageFrame>> {
+    let root = root(board);
+    let field_v = ["id", "name", "rect"];
+    let r_tree = execute(&ScriptTree {
+        script: format!("$->$output = = {root}->frame _"),
+        name: format!("frame"),
+        next_v: field_v
+            .iter()
+            .map(|field| ScriptTree {
+                script: format!("$->$output = = $->$input->{field} _"),
+                name: field.to_string(),
+                next_v: vec![],
+            })
+            .collect(),
+    })
+    .await?;
+
+    let r_frame = &r_tree["frame"];
+    let mut frame_v = Vec::new();
+    for i in 0..r_frame["id"].len() {
+        let (Some(id), Some(rect)) = (
+            r_frame["id"][i][0].as_str(),
+            r_frame["rect"][i][0].as_str().and_then(str_to_rect),
+        ) else {
+            continue;
+        };
+        frame_v.push(PageFrame {
+            id: decode_id(id),
+            name: decode_name(r_frame["name"][i][0].as_str().unwrap_or_default()),
+            rect,
+        });
+    }
+    Ok(frame_v)
+}
+
+/// Replace the page frames of `board` with `frame_v`.
+pub async fn save_page_frame_v(board: Option<&str>, frame_v: Vec<PageFrame>) -> err::Result<()> {
+    let root = root(board);
+    let mut script = clear_script(&root, &["frame->id", "frame->name", "frame->rect", "frame"]);
+    for frame in &frame_v {
+        script = format!(
+            r#"{script}
+$->$frame = = ? _
+$->$frame->id = = {} _
+$->$frame->name = = {} _
+$->$frame->rect = = {} _
+{root}->frame += = $->$frame _"#,
+            encode_id(&frame.id),
+            encode_name(&frame.name),
+            rect_to_str(&frame.rect)
+        );
+    }
+    execute(&ScriptTree {
+        script,
+        name: format!(""),
+        next_v: vec![],
+    })
+    .await?;
+    Ok(())
+}
+
+/// The paper of `board`, None if it was never set.
+pub async fn pull_paper(board: Option<&str>) -> err::Result<Option<Paper>> {
+    let root = root(board);
+    let field_v = ["kind", "color", "line_color", "spacing"];
+    let r_tree = execute(&ScriptTree {
+        script: format!("$->$output = = {root}->paper _"),
+        name: format!("paper"),
+        next_v: field_v
+            .iter()
+            .map(|field| ScriptTree {
+                script: format!("$->$output = = $->$input->{field} _"),
+                name: field.to_string(),
+                next_v: vec![],
+            })
+            .collect(),
+    })
+    .await?;
+
+    let r_paper = &r_tree["paper"];
+    let kind = match r_paper["kind"][0][0]
+        .as_str()
+        .and_then(PaperKind::from_name)
+    {
+        Some(kind) => kind,
+        None => return Ok(None),
+    };
+    let default = Paper::default();
+    Ok(Some(Paper {
+        kind,
+        color: r_paper["color"][0][0]
+            .as_str()
+            .map_or(default.color, str_to_c4),
+        line_color: r_paper["line_color"][0][0]
+            .as_str()
+            .map_or(default.line_color, str_to_c4),
+        spacing: r_paper["spacing"][0][0]
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(default.spacing),
+    }))
+}
+
+pub async fn save_paper(board: Option<&str>, paper: Paper) -> err::Result<()> {
+    let root = root(board);
+    let clear = clear_script(
+        &root,
+        &[
+            "paper->kind",
+            "paper->color",
+            "paper->line_color",
+            "paper->spacing",
+        ],
+    );
+    execute(&ScriptTree {
+        script: format!(
+            r#"{clear}
+$->$paper = = ? _
+$->$paper->kind = = {} _
+$->$paper->color = = {} _
+$->$paper->line_color = = {} _
+$->$paper->spacing = = {} _
+{root}->paper = = $->$paper _"#,
+            paper.kind.name(),
+            c4_to_str(&paper.color),
+            c4_to_str(&paper.line_color),
+            paper.spacing
+        ),
+        name: format!(""),
+        next_v: vec![],
+    })
+    .await?;
+    Ok(())
+}
+
+/// Drop every edge of `board`, erased ones included.
+pub async fn clear(board: Option<&str>) -> err::Result<()> {
+    let root = root(board);
+    execute(&ScriptTree {
+        script: format!(
+            "{}\n{root}->erased = = _ _",
+            clear_script(&root, &EDGE_PATH_V)
+        ),
+        name: format!(""),
+        next_v: vec![],
+    })
+    .await?;
+    Ok(())
+}
@@ -2,7 +2,9 @@ use std::collections::BTreeMap;
 
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{Document, Location, RequestInit, RequestMode, Response};
+use web_sys::{
+    Blob, BlobPropertyBag, Document, HtmlElement, Location, RequestInit, RequestMode, Response, Url,
+};
 
 use crate::err;
 
@@ -91,3 +93,41 @@ impl Request {
             .map_err(map_js_error)
     }
 }
+
+/// Let the browser save what `url` points at as `file_name`.
+pub fn download(url: &str, file_name: &str) -> err::Result<()> {
+    let document = get_document().ok_or(err::Error::Other("failed to get document".to_string()))?;
+    let a = document
+        .create_element("a")
+        .map_err(map_js_error)?
+        .dyn_into::<HtmlElement>()
+        .map_err(|_| err::Error::Other("failed to create link".to_string()))?;
+    a.set_attribute("href", url).map_err(map_js_error)?;
+    a.set_attribute("download", file_name)
+        .map_err(map_js_error)?;
+    a.click();
+    Ok(())
+}
+
+/// Let the browser save `data` as a `mime` file called `file_name`.
+pub fn download_data(data: &[u8], mime: &str, file_name: &str) -> err::Result<()> {
+    let part_v = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let blob =
+        Blob::new_with_u8_array_sequence_and_options(&part_v, BlobPropertyBag::new().type_(mime))
+            .map_err(map_js_error)?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(map_js_error)?;
+    let rs = download(&url, file_name);
+    let _ = Url::revoke_object_url(&url);
+    rs
+}
+
+/// The contents of `blob` as text.
+pub async fn read_text(blob: &Blob) -> err::Result<String> {
+    JsFuture::from(blob.text())
+        .await
+        .map_err(map_js_error)?
+        .as_string()
+        .ok_or(err::Error::Other(
+            "when read text:\n\tnot a string".to_string(),
+        ))
+}